@@ -0,0 +1,67 @@
+// cargo bench --bench elementwise
+// compare les ops élément par élément (moteur strided, multithreadé) à l'ancienne boucle: get_from_lin sur chaque élément.
+// LAMP_NUM_THREADS=1 pour mesurer le gain sans les threads.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lamp::tensor::{Tensor, Numel};
+use lamp::utils::parallel::num_threads;
+
+// l'ancien Add for &Tensor, gardé ici comme référence
+fn naive_add(a: &Tensor, b: &Tensor) -> Vec<f32>{
+    let shape = Tensor::broadcast_shape(&a.shape, &b.shape).unwrap();
+    let a_b = a.broadcast_view(&shape).unwrap();
+    let b_b = b.broadcast_view(&shape).unwrap();
+    (0..shape.numel()).map(|lin| a_b.get_from_lin(lin) + b_b.get_from_lin(lin)).collect()
+}
+
+fn rand_t(shape: &[usize]) -> Tensor{
+    let n = shape.iter().product();
+    Tensor::from_owned((0..n).map(|i| ((i * 7919 % 1000) as f32) / 500.0 - 1.0).collect(), shape).unwrap()
+}
+
+// temps moyen par appel, en répétant pendant au moins ~0.5s
+fn time(mut f: impl FnMut()) -> Duration{
+    f();
+    let mut iters = 0u32;
+    let start = Instant::now();
+    while iters == 0 || start.elapsed() < Duration::from_millis(500){
+        f();
+        iters += 1;
+    }
+    start.elapsed() / iters
+}
+
+fn main(){
+    println!("threads: {}", num_threads());
+    println!("{:<32} {:>12} {:>12} {:>9} {:>10}", "cas", "naif", "strided", "speedup", "Gelem/s");
+
+    // (nom, a, b): contigus, biais broadcasté, canaux d'un conv, transposée, scalaire
+    let cases: Vec<(&str, Tensor, Tensor)> = vec![
+        ("(100,200)+(100,200)", rand_t(&[100, 200]), rand_t(&[100, 200])),
+        ("(1024,1024)+(1024,1024)", rand_t(&[1024, 1024]), rand_t(&[1024, 1024])),
+        ("(1024,1024)+(1024) biais", rand_t(&[1024, 1024]), rand_t(&[1024])),
+        ("(64,16,32,32)+(16,1,1) canaux", rand_t(&[64, 16, 32, 32]), rand_t(&[16, 1, 1])),
+        ("(1024,1024)^T+(1024,1024)", rand_t(&[1024, 1024]).mat_transpose(), rand_t(&[1024, 1024])),
+        ("(1024,1024)+() scalaire", rand_t(&[1024, 1024]), rand_t(&[])),
+    ];
+
+    for (name, a, b) in &cases{
+        let t_fast = time(|| {black_box(black_box(a) + black_box(b));});
+        let t_naive = time(|| {black_box(naive_add(black_box(a), black_box(b)));});
+        let n = Tensor::broadcast_shape(&a.shape, &b.shape).unwrap().numel();
+        println!("{:<32} {:>12?} {:>12?} {:>8.1}x {:>10.2}", name, t_naive, t_fast,
+            t_naive.as_secs_f64() / t_fast.as_secs_f64(), n as f64 / t_fast.as_secs_f64() / 1e9);
+    }
+
+    let a = rand_t(&[1024, 1024]);
+    let t = a.mat_transpose();
+    println!();
+    println!("{:<32} {:>12} {:>12}", "apply / contiguous", "naif", "strided");
+    let t_naive = time(|| {black_box(black_box(&a).data.iter().map(|&x| x.tanh()).collect::<Vec<f32>>());});
+    let t_fast = time(|| {black_box(black_box(&a).apply(f32::tanh));});
+    println!("{:<32} {:>12?} {:>12?}", "tanh (1024,1024)", t_naive, t_fast);
+    let t_naive = time(|| {black_box((0..t.shape.numel()).map(|lin| t.get_from_lin(lin)).collect::<Vec<f32>>());});
+    let t_fast = time(|| {black_box(black_box(&t).contiguous());});
+    println!("{:<32} {:>12?} {:>12?}", "contiguous (1024,1024)^T", t_naive, t_fast);
+}
@@ -0,0 +1,72 @@
+// cargo bench --bench matmul
+// compare tensor_mul (gemm par blocs, multithreadé) à l'ancien noyau: triple boucle avec get2 à chaque multiplication.
+// LAMP_NUM_THREADS=1 pour mesurer le gain sans les threads.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use lamp::tensor::Tensor;
+use lamp::ops::tensor_mul;
+use lamp::utils::parallel::num_threads;
+
+// l'ancien matmul2d_vec, gardé ici comme référence
+fn naive(a: &Tensor, b: &Tensor) -> Vec<f32>{
+    let (m, p, n) = (a.shape[0], a.shape[1], b.shape[1]);
+    let mut c = Vec::with_capacity(m*n);
+    for i in 0..m{
+        for j in 0..n{
+            let mut sum = 0.0;
+            for k in 0..p{
+                sum += a.get2(i, k)*b.get2(k, j);
+            }
+            c.push(sum);
+        }
+    }
+    c
+}
+
+// entiers dans [-4, 4]: produits et sommes exacts en f32, le résultat ne dépend pas de l'ordre des additions
+fn rand_t(shape: &[usize]) -> Tensor{
+    let n = shape.iter().product();
+    Tensor::from_owned((0..n).map(|i| (i * 7919 % 9) as f32 - 4.0).collect(), shape).unwrap()
+}
+
+// temps moyen par appel, en répétant pendant au moins ~0.5s
+fn time(mut f: impl FnMut()) -> Duration{
+    f();
+    let mut iters = 0u32;
+    let start = Instant::now();
+    while iters == 0 || start.elapsed() < Duration::from_millis(500){
+        f();
+        iters += 1;
+    }
+    start.elapsed() / iters
+}
+
+fn main(){
+    println!("threads: {}", num_threads());
+    println!("{:<28} {:>12} {:>12} {:>9} {:>10}", "cas", "naif", "gemm", "speedup", "GFLOP/s");
+
+    // (nom, a, b): couches mnist (batch 100), carré, vjp (transposées), batch broadcasté
+    let cases: Vec<(&str, Tensor, Tensor)> = vec![
+        ("(100,784)@(784,200)", rand_t(&[100, 784]), rand_t(&[784, 200])),
+        ("(100,200)@(200,50)", rand_t(&[100, 200]), rand_t(&[200, 50])),
+        ("(512,512)@(512,512)", rand_t(&[512, 512]), rand_t(&[512, 512])),
+        ("(784,100)@(100,200) x^T g", rand_t(&[100, 784]).mat_transpose(), rand_t(&[100, 200])),
+        ("(100,200)@(200,784) g w^T", rand_t(&[100, 200]), rand_t(&[784, 200]).mat_transpose()),
+        ("(16,64,64)@(64,64) batch", rand_t(&[16, 64, 64]), rand_t(&[64, 64])),
+    ];
+
+    for (name, a, b) in &cases{
+        // le naif ne travaille qu'en 2d: on boucle sur le batch à la main
+        let mats: Vec<Tensor> = if a.shape.len() == 2 {vec![a.clone()]} else {(0..a.shape[0]).map(|i| a.vue2d(i*a.strides[0])).collect()};
+        let expected: Vec<f32> = mats.iter().flat_map(|m| naive(m, b)).collect();
+        assert!(tensor_mul(a, b).contiguous().data.as_slice() == expected.as_slice(), "{name}: gemm différent du naif");
+
+        let t_fast = time(|| {black_box(tensor_mul(black_box(a), black_box(b)));});
+        let t_naive = time(|| for m in &mats {black_box(naive(black_box(m), black_box(b)));});
+        let r = a.shape.len();
+        let flops = 2.0 * a.shape[..r-1].iter().product::<usize>() as f64 * a.shape[r-1] as f64 * b.shape[b.shape.len()-1] as f64;
+        println!("{:<28} {:>12?} {:>12?} {:>8.1}x {:>10.2}", name, t_naive, t_fast,
+            t_naive.as_secs_f64() / t_fast.as_secs_f64(), flops / t_fast.as_secs_f64() / 1e9);
+    }
+}
@@ -72,7 +72,7 @@ fn main() {
             });
 
         println!("loss: {}", loss.data[0]);
-        params = sgd.update(&params, &grads); 
+        sgd.update_(&mut params, &grads); 
         }
         println!("epoch {epoch} ok");
     }
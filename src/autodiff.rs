@@ -1,2 +1,10 @@
 pub mod value_and_grad;
-pub mod inference;
\ No newline at end of file
+pub mod inference;
+pub mod higher_order;
+pub mod jvp;
+pub mod jacobian;
+pub mod hessian;
+pub mod input_grad;
+pub mod gradcheck;
+
+pub use gradcheck::gradcheck;
@@ -0,0 +1,115 @@
+use std::fmt;
+
+use rand::Rng;
+
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId};
+use crate::utils::params::Pytree;
+use crate::utils::rng::SplitMix64;
+
+/*
+vérification numérique des gradients: backward_param_grads contre des différences finies centrées
+    (f(p + eps) - f(p - eps)) / 2 eps
+pour chaque scalaire de chaque param. c'est O(nombre de scalaires) forwards: à réserver aux petits modèles et aux tests.
+
+une sortie non scalaire est réduite en sum(w * out), avec des poids w aléatoires fixes
+(une simple somme ne verrait pas, par ex., une erreur dans la vjp de softmax, dont la somme est constante).
+les forwards perturbés se font sur des traces no_grad, et les sommes en f64.
+
+un élément est bon si son erreur absolue OU relative est <= tol: les grads proches de 0 se jugent à l'absolu,
+les grands au relatif. en f32, eps ~ 1e-3 et tol ~ 1e-2 sont raisonnables.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamCheck{
+    // indice du param, ou son chemin pour un ParamTree
+    pub name: String,
+    pub max_abs: f32,
+    pub max_rel: f32,
+    pub ok: bool,
+}
+
+// un ParamCheck par param, dans l'ordre de params.leaves()
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradCheck{
+    pub params: Vec<ParamCheck>,
+}
+
+impl GradCheck{
+    pub fn ok(&self) -> bool{
+        self.params.iter().all(|p| p.ok)
+    }
+}
+
+impl fmt::Display for GradCheck{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        for p in &self.params{
+            writeln!(f, "param {}: max abs {:.3e}, max rel {:.3e} {}", p.name, p.max_abs, p.max_rel, if p.ok {"ok"} else {"ECHEC"})?;
+        }
+        Ok(())
+    }
+}
+
+// <w, out> en f64
+fn weighted_sum(out: &Tensor, w: &Tensor) -> f64{
+    out.contiguous().data.iter().zip(w.data.iter()).map(|(&o, &w)| o as f64 * w as f64).sum()
+}
+
+pub fn gradcheck<P: Pytree + ?Sized>(
+    params: &P,
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId,
+    eps: f32,
+    tol: f32,
+) -> GradCheck {
+    let leaves: Vec<Tensor> = params.leaves().iter().map(|p| p.contiguous()).collect();
+    let names = params.leaf_names();
+
+    // gradients analytiques
+    let mut tr = Trace::new();
+    let pids: Vec<NodeId> = leaves.iter().map(|p| tr.param(p.clone())).collect();
+    let out_id = build(&mut tr, &pids);
+    let out_shape = tr.get_tensor(out_id).shape.clone();
+    let w = if out_shape.is_empty(){
+        Tensor::ones(&[])
+    }else{
+        let mut rng = SplitMix64::seed(0);
+        let n = out_shape.iter().product();
+        Tensor::from_owned((0..n).map(|_| rng.gen_range(0.5..1.5) * if rng.gen::<bool>() {1.0} else {-1.0}).collect(), &out_shape).unwrap()
+    };
+    let grads = tr.backward_param_grads(out_id, w.clone());
+
+    let mut eval = |leaves: &[Tensor]| -> f64{
+        let mut tr = Trace::no_grad();
+        let pids: Vec<NodeId> = leaves.iter().map(|p| tr.param(p.clone())).collect();
+        let out_id = build(&mut tr, &pids);
+        assert_eq!(tr.get_tensor(out_id).shape, out_shape, "gradcheck: la shape de sortie dépend des params");
+        weighted_sum(tr.get_tensor(out_id), &w)
+    };
+
+    let mut checks = Vec::with_capacity(leaves.len());
+    for (i, (g, name)) in grads.iter().zip(names).enumerate(){
+        let g = g.contiguous();
+        let mut perturbed = leaves.clone();
+        let (mut max_abs, mut max_rel, mut ok) = (0f32, 0f32, true);
+        for j in 0..leaves[i].data.len(){
+            let x = leaves[i].data[j];
+            let mut at = |v: f32| -> f64{
+                let mut data = leaves[i].data.to_vec();
+                data[j] = v;
+                perturbed[i] = Tensor::from_owned(data, &leaves[i].shape).unwrap();
+                eval(&perturbed)
+            };
+            let numeric = ((at(x + eps) - at(x - eps)) / (2.0 * eps as f64)) as f32;
+            let analytic = g.data[j];
+
+            let abs = (analytic - numeric).abs();
+            let scale = analytic.abs().max(numeric.abs());
+            let rel = if scale > 0.0 {abs / scale} else {0.0};
+            max_abs = max_abs.max(abs);
+            max_rel = max_rel.max(rel);
+            ok &= abs <= tol || rel <= tol;
+        }
+        checks.push(ParamCheck { name, max_abs, max_rel, ok });
+    }
+    GradCheck { params: checks }
+}
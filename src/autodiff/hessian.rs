@@ -0,0 +1,32 @@
+use crate::tensor::Tensor; 
+use crate::trace::{Trace, NodeId}; 
+use crate::autodiff::jacobian::jacobian_of;
+
+/*
+hessienne d'une loss scalaire, par blocs: hess[i][j] = d²f / dp_i dp_j, de shape [params[i].shape.., params[j].shape..].
+le gradient est tracé (backward_traced) puis on prend la jacobienne de chaque gradient.
+renvoie aussi f(p). 
+*/
+pub fn hessian(
+    params: &[Tensor], 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
+
+) -> (Tensor, Vec<Vec<Tensor>>) {
+    let mut tr = Trace::new();
+
+    let mut param_ids = Vec::with_capacity(params.len()); 
+
+    for p in params{
+        param_ids.push(tr.param(p.clone()));
+    }
+
+    let loss_id = build(&mut tr, &param_ids);
+    let loss_val = tr.get_tensor(loss_id).clone(); 
+    assert!(loss_val.shape.iter().product::<usize>() == 1, "hessian: la sortie doit etre scalaire, shape {:?}", loss_val.shape);
+
+    let grads = tr.backward_traced(loss_id, &param_ids);
+
+    let hess = grads.iter().map(|&g| jacobian_of(&tr, g, &param_ids)).collect();
+
+    (loss_val, hess)
+}
@@ -0,0 +1,60 @@
+use crate::tensor::Tensor; 
+use crate::trace::{Trace, NodeId}; 
+use crate::ops::{hadamard_mul, sum_all, add};
+
+/*
+gradient de root par rapport à wrt, enregistré sur la trace: les NodeId renvoyés s'utilisent comme n'importe quel noeud.
+permet les pénalités de gradient dans build (la loss dépend alors d'un gradient), et grad(grad(f)):
+
+    let (loss, grads) = value_and_grad(&params, |tr, pids| {
+        let x = tr.input(xb.clone());
+        let out = forward(tr, pids, x);
+        let gx = grad(tr, out, &[x])[0];
+        let pen = ...(gx);
+        add(tr, out, pen)
+    });
+*/
+pub fn grad(tr: &mut Trace, root: NodeId, wrt: &[NodeId]) -> Vec<NodeId>{
+    tr.backward_traced(root, wrt)
+}
+
+/*
+produit hessienne-vecteur H.v, sans jamais construire H: 
+    H.v = d/dp <grad f(p), v>
+renvoie aussi f(p). v a la meme structure que params.
+*/
+pub fn hvp(
+    params: &[Tensor], 
+    v: &[Tensor], 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
+
+) -> (Tensor, Vec<Tensor>) {
+    assert_eq!(params.len(), v.len(), "hvp: v doit avoir un tenseur par param");
+    let mut tr = Trace::new();
+
+    let mut param_ids = Vec::with_capacity(params.len()); 
+
+    for p in params{
+        param_ids.push(tr.param(p.clone()));
+    }
+
+    let loss_id = build(&mut tr, &param_ids);
+    let loss_val = tr.get_tensor(loss_id).clone(); 
+
+    let grads = grad(&mut tr, loss_id, &param_ids);
+
+    let mut dot: Option<NodeId> = None; 
+    for (&g, vi) in grads.iter().zip(v.iter()){
+        let vi = tr.input(vi.clone());
+        let prod = hadamard_mul(&mut tr, g, vi);
+        let s = sum_all(&mut tr, prod);
+        dot = Some(match dot{
+            Some(d) => add(&mut tr, d, s),
+            None => s,
+        });
+    }
+    let dot = dot.expect("hvp: aucun param");
+
+    let seed = Tensor::ones(&tr.get_tensor(dot).shape);
+    (loss_val, tr.backward_param_grads(dot, seed))
+}
@@ -1,18 +1,19 @@
 use crate::tensor::Tensor; 
 use crate::trace::{Trace, NodeId}; 
+use crate::utils::params::Pytree;
 
-pub fn inference(
-    params: &[Tensor], 
-    build: impl Fn (&mut Trace, &[NodeId]) -> NodeId, 
+pub fn inference<P: Pytree + ?Sized>(
+    params: &P, 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
 
 ) -> Tensor {
-    let mut tr = Trace::new();
+    let mut tr = Trace::no_grad(); // pas de backward ici, donc aucune closure de dérivation
 
-    let mut param_ids = Vec::with_capacity(params.len()); 
+    let mut param_ids = Vec::new(); 
 
     // ca permet d'avoir leur id. 
-    for p in params{
-        param_ids.push(tr.param(p.clone()));
+    for p in params.leaves(){
+        param_ids.push(tr.param(p));
     }
 
     println!("params id: {:?} ", param_ids);
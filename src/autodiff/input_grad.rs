@@ -0,0 +1,39 @@
+use crate::tensor::Tensor; 
+use crate::trace::{Trace, NodeId}; 
+
+/*
+comme value_and_grad, mais renvoie d(loss)/d(inputs): cartes de saillance, exemples adverses (FGSM, PGD), optimisation de l'entrée...
+build recoit les ids des params puis ceux des inputs. les params restent constants ici.
+
+    let (loss, gx) = value_and_input_grad(&params, &[xb.clone()], |tr, pids, xids| {
+        let y = tr.input(yb.clone());
+        let logits = forward(tr, pids, xids[0]);
+        softmax_crossentropy(tr, logits, y)
+    });
+    let x_adv = &xb + &gx[0].apply(|g| eps * g.signum());
+*/
+pub fn value_and_input_grad(
+    params: &[Tensor], 
+    inputs: &[Tensor], 
+    mut build: impl FnMut (&mut Trace, &[NodeId], &[NodeId]) -> NodeId, 
+
+) -> (Tensor, Vec<Tensor>) {
+    let mut tr = Trace::new();
+
+    let mut param_ids = Vec::with_capacity(params.len()); 
+    for p in params{
+        param_ids.push(tr.param(p.clone()));
+    }
+
+    let mut input_ids = Vec::with_capacity(inputs.len()); 
+    for x in inputs{
+        input_ids.push(tr.input(x.clone()));
+    }
+
+    let loss_id = build(&mut tr, &param_ids, &input_ids);
+    let loss_val = tr.get_tensor(loss_id).clone(); 
+
+    let grads = tr.backward(loss_id, &input_ids);
+
+    (loss_val, grads)
+}
@@ -0,0 +1,81 @@
+use crate::tensor::Tensor; 
+use crate::trace::{Trace, NodeId}; 
+
+/*
+jacobienne complète de la sortie par rapport à chaque param: jac[i] a la shape [out.shape.., params[i].shape..].
+un backward par élément de sortie (seed one-hot), la trace n'est construite qu'une fois.
+renvoie aussi la valeur de la sortie. à réserver aux petits modèles.
+*/
+pub fn jacobian(
+    params: &[Tensor], 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
+
+) -> (Tensor, Vec<Tensor>) {
+    let mut tr = Trace::new();
+
+    let mut param_ids = Vec::with_capacity(params.len()); 
+
+    for p in params{
+        param_ids.push(tr.param(p.clone()));
+    }
+
+    let out_id = build(&mut tr, &param_ids);
+    let out_val = tr.get_tensor(out_id).clone(); 
+
+    let jac = jacobian_of(&tr, out_id, &param_ids);
+
+    (out_val, jac)
+}
+
+// jacobienne de out par rapport aux param_ids, sur une trace déjà construite 
+pub(crate) fn jacobian_of(tr: &Trace, out_id: NodeId, param_ids: &[NodeId]) -> Vec<Tensor>{
+    let out_shape = tr.get_tensor(out_id).shape.clone();
+    let n_out: usize = out_shape.iter().product();
+
+    let mut rows: Vec<Vec<f32>> = param_ids.iter()
+        .map(|&id| Vec::with_capacity(n_out * tr.get_tensor(id).shape.iter().product::<usize>()))
+        .collect();
+
+    // backward_param_grads renvoie tous les params de la trace, dans l'ordre de tr.param:
+    // on retrouve la place de chaque id demandé
+    let pos: Vec<usize> = param_ids.iter().map(|id| {
+        tr.params_id().iter().position(|p| p == id).expect("jacobian: id qui n'est pas un param de la trace")
+    }).collect();
+
+    for k in 0..n_out{
+        let mut seed = vec![0.0; n_out];
+        seed[k] = 1.0;
+        let seed = Tensor::from_owned(seed, &out_shape).unwrap();
+
+        let grads = tr.backward_param_grads(out_id, seed);
+        for (row, &i) in rows.iter_mut().zip(pos.iter()){
+            row.extend(grads[i].contiguous().data.iter());
+        }
+    }
+
+    rows.into_iter().zip(param_ids.iter()).map(|(data, &id)| {
+        let shape = [out_shape.as_slice(), tr.get_tensor(id).shape.as_slice()].concat();
+        Tensor::from_owned(data, &shape).unwrap()
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::ops;
+
+    // x -> W·x, jacobienne par rapport à x seul alors que W est le premier param de la trace
+    #[test]
+    fn jacobian_of_selects_requested_params(){
+        let w = Tensor::from_vec(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]).unwrap();
+        let mut tr = Trace::new();
+        let w_id = tr.param(w.clone());
+        let x_id = tr.param(Tensor::from_vec(&[0.5, -1.0, 2.0], &[3, 1]).unwrap());
+        let out = ops::matmul(&mut tr, w_id, x_id);
+
+        let jac = jacobian_of(&tr, out, &[x_id]);
+        assert_eq!(jac.len(), 1);
+        assert_eq!(jac[0].shape, vec![2, 1, 3, 1]);
+        assert_eq!(*jac[0].contiguous().data, *w.data);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::tensor::Tensor; 
+use crate::trace::{Trace, NodeId}; 
+
+/*
+mode direct: renvoie (f(params), J.tangents), la dérivée directionnelle de f dans la direction tangents.
+un seul passage quelle que soit la taille de la sortie, contrairement à value_and_grad qui en fait un par sortie scalaire.
+meme build que value_and_grad. Err si la sortie passe par un noeud sans règle jvp (cf Trace::forward_tangents).
+*/
+pub fn jvp(
+    params: &[Tensor], 
+    tangents: &[Tensor], 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
+
+) -> Result<(Tensor, Tensor), String> {
+    assert_eq!(params.len(), tangents.len(), "jvp: il faut une tangente par param");
+    let mut tr = Trace::new();
+
+    let mut param_ids = Vec::with_capacity(params.len()); 
+
+    for p in params{
+        param_ids.push(tr.param(p.clone()));
+    }
+
+    let out_id = build(&mut tr, &param_ids);
+    let out_val = tr.get_tensor(out_id).clone(); 
+
+    let seeds: Vec<(NodeId, Tensor)> = param_ids.iter().copied().zip(tangents.iter().cloned()).collect();
+    let out_tangent = tr.forward_tangents(&seeds)?[out_id].clone()
+        .unwrap_or_else(|| Tensor::zeros(&out_val.shape)); // la sortie ne dépend d'aucun param
+
+    Ok((out_val, out_tangent))
+}
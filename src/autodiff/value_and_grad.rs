@@ -1,17 +1,20 @@
 use crate::tensor::Tensor; 
 use crate::trace::{Trace, NodeId}; 
+use crate::utils::params::Pytree;
 
-pub fn value_and_grad(
-    params: &[Tensor], 
-    build: impl Fn (&mut Trace, &[NodeId]) -> NodeId, 
+// params: Vec<Tensor>, ParamTree, ... les gradients reviennent avec la meme structure (et les memes noms)
+pub fn value_and_grad<P: Pytree + ?Sized>(
+    params: &P, 
+    mut build: impl FnMut (&mut Trace, &[NodeId]) -> NodeId, 
 
-) -> (Tensor, Vec<Tensor>) {
+) -> (Tensor, P::Tree) {
     let mut tr = Trace::new();
 
-    let mut param_ids = Vec::with_capacity(params.len()); 
+    let leaves = params.leaves();
+    let mut param_ids = Vec::with_capacity(leaves.len()); 
 
-    for p in params{
-        param_ids.push(tr.param(p.clone()));
+    for p in leaves{
+        param_ids.push(tr.param(p));
     }
 
 
@@ -20,7 +23,7 @@ pub fn value_and_grad(
 
     let loss_val = tr.get_tensor(loss_id).clone(); 
 
-    let grads = tr.backward_param_grads(loss_id);
+    let grads = tr.backward_param_grads(loss_id, Tensor::ones(&loss_val.shape));
 
-    (loss_val, grads)
+    (loss_val, params.with_leaves(grads))
 }
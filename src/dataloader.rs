@@ -1,2 +1,3 @@
+#[allow(clippy::module_inception)]
 pub mod dataloader;
 pub mod collate;
\ No newline at end of file
@@ -1,8 +1,10 @@
-use rand::{seq::SliceRandom, thread_rng};
+use rand::seq::SliceRandom;
+use crate::utils::rng::SplitMix64;
 
 pub trait Dataset{
     type Item: Clone;
     fn len(&self) -> usize;
+    fn is_empty(&self) -> bool { self.len() == 0 }
     fn get(&mut self, idx: usize) -> Self::Item;  
 }
 pub struct DataLoader<D, C, B>
@@ -16,28 +18,70 @@ where
     bs: usize, 
     pos: usize, 
     shuffle: bool, 
-    collate: C
+    collate: C,
+    rng: SplitMix64, // son état est sauvegardé avec state(), pour reprendre le meme ordre de shuffle
 
 }
 
+// position d'un DataLoader: ordre de l'epoch en cours, position dedans, et état du rng pour les epochs suivantes
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataLoaderState{
+    pub indices: Vec<usize>, 
+    pub pos: usize, 
+    pub rng: u64,
+}
+
 impl <D, C, B> DataLoader<D, C, B>
 where 
     D: Dataset, 
     C: FnMut(Vec<D::Item>) -> B
 {
     pub fn new(dataset: D, batch_size: usize, shuffle: bool, collate: C) -> Self{
-        let mut indices: Vec<_> = (0..dataset.len()).collect();
-        if shuffle{
-            indices.shuffle(&mut thread_rng());
+        let indices: Vec<_> = (0..dataset.len()).collect();
+        let mut dl = Self { dataset, indices, bs: batch_size, pos: 0, shuffle, collate, rng: SplitMix64::from_entropy() };
+        if dl.shuffle{
+            dl.indices.shuffle(&mut dl.rng);
+        }
+        dl
+    }
+
+    // ordre des batchs reproductible d'un run à l'autre
+    pub fn seed(mut self, seed: u64) -> Self{
+        self.rng = SplitMix64::seed(seed);
+        self.indices = (0..self.dataset.len()).collect();
+        if self.shuffle{
+            self.indices.shuffle(&mut self.rng);
         }
-        Self { dataset, indices, bs: batch_size, pos: 0, shuffle, collate }
+        self.pos = 0;
+        self
     }
 
     pub fn reset_epoch(&mut self){
         self.pos = 0; 
         if self.shuffle{
-            self.indices.shuffle(&mut thread_rng());
+            self.indices.shuffle(&mut self.rng);
+        }
+    }
+
+    pub fn state(&self) -> DataLoaderState{
+        DataLoaderState { indices: self.indices.clone(), pos: self.pos, rng: self.rng.state() }
+    }
+
+    pub fn load_state(&mut self, state: DataLoaderState) -> Result<(), String>{
+        let n = self.dataset.len();
+        let mut seen = vec![false; n];
+        for &i in &state.indices{
+            if i >= n || std::mem::replace(&mut seen[i], true){
+                return Err(format!("dataloader: indices incompatibles avec un dataset de taille {n}"));
+            }
+        }
+        if state.indices.len() != n || state.pos > n{
+            return Err(format!("dataloader: état pour {} éléments (pos {}), dataset de taille {n}", state.indices.len(), state.pos));
         }
+        self.indices = state.indices;
+        self.pos = state.pos;
+        self.rng.set_state(state.rng);
+        Ok(())
     }
 }
 
@@ -70,7 +114,7 @@ where
     #[inline]
     fn size_hint(& self) -> (usize, Option<usize>) {
         let remaining_items = self.indices.len().saturating_sub(self.pos);
-        let batches = (remaining_items +self.bs -1)/self.bs; // ceil 
+        let batches = remaining_items.div_ceil(self.bs); // ceil 
         (batches, Some(batches))
     }
 }
\ No newline at end of file
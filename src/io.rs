@@ -0,0 +1,7 @@
+pub mod json;
+pub mod safetensors;
+pub mod npy;
+pub mod checkpoint;
+pub mod onnx;
+mod zip;
+mod protobuf;
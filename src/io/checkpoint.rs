@@ -0,0 +1,203 @@
+use std::fs;
+use std::path::Path;
+
+use crate::tensor::Tensor;
+use crate::io::json::Json;
+use crate::io::safetensors::{serialize, deserialize, check_against};
+use crate::optim::{Optimizer, OptimState};
+use crate::optim::lr_scheduler::{LrScheduler, SchedulerState};
+use crate::dataloader::dataloader::DataLoaderState;
+use crate::utils::params::{ParamTree, Pytree};
+use crate::utils::rng::SplitMix64;
+
+/*
+checkpoint complet d'un entrainement, pour reprendre un run interrompu et obtenir exactement le meme résultat.
+un seul fichier safetensors: les tenseurs (params, buffers de l'optimiseur) + le reste en json dans les métadonnées.
+les f32 du json sont stockés par leurs bits (exacts, et inf/nan passent), les u64 en texte.
+
+    let ck = Checkpoint::new(epoch, step, params.clone(), &opt).scheduler(&sched).loader(train.state());
+    ck.save("run.ckpt")?;
+    ...
+    let ck = Checkpoint::load("run.ckpt", &model.init_tree())?;
+    ck.restore_optim(&mut opt)?;
+    ck.restore_scheduler(&mut sched)?;
+    train.load_state(ck.loader.clone().unwrap())?;
+*/
+#[derive(Debug, Clone)]
+pub struct Checkpoint{
+    pub epoch: usize, 
+    pub step: usize, 
+    pub params: ParamTree, 
+    pub optim: OptimState, 
+    pub lr: f32, 
+    pub scheduler: Option<SchedulerState>, 
+    pub rng: Option<u64>, // rng utilisateur (augmentations, dropout...) 
+    pub loader: Option<DataLoaderState>,
+}
+
+const META_KEY: &str = "lamp.checkpoint";
+
+impl Checkpoint{
+    pub fn new(epoch: usize, step: usize, params: ParamTree, opt: &dyn Optimizer) -> Checkpoint{
+        Checkpoint { epoch, step, params, optim: opt.state(), lr: opt.lr(), scheduler: None, rng: None, loader: None }
+    }
+
+    pub fn scheduler(mut self, sched: &dyn LrScheduler) -> Self{
+        self.scheduler = Some(sched.state()); 
+        self
+    }
+
+    pub fn rng(mut self, rng: &SplitMix64) -> Self{
+        self.rng = Some(rng.state()); 
+        self
+    }
+
+    pub fn loader(mut self, state: DataLoaderState) -> Self{
+        self.loader = Some(state); 
+        self
+    }
+
+    pub fn restore_optim(&self, opt: &mut dyn Optimizer) -> Result<(), String>{
+        opt.load_state_checked(self.optim.clone(), &self.params.leaves())?;
+        opt.set_lr(self.lr);
+        Ok(())
+    }
+
+    pub fn restore_scheduler(&self, sched: &mut dyn LrScheduler) -> Result<(), String>{
+        let state = self.scheduler.clone().ok_or("checkpoint: pas d'état de scheduler")?;
+        sched.load_state(state)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String>{
+        let mut tensors: Vec<(String, Tensor)> = self.params.named_leaves().into_iter()
+            .map(|(p, t)| (format!("params.{p}"), t))
+            .collect();
+        let mut buffers = Vec::with_capacity(self.optim.buffers.len());
+        for (b, (name, ts)) in self.optim.buffers.iter().enumerate(){
+            tensors.extend(ts.iter().enumerate().map(|(i, t)| (format!("optim.{b}.{i}"), t.clone())));
+            buffers.push(Json::Array(vec![Json::Str(name.clone()), Json::Number(ts.len() as f64)]));
+        }
+
+        let meta = Json::Object(vec![
+            ("epoch".into(), Json::Number(self.epoch as f64)),
+            ("step".into(), Json::Number(self.step as f64)),
+            ("lr".into(), f32_json(self.lr)),
+            ("optim_step".into(), Json::Number(self.optim.step as f64)),
+            ("optim_buffers".into(), Json::Array(buffers)),
+            ("scheduler".into(), self.scheduler.as_ref().map_or(Json::Null, sched_json)),
+            ("rng".into(), self.rng.map_or(Json::Null, |r| Json::Str(r.to_string()))),
+            ("loader".into(), self.loader.as_ref().map_or(Json::Null, |l| Json::Object(vec![
+                ("indices".into(), Json::usize_array(&l.indices)),
+                ("pos".into(), Json::Number(l.pos as f64)),
+                ("rng".into(), Json::Str(l.rng.to_string())),
+            ]))),
+        ]);
+        serialize(&tensors, &[(META_KEY.into(), meta.dump())])
+    }
+
+    /*
+    like donne la structure attendue des params (typiquement model.init_tree()):
+    chemins et shapes sont vérifiés, et les params reviennent dans l'ordre de like.
+    */
+    pub fn from_bytes(bytes: &[u8], like: &ParamTree) -> Result<Checkpoint, String>{
+        let (tensors, metadata) = deserialize(bytes)?;
+        let meta = metadata.iter().find(|(k, _)| k == META_KEY).ok_or("checkpoint: métadonnées absentes, ce n'est pas un checkpoint")?;
+        let meta = Json::parse(&meta.1)?;
+        let field = |k: &str| meta.get(k).ok_or_else(|| format!("checkpoint: champ '{k}' manquant"));
+        let usize_field = |k: &str| field(k)?.as_usize().ok_or_else(|| format!("checkpoint: '{k}' invalide"));
+
+        let params: Vec<(String, Tensor)> = tensors.iter()
+            .filter_map(|(n, t)| n.strip_prefix("params.").map(|p| (p.to_string(), t.clone())))
+            .collect();
+        check_against(&params, like)?;
+        let params = like.with_leaves(like.paths().iter()
+            .map(|p| params.iter().find(|(n, _)| n == p).unwrap().1.clone())
+            .collect());
+
+        let mut buffers = Vec::new();
+        for (b, entry) in field("optim_buffers")?.as_array().ok_or("checkpoint: optim_buffers invalide")?.iter().enumerate(){
+            let (Some(name), Some(len)) = (entry.as_array().and_then(|e| e.first()).and_then(Json::as_str), entry.as_array().and_then(|e| e.get(1)).and_then(Json::as_usize)) else {
+                return Err("checkpoint: optim_buffers invalide".into());
+            };
+            let ts = (0..len).map(|i| {
+                let key = format!("optim.{b}.{i}");
+                tensors.iter().find(|(n, _)| *n == key).map(|(_, t)| t.clone()).ok_or_else(|| format!("checkpoint: tenseur '{key}' manquant"))
+            }).collect::<Result<Vec<_>, _>>()?;
+            buffers.push((name.to_string(), ts));
+        }
+
+        let scheduler = match field("scheduler")?{
+            Json::Null => None,
+            s => Some(sched_from_json(s)?),
+        };
+        let rng = match field("rng")?{
+            Json::Null => None,
+            r => Some(u64_from_json(r)?),
+        };
+        let loader = match field("loader")?{
+            Json::Null => None,
+            l => Some(DataLoaderState {
+                indices: l.get("indices").and_then(Json::as_usize_vec).ok_or("checkpoint: loader.indices invalide")?,
+                pos: l.get("pos").and_then(Json::as_usize).ok_or("checkpoint: loader.pos invalide")?,
+                rng: u64_from_json(l.get("rng").ok_or("checkpoint: loader.rng manquant")?)?,
+            }),
+        };
+
+        Ok(Checkpoint {
+            epoch: usize_field("epoch")?, 
+            step: usize_field("step")?, 
+            params, 
+            optim: OptimState { step: usize_field("optim_step")?, buffers }, 
+            lr: f32_from_json(field("lr")?)?, 
+            scheduler, 
+            rng, 
+            loader,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String>{
+        // écrit à coté puis renomme: un run tué pendant la sauvegarde ne corrompt pas le checkpoint précédent
+        let tmp = path.as_ref().with_extension("tmp");
+        fs::write(&tmp, self.to_bytes()?).map_err(|e| format!("checkpoint: écriture de {tmp:?}: {e}"))?;
+        fs::rename(&tmp, path.as_ref()).map_err(|e| format!("checkpoint: renommage en {:?}: {e}", path.as_ref()))
+    }
+
+    pub fn load(path: impl AsRef<Path>, like: &ParamTree) -> Result<Checkpoint, String>{
+        let bytes = fs::read(path.as_ref()).map_err(|e| format!("checkpoint: lecture de {:?}: {e}", path.as_ref()))?;
+        Checkpoint::from_bytes(&bytes, like)
+    }
+}
+
+fn f32_json(x: f32) -> Json{
+    Json::Number(x.to_bits() as f64)
+}
+
+fn f32_from_json(j: &Json) -> Result<f32, String>{
+    j.as_usize().and_then(|b| u32::try_from(b).ok()).map(f32::from_bits).ok_or_else(|| "checkpoint: flottant invalide".into())
+}
+
+fn u64_from_json(j: &Json) -> Result<u64, String>{
+    j.as_str().and_then(|s| s.parse().ok()).ok_or_else(|| "checkpoint: état de rng invalide".into())
+}
+
+fn sched_json(s: &SchedulerState) -> Json{
+    Json::Object(vec![
+        ("t".into(), Json::Number(s.t as f64)),
+        ("last_lr".into(), f32_json(s.last_lr)),
+        ("values".into(), Json::Object(s.values.iter().map(|(k, v)| (k.clone(), f32_json(*v))).collect())),
+        ("after".into(), s.after.as_ref().map_or(Json::Null, |a| sched_json(a))),
+    ])
+}
+
+fn sched_from_json(j: &Json) -> Result<SchedulerState, String>{
+    let Some(Json::Object(values)) = j.get("values") else {return Err("checkpoint: scheduler.values invalide".into())};
+    Ok(SchedulerState {
+        t: j.get("t").and_then(Json::as_usize).ok_or("checkpoint: scheduler.t invalide")?,
+        last_lr: f32_from_json(j.get("last_lr").ok_or("checkpoint: scheduler.last_lr manquant")?)?,
+        values: values.iter().map(|(k, v)| Ok((k.clone(), f32_from_json(v)?))).collect::<Result<_, String>>()?,
+        after: match j.get("after"){
+            None | Some(Json::Null) => None,
+            Some(a) => Some(Box::new(sched_from_json(a)?)),
+        },
+    })
+}
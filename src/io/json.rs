@@ -0,0 +1,253 @@
+/*
+json minimal, juste ce qu'il faut pour les headers (safetensors, checkpoints): pas de dépendance serde.
+les objets gardent l'ordre des clés.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json{
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json{
+    pub fn parse(s: &str) -> Result<Json, String>{
+        let mut p = Parser { b: s.as_bytes(), i: 0 };
+        let v = p.value()?;
+        p.ws();
+        if p.i != p.b.len(){
+            return Err(format!("json: caractères en trop à la position {}", p.i));
+        }
+        Ok(v)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json>{
+        match self{
+            Json::Object(kv) => kv.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str>{
+        match self{ Json::Str(s) => Some(s), _ => None }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>>{
+        match self{ Json::Array(a) => Some(a), _ => None }
+    }
+
+    pub fn as_usize(&self) -> Option<usize>{
+        match self{
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64>{
+        match self{ Json::Number(n) => Some(*n), _ => None }
+    }
+
+    // liste d'entiers, ex: shape ou data_offsets
+    pub fn as_usize_vec(&self) -> Option<Vec<usize>>{
+        self.as_array()?.iter().map(|v| v.as_usize()).collect()
+    }
+
+    pub fn usize_array(v: &[usize]) -> Json{
+        Json::Array(v.iter().map(|&x| Json::Number(x as f64)).collect())
+    }
+
+    pub fn dump(&self) -> String{
+        let mut out = String::new();
+        self.write(&mut out);
+        out
+    }
+
+    fn write(&self, out: &mut String){
+        match self{
+            Json::Null => out.push_str("null"),
+            Json::Bool(b) => out.push_str(if *b {"true"} else {"false"}),
+            // les entiers sont écrits sans ".0" (offsets, shapes), le reste en notation rust qui relit à l'identique
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => out.push_str(&format!("{}", *n as i64)),
+            Json::Number(n) => out.push_str(&format!("{n:?}")),
+            Json::Str(s) => write_str(s, out),
+            Json::Array(a) => {
+                out.push('[');
+                for (i, v) in a.iter().enumerate(){
+                    if i > 0 { out.push(','); }
+                    v.write(out);
+                }
+                out.push(']');
+            }
+            Json::Object(kv) => {
+                out.push('{');
+                for (i, (k, v)) in kv.iter().enumerate(){
+                    if i > 0 { out.push(','); }
+                    write_str(k, out);
+                    out.push(':');
+                    v.write(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_str(s: &str, out: &mut String){
+    out.push('"');
+    for c in s.chars(){
+        match c{
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct Parser<'a>{
+    b: &'a [u8],
+    i: usize,
+}
+
+impl Parser<'_>{
+    fn ws(&mut self){
+        while self.i < self.b.len() && self.b[self.i].is_ascii_whitespace(){
+            self.i += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String>{
+        self.ws();
+        if self.b.get(self.i) != Some(&c){
+            return Err(format!("json: '{}' attendu à la position {}", c as char, self.i));
+        }
+        self.i += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String>{
+        self.ws();
+        match self.b.get(self.i){
+            None => Err("json: fin inattendue".into()),
+            Some(b'{') => {
+                self.i += 1;
+                let mut kv = Vec::new();
+                self.ws();
+                if self.b.get(self.i) == Some(&b'}'){
+                    self.i += 1;
+                    return Ok(Json::Object(kv));
+                }
+                loop{
+                    self.ws();
+                    let k = self.string()?;
+                    self.expect(b':')?;
+                    kv.push((k, self.value()?));
+                    self.ws();
+                    match self.b.get(self.i){
+                        Some(b',') => self.i += 1,
+                        Some(b'}') => { self.i += 1; return Ok(Json::Object(kv)); }
+                        _ => return Err(format!("json: ',' ou '}}' attendu à la position {}", self.i)),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.i += 1;
+                let mut a = Vec::new();
+                self.ws();
+                if self.b.get(self.i) == Some(&b']'){
+                    self.i += 1;
+                    return Ok(Json::Array(a));
+                }
+                loop{
+                    a.push(self.value()?);
+                    self.ws();
+                    match self.b.get(self.i){
+                        Some(b',') => self.i += 1,
+                        Some(b']') => { self.i += 1; return Ok(Json::Array(a)); }
+                        _ => return Err(format!("json: ',' ou ']' attendu à la position {}", self.i)),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Json::Str(self.string()?)),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(_) => self.number(),
+        }
+    }
+
+    fn literal(&mut self, word: &str, v: Json) -> Result<Json, String>{
+        if self.b[self.i..].starts_with(word.as_bytes()){
+            self.i += word.len();
+            Ok(v)
+        } else {
+            Err(format!("json: valeur invalide à la position {}", self.i))
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, String>{
+        let start = self.i;
+        while self.i < self.b.len() && matches!(self.b[self.i], b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E'){
+            self.i += 1;
+        }
+        let s = std::str::from_utf8(&self.b[start..self.i]).unwrap();
+        s.parse::<f64>().map(Json::Number).map_err(|_| format!("json: nombre invalide '{s}' à la position {start}"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String>{
+        let hex = self.b.get(self.i..self.i+4).ok_or("json: \\u incomplet")?;
+        let code = u32::from_str_radix(std::str::from_utf8(hex).map_err(|e| e.to_string())?, 16).map_err(|e| format!("json: \\u invalide: {e}"))?;
+        self.i += 4;
+        Ok(code)
+    }
+
+    fn string(&mut self) -> Result<String, String>{
+        if self.b.get(self.i) != Some(&b'"'){
+            return Err(format!("json: chaine attendue à la position {}", self.i));
+        }
+        self.i += 1;
+        let mut out: Vec<u8> = Vec::new();
+        loop{
+            let Some(&c) = self.b.get(self.i) else {return Err("json: chaine non terminée".into())};
+            self.i += 1;
+            match c{
+                b'"' => break,
+                b'\\' => {
+                    let Some(&e) = self.b.get(self.i) else {return Err("json: chaine non terminée".into())};
+                    self.i += 1;
+                    match e{
+                        b'"' => out.push(b'"'),
+                        b'\\' => out.push(b'\\'),
+                        b'/' => out.push(b'/'),
+                        b'b' => out.push(8),
+                        b'f' => out.push(12),
+                        b'n' => out.push(b'\n'),
+                        b'r' => out.push(b'\r'),
+                        b't' => out.push(b'\t'),
+                        b'u' => {
+                            let mut code = self.hex4()?;
+                            // hors du plan de base: paire surrogate \ud83d\ude00 (ce qu'écrit json.dumps de python)
+                            if (0xd800..0xdc00).contains(&code) && self.b[self.i..].starts_with(b"\\u"){
+                                self.i += 2;
+                                let low = self.hex4()?;
+                                code = if (0xdc00..0xe000).contains(&low) {0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00)} else {0xfffd};
+                            }
+                            let ch = char::from_u32(code).unwrap_or('\u{fffd}');
+                            let mut buf = [0u8; 4];
+                            out.extend_from_slice(ch.encode_utf8(&mut buf).as_bytes());
+                        }
+                        _ => return Err(format!("json: échappement invalide à la position {}", self.i)),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        String::from_utf8(out).map_err(|e| format!("json: utf8 invalide: {e}"))
+    }
+}
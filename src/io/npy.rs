@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::Path;
+
+use crate::tensor::Tensor;
+use crate::io::zip::{read_zip, write_zip};
+use crate::io::safetensors::NamedTensors;
+
+/*
+format .npy (numpy): "\x93NUMPY" | version | taille du header | dict python | données brutes
+    {'descr': '<f4', 'fortran_order': False, 'shape': (2, 3), }
+on écrit du '<f4' en ordre C (v1.0, header aligné sur 64 octets). 
+on relit f2/f4/f8, entiers signés/non signés et booléens, little ou big endian, ordre C ou fortran: tout est converti en f32.
+*/
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+impl Tensor{
+    pub fn to_npy_bytes(&self) -> Vec<u8>{
+        let shape = match self.shape.len(){
+            0 => "()".to_string(),
+            1 => format!("({},)", self.shape[0]),
+            _ => format!("({})", self.shape.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+        // magic(6) + version(2) + taille(2) + header + '\n' doit etre un multiple de 64
+        while !(10 + header.len() + 1).is_multiple_of(64){
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut out = Vec::with_capacity(10 + header.len() + 4*self.shape.iter().product::<usize>());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&[1, 0]);
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        // contiguous: une vue (mat_transpose, broadcast...) est écrite dans son ordre logique, pas celui du buffer
+        for x in self.contiguous().data.iter(){
+            out.extend_from_slice(&x.to_le_bytes());
+        }
+        out
+    }
+
+    pub fn from_npy_bytes(bytes: &[u8]) -> Result<Tensor, String>{
+        if bytes.get(..6) != Some(MAGIC){
+            return Err("npy: magic invalide".into());
+        }
+        let major = *bytes.get(6).ok_or("npy: fichier tronqué")?;
+        let (header_len, start) = match major{
+            1 => (u16::from_le_bytes(bytes.get(8..10).ok_or("npy: fichier tronqué")?.try_into().unwrap()) as usize, 10),
+            2 | 3 => (u32::from_le_bytes(bytes.get(8..12).ok_or("npy: fichier tronqué")?.try_into().unwrap()) as usize, 12),
+            v => return Err(format!("npy: version {v} non supportée")),
+        };
+        let header = bytes.get(start..start+header_len).ok_or("npy: header tronqué")?;
+        let header = std::str::from_utf8(header).map_err(|e| format!("npy: header invalide: {e}"))?;
+        let (descr, fortran, shape) = parse_header(header)?;
+
+        let raw = &bytes[start+header_len..];
+        let data = decode(&descr, raw)?;
+        let numel: usize = shape.iter().product();
+        if data.len() < numel{
+            return Err(format!("npy: {} valeurs pour la shape {shape:?}", data.len()));
+        }
+        let data = data[..numel].to_vec();
+        if !fortran || shape.len() < 2{
+            return Tensor::from_owned(data, &shape);
+        }
+        // ordre fortran = ordre C de la shape inversée, puis transposition complète
+        let rev: Vec<usize> = shape.iter().rev().copied().collect();
+        let t = Tensor::from_owned(data, &rev)?;
+        let f_strides: Vec<usize> = t.strides.iter().rev().copied().collect();
+        Ok(Tensor { data: t.data, shape, strides: f_strides, offset: 0 }.contiguous())
+    }
+
+    pub fn save_npy(&self, path: impl AsRef<Path>) -> Result<(), String>{
+        fs::write(path.as_ref(), self.to_npy_bytes()).map_err(|e| format!("npy: écriture de {:?}: {e}", path.as_ref()))
+    }
+
+    pub fn load_npy(path: impl AsRef<Path>) -> Result<Tensor, String>{
+        let bytes = fs::read(path.as_ref()).map_err(|e| format!("npy: lecture de {:?}: {e}", path.as_ref()))?;
+        Tensor::from_npy_bytes(&bytes)
+    }
+}
+
+// archive npz comme np.savez (non compressée): une entrée "nom.npy" par tenseur
+pub fn save_npz(path: impl AsRef<Path>, tensors: &[(String, Tensor)]) -> Result<(), String>{
+    let entries: Vec<(String, Vec<u8>)> = tensors.iter().map(|(n, t)| (format!("{n}.npy"), t.to_npy_bytes())).collect();
+    let bytes = write_zip(&entries)?;
+    fs::write(path.as_ref(), bytes).map_err(|e| format!("npz: écriture de {:?}: {e}", path.as_ref()))
+}
+
+// lit aussi les archives de np.savez_compressed (deflate)
+pub fn load_npz(path: impl AsRef<Path>) -> Result<NamedTensors, String>{
+    let bytes = fs::read(path.as_ref()).map_err(|e| format!("npz: lecture de {:?}: {e}", path.as_ref()))?;
+    read_zip(&bytes)?.into_iter().map(|(name, data)| {
+        let t = Tensor::from_npy_bytes(&data).map_err(|e| format!("npz: '{name}': {e}"))?;
+        Ok((name.strip_suffix(".npy").unwrap_or(&name).to_string(), t))
+    }).collect()
+}
+
+// on ne lit que les 3 clés du dict, sans vrai parseur python
+fn parse_header(h: &str) -> Result<(String, bool, Vec<usize>), String>{
+    let value_of = |key: &str| -> Result<&str, String>{
+        let i = h.find(&format!("'{key}'")).ok_or_else(|| format!("npy: clé '{key}' absente du header"))?;
+        let rest = &h[i + key.len() + 2..];
+        let colon = rest.find(':').ok_or("npy: header invalide")?;
+        Ok(rest[colon+1..].trim_start())
+    };
+
+    let d = value_of("descr")?;
+    let quote = d.chars().next().ok_or("npy: descr vide")?;
+    let end = d[1..].find(quote).ok_or("npy: descr invalide")?;
+    let descr = d[1..1+end].to_string();
+
+    let fortran = value_of("fortran_order")?.starts_with("True");
+
+    let s = value_of("shape")?;
+    let end = s.find(')').ok_or("npy: shape invalide")?;
+    let shape = s.get(1..end).ok_or("npy: shape invalide")?
+        .split(',')
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.trim_end_matches('L').parse::<usize>().map_err(|_| format!("npy: dimension invalide '{x}'")))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((descr, fortran, shape))
+}
+
+fn decode(descr: &str, raw: &[u8]) -> Result<Vec<f32>, String>{
+    let (order, kind_size) = match descr.chars().next(){
+        Some(c @ ('<' | '>' | '|' | '=')) => (c, &descr[1..]),
+        _ => ('=', descr),
+    };
+    let big = order == '>';
+    let kind = kind_size.chars().next().ok_or("npy: descr vide")?;
+    let size: usize = kind_size[1..].parse().map_err(|_| format!("npy: dtype '{descr}' non supporté"))?;
+
+    macro_rules! read{
+        ($t:ty) => {
+            raw.chunks_exact(size).map(|c| {
+                let b = c.try_into().unwrap();
+                (if big {<$t>::from_be_bytes(b)} else {<$t>::from_le_bytes(b)}) as f32
+            }).collect()
+        };
+    }
+    Ok(match (kind, size){
+        ('f', 4) => read!(f32),
+        ('f', 8) => read!(f64),
+        ('f', 2) => raw.chunks_exact(2).map(|c| {
+            let b = c.try_into().unwrap();
+            crate::io::safetensors::f16_to_f32(if big {u16::from_be_bytes(b)} else {u16::from_le_bytes(b)})
+        }).collect(),
+        ('i', 1) => read!(i8),
+        ('i', 2) => read!(i16),
+        ('i', 4) => read!(i32),
+        ('i', 8) => read!(i64),
+        ('u', 1) | ('b', 1) => read!(u8),
+        ('u', 2) => read!(u16),
+        ('u', 4) => read!(u32),
+        ('u', 8) => read!(u64),
+        _ => return Err(format!("npy: dtype '{descr}' non supporté")),
+    })
+}
@@ -0,0 +1,1044 @@
+use std::fs;
+use std::path::Path;
+
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId, OpKind};
+use crate::io::protobuf::{Encoder, Message};
+use crate::utils::params::{ParamTree, Pytree, get_params_id};
+use crate::ops::{self, Conv2dOpts};
+use crate::nn::functions;
+
+/*
+export onnx d'une trace (opset 13). les shapes sont celles des exemples qui ont servi à construire la trace:
+pas de dimension symbolique pour le batch, il faut réexporter pour changer de shape.
+
+nommage dans le graphe:
+    entrées "input{i}", sorties "output{i}", params => leur nom (chemin du ParamTree),
+    noeuds intermédiaires "n{id}", entrées non déclarées (constantes, cibles...) => initializers "const{id}"
+*/
+
+const OPSET: i64 = 13;
+const IR_VERSION: u64 = 7;
+
+// TensorProto.DataType
+const FLOAT: u64 = 1;
+const INT64: u64 = 7;
+
+// AttributeProto.AttributeType
+const ATTR_INT: u64 = 2;
+const ATTR_INTS: u64 = 7;
+
+enum Attr{
+    Int(i64),
+    Ints(Vec<i64>),
+}
+
+fn attribute(name: &str, a: &Attr) -> Encoder{
+    let mut e = Encoder::new();
+    e.string(1, name);
+    match a{
+        Attr::Int(i) => {e.int64(3, *i); e.varint(20, ATTR_INT);}
+        Attr::Ints(v) => {
+            for &i in v{
+                e.int64(8, i);
+            }
+            e.varint(20, ATTR_INTS);
+        }
+    }
+    e
+}
+
+fn float_tensor(name: &str, t: &Tensor) -> Encoder{
+    let mut e = Encoder::new();
+    for &d in &t.shape{
+        e.int64(1, d as i64);
+    }
+    e.varint(2, FLOAT);
+    e.string(8, name);
+    let raw: Vec<u8> = t.contiguous().data.iter().flat_map(|x| x.to_le_bytes()).collect();
+    e.bytes(9, &raw);
+    e
+}
+
+// tenseur 1d d'entiers (axes, shapes) passé en entrée des ops depuis l'opset 13
+fn int64_tensor(name: &str, v: &[i64]) -> Encoder{
+    let mut e = Encoder::new();
+    e.int64(1, v.len() as i64);
+    e.varint(2, INT64);
+    e.string(8, name);
+    let raw: Vec<u8> = v.iter().flat_map(|x| x.to_le_bytes()).collect();
+    e.bytes(9, &raw);
+    e
+}
+
+fn value_info(name: &str, shape: &[usize]) -> Encoder{
+    let mut shape_e = Encoder::new();
+    for &d in shape{
+        let mut dim = Encoder::new();
+        dim.int64(1, d as i64);
+        shape_e.message(1, &dim);
+    }
+    let mut tensor_type = Encoder::new();
+    tensor_type.varint(1, FLOAT);
+    tensor_type.message(2, &shape_e);
+    let mut ty = Encoder::new();
+    ty.message(1, &tensor_type);
+
+    let mut e = Encoder::new();
+    e.string(1, name);
+    e.message(2, &ty);
+    e
+}
+
+#[derive(Default)]
+struct Graph{
+    nodes: Vec<Encoder>,
+    initializers: Vec<Encoder>,
+}
+
+impl Graph{
+    fn node(&mut self, op_type: &str, name: &str, inputs: &[&str], outputs: &[&str], attrs: &[(&str, Attr)]){
+        let mut e = Encoder::new();
+        for i in inputs{
+            e.string(1, i);
+        }
+        for o in outputs{
+            e.string(2, o);
+        }
+        e.string(3, name);
+        e.string(4, op_type);
+        for (n, a) in attrs{
+            e.message(5, &attribute(n, a));
+        }
+        self.nodes.push(e);
+    }
+
+    // constante entière, renvoie son nom
+    fn ints(&mut self, name: String, v: &[i64]) -> String{
+        self.initializers.push(int64_tensor(&name, v));
+        name
+    }
+}
+
+fn pair(p: (usize, usize)) -> Vec<i64>{
+    vec![p.0 as i64, p.1 as i64]
+}
+
+// [haut, gauche, bas, droite]: le padding de lamp est symétrique
+fn pads(p: (usize, usize)) -> Vec<i64>{
+    vec![p.0 as i64, p.1 as i64, p.0 as i64, p.1 as i64]
+}
+
+fn export_node(g: &mut Graph, tr: &Trace, id: NodeId, ins: &[&str], out: &str) -> Result<(), ()>{
+    let node = tr.node(id);
+    let x_shape = node.parents_id.first().map(|&p| tr.get_tensor(p).shape.clone()).unwrap_or_default();
+    let rank = x_shape.len() as i64;
+    let name = format!("n{id}");
+
+    match &node.op{
+        // memes noms et broadcast numpy des deux cotés
+        OpKind::Add | OpKind::Sub | OpKind::Mul | OpKind::MatMul | OpKind::Relu | OpKind::Tanh => g.node(node.op.name(), &name, ins, &[out], &[]),
+        OpKind::StopGradient => g.node("Identity", &name, ins, &[out], &[]),
+        OpKind::Scale(c) => {
+            let c_name = format!("{name}_c");
+            g.initializers.push(float_tensor(&c_name, &Tensor::from_vec(&[*c], &[]).unwrap()));
+            g.node("Mul", &name, &[ins[0], &c_name], &[out], &[]);
+        }
+        OpKind::Softmax => g.node("Softmax", &name, ins, &[out], &[("axis", Attr::Int(-1))]),
+        OpKind::MeanAll => g.node("ReduceMean", &name, ins, &[out], &[("keepdims", Attr::Int(0))]),
+        OpKind::SumAll => g.node("ReduceSum", &name, ins, &[out], &[("keepdims", Attr::Int(0))]),
+        OpKind::SumLast => {
+            let axes = g.ints(format!("{name}_axes"), &[-1]);
+            g.node("ReduceSum", &name, &[ins[0], &axes], &[out], &[("keepdims", Attr::Int(1))]);
+        }
+        // axes explicites et triés dans l'OpKind. opset 13: axes en entrée pour ReduceSum, en attribut pour les autres
+        OpKind::Sum{axes, keepdim} => {
+            let axes = g.ints(format!("{name}_axes"), &axes.iter().map(|&a| a as i64).collect::<Vec<_>>());
+            g.node("ReduceSum", &name, &[ins[0], &axes], &[out], &[("keepdims", Attr::Int(*keepdim as i64))]);
+        }
+        OpKind::Mean{axes, keepdim} | OpKind::Max{axes, keepdim} => {
+            let op_type = if matches!(node.op, OpKind::Mean{..}) {"ReduceMean"} else {"ReduceMax"};
+            g.node(op_type, &name, ins, &[out], &[
+                ("axes", Attr::Ints(axes.iter().map(|&a| a as i64).collect())),
+                ("keepdims", Attr::Int(*keepdim as i64)),
+            ]);
+        }
+        OpKind::SumToShape(shape) => {
+            // axes en trop à gauche + axes broadcastés (1 dans la cible), puis reshape vers la cible
+            let lead = x_shape.len() - shape.len();
+            let axes: Vec<i64> = (0..x_shape.len())
+                .filter(|&i| i < lead || (shape[i-lead] == 1 && x_shape[i] != 1))
+                .map(|i| i as i64)
+                .collect();
+            let target = g.ints(format!("{name}_shape"), &shape.iter().map(|&d| d as i64).collect::<Vec<_>>());
+            if axes.is_empty(){
+                g.node("Reshape", &name, &[ins[0], &target], &[out], &[]);
+            }else{
+                let axes = g.ints(format!("{name}_axes"), &axes);
+                let summed = format!("{name}_sum");
+                g.node("ReduceSum", &summed, &[ins[0], &axes], &[&summed], &[("keepdims", Attr::Int(1))]);
+                g.node("Reshape", &name, &[&summed, &target], &[out], &[]);
+            }
+        }
+        OpKind::BroadcastTo(shape) => {
+            let target = g.ints(format!("{name}_shape"), &shape.iter().map(|&d| d as i64).collect::<Vec<_>>());
+            g.node("Expand", &name, &[ins[0], &target], &[out], &[]);
+        }
+        OpKind::MatTranspose => {
+            let mut perm: Vec<i64> = (0..rank).collect();
+            perm.swap(rank as usize - 2, rank as usize - 1);
+            g.node("Transpose", &name, ins, &[out], &[("perm", Attr::Ints(perm))]);
+        }
+        OpKind::Unsqueeze(axis) | OpKind::Squeeze(axis) => {
+            let axes = g.ints(format!("{name}_axes"), &[*axis as i64]);
+            g.node(node.op.name(), &name, &[ins[0], &axes], &[out], &[]);
+        }
+        OpKind::Permute(perm) => {
+            g.node("Transpose", &name, ins, &[out], &[("perm", Attr::Ints(perm.iter().map(|&p| p as i64).collect()))]);
+        }
+        OpKind::Reshape(shape) => {
+            let target = g.ints(format!("{name}_shape"), &shape.iter().map(|&d| d as i64).collect::<Vec<_>>());
+            g.node("Reshape", &name, &[ins[0], &target], &[out], &[]);
+        }
+        OpKind::Conv2d(opts) => {
+            let w_shape = &tr.get_tensor(node.parents_id[1]).shape;
+            g.node("Conv", &name, ins, &[out], &[
+                ("kernel_shape", Attr::Ints(pair((w_shape[2], w_shape[3])))),
+                ("strides", Attr::Ints(pair(opts.stride))),
+                ("pads", Attr::Ints(pads(opts.padding))),
+                ("dilations", Attr::Ints(pair(opts.dilation))),
+            ]);
+        }
+        OpKind::MaxPool2d{kernel, stride, padding} => {
+            g.node("MaxPool", &name, ins, &[out], &[
+                ("kernel_shape", Attr::Ints(pair(*kernel))),
+                ("strides", Attr::Ints(pair(*stride))),
+                ("pads", Attr::Ints(pads(*padding))),
+            ]);
+        }
+        OpKind::AvgPool2d{kernel, stride, padding} => {
+            g.node("AveragePool", &name, ins, &[out], &[
+                ("kernel_shape", Attr::Ints(pair(*kernel))),
+                ("strides", Attr::Ints(pair(*stride))),
+                ("pads", Attr::Ints(pads(*padding))),
+                ("count_include_pad", Attr::Int(1)),
+            ]);
+        }
+        OpKind::GlobalAvgPool2d => g.node("GlobalAveragePool", &name, ins, &[out], &[]),
+        // fenetres variables: pas d'équivalent onnx, sauf si elles sont toutes de la meme taille
+        OpKind::AdaptiveAvgPool2d((oh, ow)) if x_shape[2].is_multiple_of(*oh) && x_shape[3].is_multiple_of(*ow) => {
+            let k = (x_shape[2] / oh, x_shape[3] / ow);
+            g.node("AveragePool", &name, ins, &[out], &[
+                ("kernel_shape", Attr::Ints(pair(k))),
+                ("strides", Attr::Ints(pair(k))),
+            ]);
+        }
+        _ => return Err(()),
+    }
+    Ok(())
+}
+
+/*
+inputs: les noeuds qui deviennent les entrées du graphe, outputs: ses sorties.
+param_names: un nom par param de la trace, dans l'ordre de tr.params_id().
+seuls les noeuds dont dépendent les sorties sont exportés; une op sans équivalent onnx (apply quelconque,
+gradients tracés, losses...) fait échouer l'export avec la liste des ops concernées.
+*/
+pub fn export_trace(tr: &Trace, inputs: &[NodeId], outputs: &[NodeId], param_names: &[String]) -> Result<Vec<u8>, String>{
+    let params = tr.params_id();
+    if param_names.len() != params.len(){
+        return Err(format!("onnx: {} noms pour {} params", param_names.len(), params.len()));
+    }
+
+    // entrées et params déclarés sont toujours émis, meme sans chemin vers une sortie: le modèle réimporté garde la meme arité
+    let mut needed = vec![false; tr.len()];
+    for &id in inputs.iter().chain(params){
+        needed[id] = true;
+    }
+    for &o in outputs{
+        for id in tr.order(o){
+            needed[id] = true;
+        }
+    }
+
+    let mut names: Vec<String> = (0..tr.len()).map(|id| format!("n{id}")).collect();
+    for (i, &id) in inputs.iter().enumerate(){
+        names[id] = format!("input{i}");
+    }
+    for (&id, n) in params.iter().zip(param_names){
+        names[id] = n.clone();
+    }
+    // les sorties calculées gardent leur nom de sortie, les autres (entrée ou param renvoyé tel quel) passent par un Identity
+    let mut aliased = Vec::new();
+    for (i, &id) in outputs.iter().enumerate(){
+        let is_leaf = matches!(tr.node(id).op, OpKind::Input | OpKind::Param);
+        if is_leaf || names[id].starts_with("output"){
+            aliased.push((id, format!("output{i}")));
+        }else{
+            names[id] = format!("output{i}");
+        }
+    }
+
+    let mut g = Graph::default();
+    let mut graph_inputs = Vec::new();
+    let mut unsupported: Vec<&'static str> = Vec::new();
+    for id in (0..tr.len()).filter(|&id| needed[id]){
+        match tr.node(id).op{
+            OpKind::Input if inputs.contains(&id) => graph_inputs.push(value_info(&names[id], &tr.get_tensor(id).shape)),
+            OpKind::Input => {
+                names[id] = format!("const{id}");
+                g.initializers.push(float_tensor(&names[id], tr.get_tensor(id)));
+            }
+            OpKind::Param => g.initializers.push(float_tensor(&names[id], tr.get_tensor(id))),
+            _ => {
+                let ins: Vec<&str> = tr.node(id).parents_id.iter().map(|&p| names[p].as_str()).collect();
+                if export_node(&mut g, tr, id, &ins, &names[id]).is_err() && !unsupported.contains(&tr.node(id).op.name()){
+                    unsupported.push(tr.node(id).op.name());
+                }
+            }
+        }
+    }
+    if !unsupported.is_empty(){
+        return Err(format!("onnx: ops non supportées à l'export: {}", unsupported.join(", ")));
+    }
+    for (id, out) in &aliased{
+        g.node("Identity", out, &[&names[*id]], &[out], &[]);
+    }
+
+    let mut graph = Encoder::new();
+    for n in &g.nodes{
+        graph.message(1, n);
+    }
+    graph.string(2, "lamp");
+    for init in &g.initializers{
+        graph.message(5, init);
+    }
+    for vi in &graph_inputs{
+        graph.message(11, vi);
+    }
+    for (i, &id) in outputs.iter().enumerate(){
+        graph.message(12, &value_info(&format!("output{i}"), &tr.get_tensor(id).shape));
+    }
+
+    let mut opset = Encoder::new();
+    opset.string(1, "");
+    opset.int64(2, OPSET);
+
+    let mut model = Encoder::new();
+    model.varint(1, IR_VERSION);
+    model.string(2, "lamp");
+    model.message(7, &graph);
+    model.message(8, &opset);
+    Ok(model.buf)
+}
+
+/*
+export d'un modèle à partir de sa fonction forward, comme pour inference:
+build(tr, params ids, inputs ids) -> logits. les params sont nommés par leur chemin dans l'arbre ("layer0.w"...).
+*/
+pub fn export_onnx(
+    params: &ParamTree,
+    example_inputs: &[Tensor],
+    build: impl FnOnce(&mut Trace, &[NodeId], &[NodeId]) -> NodeId,
+) -> Result<Vec<u8>, String>{
+    let mut tr = Trace::no_grad();
+    let pids = get_params_id(&mut tr, &params.leaves());
+    let xids: Vec<NodeId> = example_inputs.iter().map(|x| tr.input(x.clone())).collect();
+    let out = build(&mut tr, &pids, &xids);
+    export_trace(&tr, &xids, &[out], &params.paths())
+}
+
+pub fn save_onnx(
+    path: impl AsRef<Path>,
+    params: &ParamTree,
+    example_inputs: &[Tensor],
+    build: impl FnOnce(&mut Trace, &[NodeId], &[NodeId]) -> NodeId,
+) -> Result<(), String>{
+    let bytes = export_onnx(params, example_inputs, build)?;
+    fs::write(path.as_ref(), bytes).map_err(|e| format!("onnx: écriture de {:?}: {e}", path.as_ref()))
+}
+
+/*
+import: le graphe onnx est validé et converti une fois pour toutes en une liste d'ops lamp (OpKind),
+rejouée sur une trace par forward. les initializers float deviennent des params (nommés comme dans le fichier),
+les initializers entiers (axes, shapes) sont lus à l'import.
+
+    let model = load_onnx("mlp.onnx")?;
+    let logits = inference(&model.params, |tr, pids| {
+        let x = tr.input(x.clone());
+        model.forward(tr, pids, &[x]).unwrap()[0]
+    });
+*/
+pub struct OnnxModel{
+    pub params: ParamTree,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    consts: Vec<(String, Tensor)>, // noeuds Constant float: entrées fixes, pas des params
+    steps: Vec<Step>,
+}
+
+struct Step{
+    op: StepOp,
+    inputs: Vec<String>,
+    output: String,
+}
+
+enum StepOp{
+    Lamp(OpKind),
+    // paramètres onnx bruts, qui dépendent de la shape d'entrée: résolus au forward
+    Softmax(i64),
+    Reshape(Vec<i64>),
+    Expand(Vec<i64>),
+    Flatten(i64),
+}
+
+// Tensor.DataType lus en plus de FLOAT et INT64
+const INT32: u64 = 6;
+const DOUBLE: u64 = 11;
+
+enum Data{
+    Float(Tensor),
+    Int(Vec<i64>),
+}
+
+fn parse_tensor(bytes: &[u8]) -> Result<(String, Data), String>{
+    let m = Message::parse(bytes)?;
+    let name = m.string(8)?.unwrap_or("").to_string();
+    if m.varint(14) == Some(1){
+        return Err(format!("onnx: '{name}': données externes non supportées"));
+    }
+    let dims = m.repeated_int64(1)?;
+    let shape: Vec<usize> = dims.iter().map(|&d| usize::try_from(d).map_err(|_| format!("onnx: '{name}': dimension {d} invalide"))).collect::<Result<_, _>>()?;
+    let raw = m.bytes(9);
+    let data_type = m.varint(2).unwrap_or(0);
+
+    let data = match (data_type, raw){
+        (FLOAT, Some(raw)) => Data::Float(Tensor::from_owned(raw.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap())).collect(), &shape)?),
+        (FLOAT, None) => Data::Float(Tensor::from_owned(m.repeated_float(4)?, &shape)?),
+        (DOUBLE, Some(raw)) => Data::Float(Tensor::from_owned(raw.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap()) as f32).collect(), &shape)?),
+        (DOUBLE, None) => Data::Float(Tensor::from_owned(m.repeated_double(10)?.into_iter().map(|x| x as f32).collect(), &shape)?),
+        (INT64, Some(raw)) => Data::Int(raw.chunks_exact(8).map(|c| i64::from_le_bytes(c.try_into().unwrap())).collect()),
+        (INT64, None) => Data::Int(m.repeated_int64(7)?),
+        (INT32, Some(raw)) => Data::Int(raw.chunks_exact(4).map(|c| i32::from_le_bytes(c.try_into().unwrap()) as i64).collect()),
+        (INT32, None) => Data::Int(m.repeated_int64(5)?),
+        (t, _) => return Err(format!("onnx: '{name}': type de données {t} non supporté (float, double, int32, int64)")),
+    };
+    if let Data::Int(v) = &data{
+        if v.len() != shape.iter().product::<usize>(){
+            return Err(format!("onnx: '{name}': {} valeurs pour la shape {shape:?}", v.len()));
+        }
+    }
+    Ok((name, data))
+}
+
+enum AttrValue<'a>{
+    Int(i64),
+    Ints(Vec<i64>),
+    Float(f32),
+    Str(&'a str),
+    Tensor(&'a [u8]),
+    Other,
+}
+
+// attributs d'un noeud, avec les valeurs par défaut de la spec à la lecture
+struct Attrs<'a>(Vec<(&'a str, AttrValue<'a>)>);
+
+impl<'a> Attrs<'a>{
+    fn parse(node: &Message<'a>) -> Result<Attrs<'a>, String>{
+        let mut attrs = Vec::new();
+        for bytes in node.repeated_bytes(5){
+            let a = Message::parse(bytes)?;
+            let name = a.string(1)?.unwrap_or("");
+            // le champ type n'est pas toujours rempli par les vieux exporteurs: on regarde ce qui est présent
+            let v = match a.varint(20){
+                Some(1) => AttrValue::Float(a.float(2).unwrap_or(0.0)),
+                Some(2) => AttrValue::Int(a.int64(3).unwrap_or(0)),
+                Some(3) => AttrValue::Str(a.string(4)?.unwrap_or("")),
+                Some(4) => AttrValue::Tensor(a.bytes(5).unwrap_or(&[])),
+                Some(7) => AttrValue::Ints(a.repeated_int64(8)?),
+                Some(_) => AttrValue::Other,
+                None if a.float(2).is_some() => AttrValue::Float(a.float(2).unwrap()),
+                None if a.int64(3).is_some() => AttrValue::Int(a.int64(3).unwrap()),
+                None if a.bytes(5).is_some() => AttrValue::Tensor(a.bytes(5).unwrap()),
+                None if a.bytes(4).is_some() => AttrValue::Str(a.string(4)?.unwrap()),
+                None => AttrValue::Ints(a.repeated_int64(8)?),
+            };
+            attrs.push((name, v));
+        }
+        Ok(Attrs(attrs))
+    }
+
+    fn get(&self, name: &str) -> Option<&AttrValue<'a>>{
+        self.0.iter().find(|(n, _)| *n == name).map(|(_, v)| v)
+    }
+
+    fn int(&self, name: &str, default: i64) -> i64{
+        match self.get(name) {Some(AttrValue::Int(i)) => *i, _ => default}
+    }
+
+    fn float(&self, name: &str, default: f32) -> f32{
+        match self.get(name) {Some(AttrValue::Float(f)) => *f, _ => default}
+    }
+
+    fn ints(&self, name: &str) -> Option<&[i64]>{
+        match self.get(name) {Some(AttrValue::Ints(v)) => Some(v), _ => None}
+    }
+
+    fn str(&self, name: &str) -> Option<&'a str>{
+        match self.get(name) {Some(AttrValue::Str(s)) => Some(s), _ => None}
+    }
+}
+
+fn to_pair(v: &[i64]) -> Option<(usize, usize)>{
+    match v{
+        [a, b] if *a >= 0 && *b >= 0 => Some((*a as usize, *b as usize)),
+        _ => None,
+    }
+}
+
+// pads onnx [haut, gauche, bas, droite] => padding symétrique de lamp
+fn to_padding(pads: Option<&[i64]>) -> Option<(usize, usize)>{
+    match pads{
+        None => Some((0, 0)),
+        Some([t, l, b, r]) if t == b && l == r => to_pair(&[*t, *l]),
+        Some(_) => None,
+    }
+}
+
+// paramètres d'un pooling 2d; None si le noeud utilise une option qu'on n'a pas
+// [kernel, stride, padding]
+fn pool_params(a: &Attrs) -> Option<[(usize, usize); 3]>{
+    let no_dilation = a.ints("dilations").is_none_or(|d| d.iter().all(|&x| x == 1));
+    let auto_pad = a.str("auto_pad").unwrap_or("NOTSET");
+    if a.int("ceil_mode", 0) != 0 || !no_dilation || auto_pad != "NOTSET"{
+        return None;
+    }
+    let kernel = to_pair(a.ints("kernel_shape")?)?;
+    let stride = match a.ints("strides") {Some(s) => to_pair(s)?, None => (1, 1)};
+    Some([kernel, stride, to_padding(a.ints("pads"))?])
+}
+
+struct Importer{
+    steps: Vec<Step>,
+    ints: Vec<(String, Vec<i64>)>,
+    // Identity/Dropout: la sortie est un autre nom pour l'entrée
+    aliases: Vec<(String, String)>,
+    opset: i64,
+}
+
+impl Importer{
+    fn resolve(&self, name: &str) -> String{
+        match self.aliases.iter().find(|(a, _)| a == name){
+            Some((_, target)) => target.clone(),
+            None => name.to_string(),
+        }
+    }
+
+    fn step(&mut self, op: OpKind, inputs: &[&str], output: String) -> String{
+        self.step_op(StepOp::Lamp(op), inputs, output)
+    }
+
+    fn step_op(&mut self, op: StepOp, inputs: &[&str], output: String) -> String{
+        let inputs = inputs.iter().map(|i| self.resolve(i)).collect();
+        self.steps.push(Step { op, inputs, output: output.clone() });
+        output
+    }
+
+    // entrée entière constante (axes, shape) des ops opset 13
+    fn const_ints(&self, name: Option<&&str>) -> Option<Vec<i64>>{
+        let name = self.resolve(name?);
+        self.ints.iter().find(|(n, _)| *n == name).map(|(_, v)| v.clone())
+    }
+
+    // convertit un noeud onnx en une ou plusieurs ops; Err => nom affiché dans la liste des ops non supportées
+    fn node(&mut self, op_type: &str, ins: &[&str], out: &str, a: &Attrs) -> Result<(), String>{
+        let unsupported = |detail: &str| Err(format!("{op_type}({detail})"));
+        let out = out.to_string();
+        // entrées optionnelles comprises
+        let arity = match op_type{
+            "Add" | "Sub" | "Mul" | "MatMul" => 2..=2,
+            "Relu" | "Tanh" | "Softmax" | "Flatten" | "Transpose" | "GlobalAveragePool" | "MaxPool" | "AveragePool" => 1..=1,
+            "Gemm" | "Conv" => 2..=3,
+            _ => 1..=usize::MAX,
+        };
+        if !arity.contains(&ins.len()){
+            return unsupported(&format!("{} entrées", ins.len()));
+        }
+        match op_type{
+            "Add" | "Sub" | "Mul" | "MatMul" | "Relu" | "Tanh" => {
+                let op = match op_type {"Add" => OpKind::Add, "Sub" => OpKind::Sub, "Mul" => OpKind::Mul, "MatMul" => OpKind::MatMul, "Relu" => OpKind::Relu, _ => OpKind::Tanh};
+                self.step(op, ins, out);
+            }
+            "Identity" | "Dropout" => {
+                let target = self.resolve(ins[0]);
+                self.aliases.push((out, target));
+            }
+            "Softmax" => {
+                // avant l'opset 13 l'axe par défaut est 1
+                let axis = a.int("axis", if self.opset < 13 {1} else {-1});
+                self.step_op(StepOp::Softmax(axis), ins, out);
+            }
+            "Flatten" => {self.step_op(StepOp::Flatten(a.int("axis", 1)), ins, out);}
+            // y = alpha * A' B' + beta * C
+            "Gemm" => {
+                let mut x = ins[0].to_string();
+                let mut w = ins[1].to_string();
+                if a.int("transA", 0) != 0{
+                    x = self.step(OpKind::MatTranspose, &[&x], format!("{out}#a"));
+                }
+                if a.int("transB", 0) != 0{
+                    w = self.step(OpKind::MatTranspose, &[&w], format!("{out}#b"));
+                }
+                let (alpha, beta) = (a.float("alpha", 1.0), a.float("beta", 1.0));
+                let mut y = self.step(OpKind::MatMul, &[&x, &w], format!("{out}#mm"));
+                if alpha != 1.0{
+                    y = self.step(OpKind::Scale(alpha), &[&y], format!("{out}#alpha"));
+                }
+                if let Some(c) = ins.get(2).filter(|c| !c.is_empty()){
+                    let mut c = c.to_string();
+                    if beta != 1.0{
+                        c = self.step(OpKind::Scale(beta), &[&c], format!("{out}#beta"));
+                    }
+                    y = self.step(OpKind::Add, &[&y, &c], format!("{out}#c"));
+                }
+                self.aliases.push((out, y));
+            }
+            "Conv" => {
+                if a.int("group", 1) != 1{
+                    return unsupported("group != 1");
+                }
+                if a.str("auto_pad").unwrap_or("NOTSET") != "NOTSET"{
+                    return unsupported("auto_pad");
+                }
+                let pair_or = |name: &str| match a.ints(name) {Some(v) => to_pair(v), None => Some((1, 1))};
+                let (Some(stride), Some(dilation), Some(padding)) = (pair_or("strides"), pair_or("dilations"), to_padding(a.ints("pads"))) else {
+                    return unsupported("pads asymétriques ou pas 2d");
+                };
+                let opts = Conv2dOpts { stride, padding, dilation };
+                match ins.get(2).filter(|b| !b.is_empty()){
+                    None => {self.step(OpKind::Conv2d(opts), &ins[..2], out);}
+                    // biais (C) => (C, 1, 1) pour le broadcast sur (N, C, H, W)
+                    Some(b) => {
+                        let y = self.step(OpKind::Conv2d(opts), &ins[..2], format!("{out}#conv"));
+                        let b1 = self.step(OpKind::Unsqueeze(1), &[b], format!("{out}#b1"));
+                        let b2 = self.step(OpKind::Unsqueeze(2), &[&b1], format!("{out}#b2"));
+                        self.step(OpKind::Add, &[&y, &b2], out);
+                    }
+                }
+            }
+            "MaxPool" => {
+                let Some([kernel, stride, padding]) = pool_params(a) else {return unsupported("ceil_mode, dilations ou pads")};
+                if a.int("storage_order", 0) != 0{
+                    return unsupported("storage_order");
+                }
+                self.step(OpKind::MaxPool2d { kernel, stride, padding }, ins, out);
+            }
+            "AveragePool" => {
+                let Some([kernel, stride, padding]) = pool_params(a) else {return unsupported("ceil_mode, dilations ou pads")};
+                // lamp divise toujours par la taille du noyau
+                if padding != (0, 0) && a.int("count_include_pad", 0) == 0{
+                    return unsupported("count_include_pad=0 avec padding");
+                }
+                self.step(OpKind::AvgPool2d { kernel, stride, padding }, ins, out);
+            }
+            "GlobalAveragePool" => {self.step(OpKind::GlobalAvgPool2d, ins, out);}
+            "ReduceMean" | "ReduceSum" | "ReduceMax" => {
+                // axes: attribut avant l'opset 13 (18 pour ReduceMean et ReduceMax), entrée après
+                let axes = a.ints("axes").map(|v| v.to_vec()).or_else(|| self.const_ints(ins.get(1)));
+                if ins.len() > 1 && axes.is_none(){
+                    return unsupported("axes non constants");
+                }
+                let keepdims = a.int("keepdims", 1) != 0;
+                let op = match (op_type, axes.as_deref(), keepdims){
+                    ("ReduceMean", None | Some([]), false) => OpKind::MeanAll,
+                    ("ReduceSum", None | Some([]), false) => OpKind::SumAll,
+                    ("ReduceSum", Some([-1]), true) => OpKind::SumLast,
+                    // axes explicites: sum/mean/max sur des axes quelconques, sans négatifs (le rang n'est pas connu ici)
+                    (_, Some(ax), keepdim) if !ax.is_empty() && ax.iter().all(|&x| x >= 0) => {
+                        let mut axes: Vec<usize> = ax.iter().map(|&x| x as usize).collect();
+                        axes.sort_unstable();
+                        axes.dedup();
+                        match op_type{
+                            "ReduceSum" => OpKind::Sum { axes, keepdim },
+                            "ReduceMean" => OpKind::Mean { axes, keepdim },
+                            _ => OpKind::Max { axes, keepdim },
+                        }
+                    }
+                    _ => return unsupported(&format!("axes={axes:?}, keepdims={keepdims}")),
+                };
+                self.step(op, &ins[..1], out);
+            }
+            "Transpose" => {
+                // sans perm, onnx inverse les axes: il faudrait connaitre le rang
+                let Some(perm) = a.ints("perm") else {return unsupported("perm absent")};
+                let mut sorted = perm.to_vec();
+                sorted.sort_unstable();
+                if sorted.iter().enumerate().any(|(i, &p)| p != i as i64){
+                    return unsupported(&format!("perm={perm:?}"));
+                }
+                self.step(OpKind::Permute(perm.iter().map(|&p| p as usize).collect()), ins, out);
+            }
+            "Reshape" => {
+                // 0 (copie de l'axe) et -1 (déduit) sont résolus au forward, avec la shape d'entrée
+                let Some(shape) = self.const_ints(ins.get(1)) else {return unsupported("shape non constante")};
+                if a.int("allowzero", 0) != 0 && shape.contains(&0){
+                    return unsupported("allowzero");
+                }
+                if shape.iter().any(|&d| d < -1) || shape.iter().filter(|&&d| d == -1).count() > 1{
+                    return unsupported(&format!("shape={shape:?}"));
+                }
+                self.step_op(StepOp::Reshape(shape), &ins[..1], out);
+            }
+            "Unsqueeze" | "Squeeze" => {
+                let Some(mut axes) = a.ints("axes").map(|v| v.to_vec()).or_else(|| self.const_ints(ins.get(1))) else {
+                    return unsupported("axes non constants");
+                };
+                if axes.iter().any(|&x| x < 0){
+                    return unsupported("axes négatifs");
+                }
+                // unsqueeze dans l'ordre croissant, squeeze dans l'ordre décroissant: les indices restent valides
+                axes.sort_unstable();
+                if op_type == "Squeeze"{
+                    axes.reverse();
+                }
+                let mut x = ins[0].to_string();
+                for (k, &axis) in axes.iter().enumerate(){
+                    let op = if op_type == "Unsqueeze" {OpKind::Unsqueeze(axis as usize)} else {OpKind::Squeeze(axis as usize)};
+                    let name = if k + 1 == axes.len() {out.clone()} else {format!("{out}#{k}")};
+                    x = self.step(op, &[&x], name);
+                }
+            }
+            "Expand" => {
+                let Some(shape) = self.const_ints(ins.get(1)) else {return unsupported("shape non constante")};
+                if shape.iter().any(|&d| d < 0){
+                    return unsupported(&format!("shape={shape:?}"));
+                }
+                self.step_op(StepOp::Expand(shape), &ins[..1], out);
+            }
+            _ => return Err(op_type.to_string()),
+        }
+        Ok(())
+    }
+}
+
+pub fn import_onnx(bytes: &[u8]) -> Result<OnnxModel, String>{
+    let model = Message::parse(bytes)?;
+    let graph = Message::parse(model.bytes(7).ok_or("onnx: pas de graphe")?)?;
+
+    let mut opset = OPSET;
+    for o in model.repeated_bytes(8){
+        let o = Message::parse(o)?;
+        if matches!(o.string(1)?, None | Some("") | Some("ai.onnx")){
+            opset = o.int64(2).unwrap_or(OPSET);
+        }
+    }
+
+    let mut imp = Importer { steps: Vec::new(), ints: Vec::new(), aliases: Vec::new(), opset };
+    let mut named_params = Vec::new();
+    for t in graph.repeated_bytes(5){
+        match parse_tensor(t)?{
+            (name, Data::Float(t)) => named_params.push((name, t)),
+            (name, Data::Int(v)) => imp.ints.push((name, v)),
+        }
+    }
+    let params = ParamTree::from_named(named_params.clone()).map_err(|e| format!("onnx: noms d'initializers: {e}"))?;
+
+    // les vieux exporteurs listent aussi les initializers dans les entrées
+    let is_init = |n: &str| named_params.iter().any(|(p, _)| p == n) || imp.ints.iter().any(|(p, _)| p == n);
+    let mut inputs = Vec::new();
+    for vi in graph.repeated_bytes(11){
+        let name = Message::parse(vi)?.string(1)?.unwrap_or("").to_string();
+        if !is_init(&name){
+            inputs.push(name);
+        }
+    }
+
+    let mut consts = Vec::new();
+    let mut unsupported: Vec<String> = Vec::new();
+    for n in graph.repeated_bytes(1){
+        let node = Message::parse(n)?;
+        let op_type = node.string(4)?.unwrap_or("");
+        let domain = node.string(7)?.unwrap_or("");
+        let ins = node.repeated_strings(1)?;
+        let outs = node.repeated_strings(2)?;
+        let attrs = Attrs::parse(&node)?;
+
+        // Dropout peut avoir une sortie masque, qu'on ignore tant que personne ne la lit
+        let res = if !domain.is_empty() && domain != "ai.onnx"{
+            Err(format!("{domain}.{op_type}"))
+        }else if op_type == "Constant"{
+            match attrs.get("value"){
+                Some(AttrValue::Tensor(t)) => match parse_tensor(t)?{
+                    (_, Data::Float(t)) => {consts.push((outs[0].to_string(), t)); Ok(())}
+                    (_, Data::Int(v)) => {imp.ints.push((outs[0].to_string(), v)); Ok(())}
+                },
+                _ => Err("Constant(value non tensorielle)".to_string()),
+            }
+        }else if outs.len() != 1 && op_type != "Dropout"{
+            Err(format!("{op_type}({} sorties)", outs.len()))
+        }else if outs.is_empty() || (ins.is_empty() && op_type != "Constant"){
+            Err(format!("{op_type}(sans entrée ou sortie)"))
+        }else{
+            imp.node(op_type, &ins, outs[0], &attrs)
+        };
+        match res{
+            Err(name) if !unsupported.contains(&name) => unsupported.push(name),
+            _ => {}
+        }
+    }
+    if !unsupported.is_empty(){
+        return Err(format!("onnx: ops non supportées à l'import: {}", unsupported.join(", ")));
+    }
+
+    let mut outputs = Vec::new();
+    for vi in graph.repeated_bytes(12){
+        outputs.push(imp.resolve(Message::parse(vi)?.string(1)?.unwrap_or("")));
+    }
+
+    Ok(OnnxModel { params, inputs, outputs, consts, steps: imp.steps })
+}
+
+pub fn load_onnx(path: impl AsRef<Path>) -> Result<OnnxModel, String>{
+    let bytes = fs::read(path.as_ref()).map_err(|e| format!("onnx: lecture de {:?}: {e}", path.as_ref()))?;
+    import_onnx(&bytes)
+}
+
+impl OnnxModel{
+    /*
+    rejoue le graphe sur la trace. pids: les params dans l'ordre de self.params (comme passés par value_and_grad/inference),
+    inputs: un noeud par entrée du graphe, dans l'ordre de self.inputs. renvoie un noeud par sortie.
+    */
+    pub fn forward(&self, tr: &mut Trace, pids: &[NodeId], inputs: &[NodeId]) -> Result<Vec<NodeId>, String>{
+        if pids.len() != self.params.len() || inputs.len() != self.inputs.len(){
+            return Err(format!("onnx: forward avec {} params et {} entrées, le modèle en attend {} et {}",
+                pids.len(), inputs.len(), self.params.len(), self.inputs.len()));
+        }
+        let mut env: Vec<(String, NodeId)> = self.params.paths().into_iter().zip(pids.iter().copied()).collect();
+        env.extend(self.inputs.iter().cloned().zip(inputs.iter().copied()));
+        for (name, t) in &self.consts{
+            env.push((name.clone(), tr.input(t.clone())));
+        }
+
+        fn lookup(env: &[(String, NodeId)], name: &str) -> Result<NodeId, String>{
+            // la dernière définition gagne
+            env.iter().rev().find(|(n, _)| n == name).map(|(_, id)| *id).ok_or_else(|| format!("onnx: '{name}' utilisé avant d'etre défini"))
+        }
+
+        for s in &self.steps{
+            let ins: Vec<NodeId> = s.inputs.iter().map(|n| lookup(&env, n)).collect::<Result<_, _>>()?;
+            let y = match &s.op{
+                StepOp::Lamp(op) => run_op(tr, op, &ins),
+                op => run_shaped(tr, op, ins[0])?,
+            };
+            env.push((s.output.clone(), y));
+        }
+        self.outputs.iter().map(|o| lookup(&env, o)).collect()
+    }
+}
+
+// ops onnx dont les paramètres se lisent avec la shape de x; Err si elle ne convient pas
+fn run_shaped(tr: &mut Trace, op: &StepOp, x: NodeId) -> Result<NodeId, String>{
+    let x_shape = tr.get_tensor(x).shape.clone();
+    let rank = x_shape.len() as i64;
+    match op{
+        StepOp::Softmax(axis) => {
+            // axe négatif: compté depuis la fin
+            if !(-rank..rank).contains(axis) || axis.rem_euclid(rank) != rank - 1{
+                return Err(format!("onnx: Softmax sur l'axe {axis} d'un tenseur de rang {rank}: seul le dernier axe est supporté"));
+            }
+            Ok(functions::softmax(tr, x))
+        }
+        StepOp::Reshape(shape) => {
+            // 0 => axe de meme indice de l'entrée, -1 => ce qu'il reste
+            let mut dims = Vec::with_capacity(shape.len());
+            for (i, &d) in shape.iter().enumerate(){
+                dims.push(match d{
+                    0 => *x_shape.get(i).ok_or_else(|| format!("onnx: Reshape {shape:?}: pas d'axe {i} à copier dans {x_shape:?}"))?,
+                    -1 => 1,
+                    d => d as usize,
+                });
+            }
+            let numel: usize = x_shape.iter().product();
+            if let Some(k) = shape.iter().position(|&d| d == -1){
+                let known: usize = dims.iter().product();
+                if known == 0 || !numel.is_multiple_of(known){
+                    return Err(format!("onnx: Reshape {x_shape:?} => {shape:?}: -1 ne se déduit pas"));
+                }
+                dims[k] = numel / known;
+            }
+            if dims.iter().product::<usize>() != numel{
+                return Err(format!("onnx: Reshape {x_shape:?} => {dims:?}: nombre d'éléments différent"));
+            }
+            Ok(ops::reshape(tr, x, &dims))
+        }
+        StepOp::Expand(shape) => {
+            // broadcast dans les deux sens: un 1 de la shape garde l'axe de x
+            let shape: Vec<usize> = shape.iter().map(|&d| d as usize).collect();
+            let target = Tensor::broadcast_shape(&x_shape, &shape).map_err(|_| format!("onnx: Expand {x_shape:?} vers {shape:?}: shapes incompatibles"))?;
+            Ok(ops::broadcast_to(tr, x, &target))
+        }
+        StepOp::Flatten(axis) => {
+            // (d0..d_axis-1, d_axis..) => 2 axes; un groupe vide devient un axe de taille 1
+            if !(-rank..=rank).contains(axis){
+                return Err(format!("onnx: Flatten sur l'axe {axis} d'un tenseur de rang {rank}"));
+            }
+            let (mut a, mut r) = ((if *axis < 0 {axis + rank} else {*axis}) as usize, rank as usize);
+            let mut y = x;
+            if a == 0{
+                y = ops::unsqueeze(tr, y, 0);
+                (a, r) = (1, r + 1);
+            }
+            if a == r{
+                y = ops::unsqueeze(tr, y, r);
+                r += 1;
+            }
+            let y = ops::flatten(tr, y, a, r - 1);
+            Ok(ops::flatten(tr, y, 0, a - 1))
+        }
+        StepOp::Lamp(_) => unreachable!(),
+    }
+}
+
+// appelle l'op lamp correspondante (seulement les kinds produits par l'import)
+fn run_op(tr: &mut Trace, op: &OpKind, ins: &[NodeId]) -> NodeId{
+    match op{
+        OpKind::Add => ops::add(tr, ins[0], ins[1]),
+        OpKind::Sub => ops::sub(tr, ins[0], ins[1]),
+        OpKind::Mul => ops::hadamard_mul(tr, ins[0], ins[1]),
+        OpKind::MatMul => ops::matmul(tr, ins[0], ins[1]),
+        OpKind::Scale(c) => ops::scale(tr, ins[0], *c),
+        OpKind::Relu => functions::relu(tr, ins[0]),
+        OpKind::Tanh => functions::tanh(tr, ins[0]),
+        OpKind::Softmax => functions::softmax(tr, ins[0]),
+        OpKind::MeanAll => ops::mean_all(tr, ins[0]),
+        OpKind::SumAll => ops::sum_all(tr, ins[0]),
+        OpKind::SumLast => ops::sum_last(tr, ins[0]),
+        OpKind::Sum{axes, keepdim} => ops::sum(tr, ins[0], axes, *keepdim),
+        OpKind::Mean{axes, keepdim} => ops::mean(tr, ins[0], axes, *keepdim),
+        OpKind::Max{axes, keepdim} => ops::max(tr, ins[0], axes, *keepdim),
+        OpKind::BroadcastTo(shape) => ops::broadcast_to(tr, ins[0], shape),
+        OpKind::MatTranspose => ops::mat_transpose(tr, ins[0]),
+        OpKind::Unsqueeze(axis) => ops::unsqueeze(tr, ins[0], *axis),
+        OpKind::Squeeze(axis) => ops::squeeze(tr, ins[0], *axis),
+        OpKind::Permute(perm) => ops::permute(tr, ins[0], perm),
+        OpKind::Reshape(shape) => ops::reshape(tr, ins[0], shape),
+        OpKind::Conv2d(opts) => ops::conv2d(tr, ins[0], ins[1], *opts),
+        OpKind::MaxPool2d{kernel, stride, padding} => ops::max_pool2d(tr, ins[0], *kernel, *stride, *padding),
+        OpKind::AvgPool2d{kernel, stride, padding} => ops::avg_pool2d(tr, ins[0], *kernel, *stride, *padding),
+        OpKind::GlobalAvgPool2d => ops::global_avg_pool2d(tr, ins[0]),
+        _ => unreachable!("onnx: {} n'est pas produit par l'import", op.name()),
+    }
+}
+
+// graphes écrits à la main (le protobuf n'est pas public): les ops dont les paramètres dépendent de la shape d'entrée
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // une entrée "x", une sortie "y", opset 13
+    fn model(g: &Graph, x_shape: &[usize]) -> Vec<u8>{
+        let mut graph = Encoder::new();
+        for n in &g.nodes{
+            graph.message(1, n);
+        }
+        for init in &g.initializers{
+            graph.message(5, init);
+        }
+        graph.message(11, &value_info("x", x_shape));
+        graph.message(12, &value_info("y", &[]));
+        let mut opset = Encoder::new();
+        opset.int64(2, OPSET);
+        let mut m = Encoder::new();
+        m.varint(1, IR_VERSION);
+        m.message(7, &graph);
+        m.message(8, &opset);
+        m.buf
+    }
+
+    fn run(g: &Graph, x: &Tensor) -> Result<Tensor, String>{
+        let onnx = import_onnx(&model(g, &x.shape))?;
+        let mut tr = Trace::no_grad();
+        let xid = tr.input(x.clone());
+        let y = onnx.forward(&mut tr, &[], &[xid])?[0];
+        Ok(tr.get_tensor(y).clone())
+    }
+
+    fn arange(shape: &[usize]) -> Tensor{
+        let n = shape.iter().product::<usize>();
+        Tensor::from_owned((0..n).map(|i| i as f32).collect(), shape).unwrap()
+    }
+
+    fn single(op_type: &str, extra: Option<&[i64]>, attrs: &[(&str, Attr)]) -> Graph{
+        let mut g = Graph::default();
+        match extra{
+            Some(v) => {
+                let c = g.ints("c".into(), v);
+                g.node(op_type, "n", &["x", &c], &["y"], attrs);
+            }
+            None => g.node(op_type, "n", &["x"], &["y"], attrs),
+        }
+        g
+    }
+
+    #[test]
+    fn softmax_negative_axis(){
+        let x = arange(&[2, 3, 4]);
+        let last = run(&single("Softmax", None, &[("axis", Attr::Int(2))]), &x).unwrap();
+        let neg = run(&single("Softmax", None, &[("axis", Attr::Int(-1))]), &x).unwrap();
+        assert_eq!(last.shape, vec![2, 3, 4]);
+        assert_eq!(last.contiguous().data, neg.contiguous().data);
+        for axis in [-2, 0, 3, -4]{
+            let err = run(&single("Softmax", None, &[("axis", Attr::Int(axis))]), &x).unwrap_err();
+            assert!(err.contains("seul le dernier axe"), "{err}");
+        }
+    }
+
+    #[test]
+    fn reshape_zero_and_inferred(){
+        let x = arange(&[2, 3, 4]);
+        let y = run(&single("Reshape", Some(&[0, -1]), &[]), &x).unwrap();
+        assert_eq!(y.shape, vec![2, 12]);
+        assert_eq!(y.contiguous().data, x.data);
+        let y = run(&single("Reshape", Some(&[-1, 0, 2]), &[]), &x).unwrap();
+        assert_eq!(y.shape, vec![4, 3, 2]);
+        assert!(run(&single("Reshape", Some(&[5, -1]), &[]), &x).is_err());
+        assert!(run(&single("Reshape", Some(&[0, 0, 0, 0]), &[]), &x).is_err());
+        // deux -1: refusé à l'import
+        let err = run(&single("Reshape", Some(&[-1, -1]), &[]), &x).unwrap_err();
+        assert!(err.contains("Reshape(shape=[-1, -1])"), "{err}");
+    }
+
+    #[test]
+    fn expand_broadcasts_both_ways(){
+        // (3, 1) et (2, 1, 4) => (2, 3, 4): le 1 de la shape garde l'axe de x
+        let x = arange(&[3, 1]);
+        let y = run(&single("Expand", Some(&[2, 1, 4]), &[]), &x).unwrap();
+        assert_eq!(y.shape, vec![2, 3, 4]);
+        assert_eq!(y.contiguous().data[4..8], [1.0; 4]);
+        let err = run(&single("Expand", Some(&[2, 2]), &[]), &x).unwrap_err();
+        assert!(err.contains("incompatibles"), "{err}");
+        assert!(run(&single("Expand", Some(&[-1, 4]), &[]), &x).unwrap_err().contains("Expand"));
+    }
+
+    #[test]
+    fn flatten_axes(){
+        let x = arange(&[2, 3, 4, 5]);
+        for (axis, shape) in [(1, [2, 60]), (2, [6, 20]), (0, [1, 120]), (4, [120, 1]), (-1, [24, 5])]{
+            let y = run(&single("Flatten", None, &[("axis", Attr::Int(axis))]), &x).unwrap();
+            assert_eq!(y.shape, shape, "axis {axis}");
+            assert_eq!(y.contiguous().data, x.data);
+        }
+        assert!(run(&single("Flatten", None, &[("axis", Attr::Int(5))]), &x).is_err());
+    }
+
+    #[test]
+    fn arity_is_checked(){
+        let x = arange(&[2, 2]);
+        for (op_type, ins) in [("Gemm", vec!["x"]), ("Conv", vec!["x"]), ("Add", vec!["x"]), ("Mul", vec!["x", "x", "x"])]{
+            let mut g = Graph::default();
+            g.node(op_type, "n", &ins, &["y"], &[]);
+            let err = run(&g, &x).unwrap_err();
+            assert!(err.contains(&format!("{op_type}({} entrées)", ins.len())), "{err}");
+        }
+    }
+}
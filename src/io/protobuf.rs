@@ -0,0 +1,192 @@
+/*
+encodage/décodage protobuf minimal, juste ce qu'il faut pour onnx (pas de schéma: on lit et écrit les champs à la main).
+chaque champ = clé varint (numéro << 3 | wire type) puis la valeur:
+    0: varint, 1: 8 octets, 2: longueur varint + octets (string, bytes, sous message), 5: 4 octets (float)
+*/
+
+const VARINT: u64 = 0;
+const LEN: u64 = 2;
+
+#[derive(Default)]
+pub(crate) struct Encoder{
+    pub buf: Vec<u8>,
+}
+
+impl Encoder{
+    pub fn new() -> Encoder{
+        Encoder::default()
+    }
+
+    fn raw_varint(&mut self, mut v: u64){
+        while v >= 0x80{
+            self.buf.push((v as u8 & 0x7f) | 0x80);
+            v >>= 7;
+        }
+        self.buf.push(v as u8);
+    }
+
+    fn key(&mut self, field: u32, wire: u64){
+        self.raw_varint(((field as u64) << 3) | wire);
+    }
+
+    pub fn varint(&mut self, field: u32, v: u64){
+        self.key(field, VARINT);
+        self.raw_varint(v);
+    }
+
+    // int64 négatifs: complément à 2 sur 10 octets, comme le fait protobuf
+    pub fn int64(&mut self, field: u32, v: i64){
+        self.varint(field, v as u64);
+    }
+
+    pub fn bytes(&mut self, field: u32, b: &[u8]){
+        self.key(field, LEN);
+        self.raw_varint(b.len() as u64);
+        self.buf.extend_from_slice(b);
+    }
+
+    pub fn string(&mut self, field: u32, s: &str){
+        self.bytes(field, s.as_bytes());
+    }
+
+    pub fn message(&mut self, field: u32, m: &Encoder){
+        self.bytes(field, &m.buf);
+    }
+}
+
+/*
+décodage: on découpe un message en champs (numéro, valeur) sans rien interpréter,
+c'est l'appelant qui sait quel champ est un sous message, une string, un int...
+*/
+#[derive(Clone, Copy)]
+pub(crate) enum Value<'a>{
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64, String>{
+    let mut v = 0u64;
+    for shift in (0..64).step_by(7){
+        let b = *buf.get(*pos).ok_or("protobuf: varint tronqué")?;
+        *pos += 1;
+        v |= ((b & 0x7f) as u64) << shift;
+        if b < 0x80{
+            return Ok(v);
+        }
+    }
+    Err("protobuf: varint trop long".into())
+}
+
+fn take<'a>(buf: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], String>{
+    let end = pos.checked_add(n).filter(|&e| e <= buf.len()).ok_or("protobuf: champ plus long que le message")?;
+    let s = &buf[*pos..end];
+    *pos = end;
+    Ok(s)
+}
+
+pub(crate) struct Message<'a>{
+    fields: Vec<(u32, Value<'a>)>,
+}
+
+impl<'a> Message<'a>{
+    pub fn parse(buf: &'a [u8]) -> Result<Message<'a>, String>{
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while pos < buf.len(){
+            let key = read_varint(buf, &mut pos)?;
+            let field = (key >> 3) as u32;
+            let v = match key & 7{
+                0 => Value::Varint(read_varint(buf, &mut pos)?),
+                1 => Value::Fixed64(u64::from_le_bytes(take(buf, &mut pos, 8)?.try_into().unwrap())),
+                2 => {
+                    let n = read_varint(buf, &mut pos)? as usize;
+                    Value::Bytes(take(buf, &mut pos, n)?)
+                }
+                5 => Value::Fixed32(u32::from_le_bytes(take(buf, &mut pos, 4)?.try_into().unwrap())),
+                w => return Err(format!("protobuf: wire type {w} non supporté (champ {field})")),
+            };
+            fields.push((field, v));
+        }
+        Ok(Message { fields })
+    }
+
+    fn all(&self, field: u32) -> impl Iterator<Item = Value<'a>> + '_{
+        self.fields.iter().filter(move |(f, _)| *f == field).map(|(_, v)| *v)
+    }
+
+    // champ non répété: la dernière occurrence gagne, comme en protobuf
+    pub fn varint(&self, field: u32) -> Option<u64>{
+        self.all(field).filter_map(|v| match v {Value::Varint(x) => Some(x), _ => None}).last()
+    }
+
+    pub fn int64(&self, field: u32) -> Option<i64>{
+        self.varint(field).map(|v| v as i64)
+    }
+
+    pub fn float(&self, field: u32) -> Option<f32>{
+        self.all(field).filter_map(|v| match v {Value::Fixed32(x) => Some(f32::from_bits(x)), _ => None}).last()
+    }
+
+    pub fn bytes(&self, field: u32) -> Option<&'a [u8]>{
+        self.repeated_bytes(field).last()
+    }
+
+    pub fn string(&self, field: u32) -> Result<Option<&'a str>, String>{
+        self.bytes(field).map(|b| std::str::from_utf8(b).map_err(|e| format!("protobuf: string non utf8 (champ {field}): {e}"))).transpose()
+    }
+
+    // strings et sous messages répétés
+    pub fn repeated_bytes(&self, field: u32) -> impl Iterator<Item = &'a [u8]> + '_{
+        self.all(field).filter_map(|v| match v {Value::Bytes(b) => Some(b), _ => None})
+    }
+
+    pub fn repeated_strings(&self, field: u32) -> Result<Vec<&'a str>, String>{
+        self.repeated_bytes(field)
+            .map(|b| std::str::from_utf8(b).map_err(|e| format!("protobuf: string non utf8 (champ {field}): {e}")))
+            .collect()
+    }
+
+    // entiers répétés, packés (un seul champ bytes) ou non
+    pub fn repeated_int64(&self, field: u32) -> Result<Vec<i64>, String>{
+        let mut out = Vec::new();
+        for v in self.all(field){
+            match v{
+                Value::Varint(x) => out.push(x as i64),
+                Value::Bytes(b) => {
+                    let mut pos = 0;
+                    while pos < b.len(){
+                        out.push(read_varint(b, &mut pos)? as i64);
+                    }
+                }
+                _ => return Err(format!("protobuf: champ {field}: entiers attendus")),
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn repeated_float(&self, field: u32) -> Result<Vec<f32>, String>{
+        let mut out = Vec::new();
+        for v in self.all(field){
+            match v{
+                Value::Fixed32(x) => out.push(f32::from_bits(x)),
+                Value::Bytes(b) if b.len().is_multiple_of(4) => out.extend(b.chunks_exact(4).map(|c| f32::from_le_bytes(c.try_into().unwrap()))),
+                _ => return Err(format!("protobuf: champ {field}: floats attendus")),
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn repeated_double(&self, field: u32) -> Result<Vec<f64>, String>{
+        let mut out = Vec::new();
+        for v in self.all(field){
+            match v{
+                Value::Fixed64(x) => out.push(f64::from_bits(x)),
+                Value::Bytes(b) if b.len().is_multiple_of(8) => out.extend(b.chunks_exact(8).map(|c| f64::from_le_bytes(c.try_into().unwrap()))),
+                _ => return Err(format!("protobuf: champ {field}: doubles attendus")),
+            }
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,176 @@
+use std::fs;
+use std::path::Path;
+
+use crate::tensor::Tensor;
+use crate::io::json::Json;
+use crate::utils::params::{ParamTree, Pytree};
+
+/*
+format safetensors:
+    u64 little endian N | header json de N octets | buffers bruts little endian
+le header: {"nom": {"dtype": "F32", "shape": [..], "data_offsets": [debut, fin]}, ..., "__metadata__": {"clé": "valeur"}}
+les offsets sont relatifs au début des buffers. on écrit du F32, on relit F32/F64/F16/BF16 (convertis en f32).
+*/
+
+pub type NamedTensors = Vec<(String, Tensor)>;
+
+pub fn serialize(tensors: &[(String, Tensor)], metadata: &[(String, String)]) -> Result<Vec<u8>, String>{
+    let mut header: Vec<(String, Json)> = Vec::with_capacity(tensors.len() + 1);
+    if !metadata.is_empty(){
+        header.push(("__metadata__".into(), Json::Object(
+            metadata.iter().map(|(k, v)| (k.clone(), Json::Str(v.clone()))).collect()
+        )));
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    for (name, t) in tensors{
+        if name == "__metadata__" || header.iter().any(|(n, _)| n == name){
+            return Err(format!("safetensors: nom de tenseur invalide ou dupliqué '{name}'"));
+        }
+        let begin = body.len();
+        // contiguous: les vues (transposées, broadcast...) sont écrites dans l'ordre logique
+        for x in t.contiguous().data.iter(){
+            body.extend_from_slice(&x.to_le_bytes());
+        }
+        header.push((name.clone(), Json::Object(vec![
+            ("dtype".into(), Json::Str("F32".into())),
+            ("shape".into(), Json::usize_array(&t.shape)),
+            ("data_offsets".into(), Json::usize_array(&[begin, body.len()])),
+        ])));
+    }
+
+    let mut header = Json::Object(header).dump().into_bytes();
+    // padding avec des espaces pour aligner les buffers sur 8 octets (autorisé par le format)
+    while !header.len().is_multiple_of(8){
+        header.push(b' ');
+    }
+
+    let mut out = Vec::with_capacity(8 + header.len() + body.len());
+    out.extend_from_slice(&(header.len() as u64).to_le_bytes());
+    out.extend_from_slice(&header);
+    out.extend_from_slice(&body);
+    Ok(out)
+}
+
+// tenseurs dans l'ordre des buffers, + métadonnées
+pub fn deserialize(bytes: &[u8]) -> Result<(NamedTensors, Vec<(String, String)>), String>{
+    let n = u64::from_le_bytes(bytes.get(..8).ok_or("safetensors: fichier trop court")?.try_into().unwrap()) as usize;
+    let header = bytes.get(8..8usize.saturating_add(n)).ok_or("safetensors: header plus long que le fichier")?;
+    let header = std::str::from_utf8(header).map_err(|e| format!("safetensors: header non utf8: {e}"))?;
+    let Json::Object(entries) = Json::parse(header.trim_end())? else {
+        return Err("safetensors: le header n'est pas un objet json".into());
+    };
+    let body = &bytes[8+n..];
+
+    let mut metadata = Vec::new();
+    let mut tensors: Vec<(usize, String, Tensor)> = Vec::with_capacity(entries.len());
+    for (name, info) in entries{
+        if name == "__metadata__"{
+            let Json::Object(kv) = info else {return Err("safetensors: __metadata__ doit etre un objet".into())};
+            for (k, v) in kv{
+                let v = v.as_str().ok_or_else(|| format!("safetensors: métadonnée '{k}' non textuelle"))?;
+                metadata.push((k, v.to_string()));
+            }
+            continue;
+        }
+        let dtype = info.get("dtype").and_then(Json::as_str).ok_or_else(|| format!("safetensors: '{name}' sans dtype"))?;
+        let shape = info.get("shape").and_then(Json::as_usize_vec).ok_or_else(|| format!("safetensors: '{name}' sans shape valide"))?;
+        let offsets = info.get("data_offsets").and_then(Json::as_usize_vec).ok_or_else(|| format!("safetensors: '{name}' sans data_offsets valides"))?;
+        let [begin, end] = offsets[..] else {return Err(format!("safetensors: '{name}': data_offsets doit avoir 2 valeurs"))};
+
+        let raw = body.get(begin..end).ok_or_else(|| format!("safetensors: '{name}': offsets [{begin}, {end}] hors du fichier"))?;
+        let data = decode(dtype, raw).map_err(|e| format!("safetensors: '{name}': {e}"))?;
+        let numel: usize = shape.iter().product();
+        if data.len() != numel{
+            return Err(format!("safetensors: '{name}': {} valeurs pour la shape {shape:?} ({numel} attendues)", data.len()));
+        }
+        tensors.push((begin, name, Tensor::from_owned(data, &shape)?));
+    }
+    tensors.sort_by_key(|(begin, _, _)| *begin);
+    Ok((tensors.into_iter().map(|(_, n, t)| (n, t)).collect(), metadata))
+}
+
+fn decode(dtype: &str, raw: &[u8]) -> Result<Vec<f32>, String>{
+    let size = match dtype{
+        "F32" => 4,
+        "F64" => 8,
+        "F16" | "BF16" => 2,
+        _ => return Err(format!("dtype {dtype} non supporté (F32, F64, F16, BF16)")),
+    };
+    if !raw.len().is_multiple_of(size){
+        return Err(format!("{} octets, pas un multiple de {size} ({dtype})", raw.len()));
+    }
+    Ok(raw.chunks_exact(size).map(|c| match dtype{
+        "F32" => f32::from_le_bytes(c.try_into().unwrap()),
+        "F64" => f64::from_le_bytes(c.try_into().unwrap()) as f32,
+        "BF16" => f32::from_bits((u16::from_le_bytes(c.try_into().unwrap()) as u32) << 16),
+        _ => f16_to_f32(u16::from_le_bytes(c.try_into().unwrap())),
+    }).collect())
+}
+
+pub(crate) fn f16_to_f32(h: u16) -> f32{
+    let sign = ((h >> 15) as u32) << 31;
+    let exp = ((h >> 10) & 0x1f) as u32;
+    let mant = (h & 0x3ff) as u32;
+    let bits = match (exp, mant){
+        (0, 0) => sign,
+        (0, _) => {
+            // sous-normal: on renormalise
+            let mut e = 127 - 15 + 1;
+            let mut m = mant;
+            while m & 0x400 == 0{
+                m <<= 1;
+                e -= 1;
+            }
+            sign | (e << 23) | ((m & 0x3ff) << 13)
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (mant << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (mant << 13),
+    };
+    f32::from_bits(bits)
+}
+
+pub fn save_safetensors(path: impl AsRef<Path>, tensors: &[(String, Tensor)]) -> Result<(), String>{
+    let bytes = serialize(tensors, &[])?;
+    fs::write(path.as_ref(), bytes).map_err(|e| format!("safetensors: écriture de {:?}: {e}", path.as_ref()))
+}
+
+pub fn load_safetensors(path: impl AsRef<Path>) -> Result<NamedTensors, String>{
+    let bytes = fs::read(path.as_ref()).map_err(|e| format!("safetensors: lecture de {:?}: {e}", path.as_ref()))?;
+    Ok(deserialize(&bytes)?.0)
+}
+
+// les noms sont les chemins de l'arbre ("layer0.w"...)
+pub fn save_params(path: impl AsRef<Path>, params: &ParamTree) -> Result<(), String>{
+    save_safetensors(path, &params.named_leaves())
+}
+
+/*
+recharge des params avec la structure de like (typiquement model.init_tree()).
+chaque chemin de like doit etre dans le fichier avec la meme shape; tout écart est listé dans l'erreur.
+*/
+pub fn load_params(path: impl AsRef<Path>, like: &ParamTree) -> Result<ParamTree, String>{
+    let loaded = load_safetensors(path)?;
+    check_against(&loaded, like)?;
+    let leaves = like.paths().iter()
+        .map(|p| loaded.iter().find(|(n, _)| n == p).unwrap().1.clone())
+        .collect();
+    Ok(like.with_leaves(leaves))
+}
+
+pub(crate) fn check_against(loaded: &[(String, Tensor)], like: &ParamTree) -> Result<(), String>{
+    let mut errors = Vec::new();
+    for (path, t) in like.named_leaves(){
+        match loaded.iter().find(|(n, _)| *n == path){
+            None => errors.push(format!("'{path}' manquant")),
+            Some((_, l)) if l.shape != t.shape => errors.push(format!("'{path}': shape {:?} dans le fichier, {:?} attendue", l.shape, t.shape)),
+            Some(_) => {}
+        }
+    }
+    for (n, _) in loaded{
+        if like.get(n).is_none(){
+            errors.push(format!("'{n}' inattendu"));
+        }
+    }
+    if errors.is_empty() {Ok(())} else {Err(format!("params incompatibles: {}", errors.join(", ")))}
+}
@@ -0,0 +1,300 @@
+/*
+zip minimal pour les .npz: écriture sans compression (comme np.savez), 
+lecture des entrées stockées ou deflate (np.savez_compressed). pas de zip64 ni de chiffrement.
+*/
+
+pub(crate) fn crc32(data: &[u8]) -> u32{
+    let mut crc = !0u32;
+    for &b in data{
+        crc ^= b as u32;
+        for _ in 0..8{
+            crc = if crc & 1 != 0 {(crc >> 1) ^ 0xedb8_8320} else {crc >> 1};
+        }
+    }
+    !crc
+}
+
+pub(crate) fn write_zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String>{
+    const DOS_DATE: u16 = (1 << 5) | 1; // 1980-01-01
+    let mut out: Vec<u8> = Vec::new();
+    let mut central: Vec<u8> = Vec::new();
+
+    for (name, data) in entries{
+        if data.len() > u32::MAX as usize || out.len() > u32::MAX as usize{
+            return Err(format!("zip: '{name}' trop gros (zip64 non supporté)"));
+        }
+        let (crc, size, offset) = (crc32(data), data.len() as u32, out.len() as u32);
+
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version
+        out.extend_from_slice(&0u16.to_le_bytes()); // flags
+        out.extend_from_slice(&0u16.to_le_bytes()); // stored
+        out.extend_from_slice(&0u16.to_le_bytes()); // heure
+        out.extend_from_slice(&DOS_DATE.to_le_bytes());
+        out.extend_from_slice(&crc.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // extra
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        central.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&0u16.to_le_bytes());
+        central.extend_from_slice(&DOS_DATE.to_le_bytes());
+        central.extend_from_slice(&crc.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&size.to_le_bytes());
+        central.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central.extend_from_slice(&[0u8; 12]); // extra, commentaire, disque, attributs internes et externes
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let cd_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+    out.extend_from_slice(&[0u8; 4]); // disques
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&cd_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // commentaire
+    Ok(out)
+}
+
+fn u16_at(b: &[u8], i: usize) -> Result<u16, String>{
+    b.get(i..i+2).map(|s| u16::from_le_bytes(s.try_into().unwrap())).ok_or_else(|| "zip: archive tronquée".into())
+}
+
+fn u32_at(b: &[u8], i: usize) -> Result<u32, String>{
+    b.get(i..i+4).map(|s| u32::from_le_bytes(s.try_into().unwrap())).ok_or_else(|| "zip: archive tronquée".into())
+}
+
+// (nom, contenu décompressé), dans l'ordre du répertoire central
+pub(crate) fn read_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String>{
+    // fin du répertoire central: dernière signature trouvée en partant de la fin (il peut y avoir un commentaire)
+    let eocd = (0..bytes.len().saturating_sub(21)).rev()
+        .find(|&i| bytes[i..i+4] == 0x0605_4b50u32.to_le_bytes())
+        .ok_or("zip: fin de répertoire central introuvable")?;
+    let count = u16_at(bytes, eocd + 10)? as usize;
+    let mut p = u32_at(bytes, eocd + 16)? as usize;
+
+    let mut out = Vec::with_capacity(count);
+    for _ in 0..count{
+        if u32_at(bytes, p)? != 0x0201_4b50{
+            return Err("zip: entrée du répertoire central invalide".into());
+        }
+        let method = u16_at(bytes, p + 10)?;
+        let crc = u32_at(bytes, p + 16)?;
+        let comp_size = u32_at(bytes, p + 20)? as usize;
+        let size = u32_at(bytes, p + 24)? as usize;
+        let (name_len, extra_len, comment_len) = (u16_at(bytes, p + 28)? as usize, u16_at(bytes, p + 30)? as usize, u16_at(bytes, p + 32)? as usize);
+        let local = u32_at(bytes, p + 42)? as usize;
+        let name = bytes.get(p+46..p+46+name_len).ok_or("zip: archive tronquée")?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        p += 46 + name_len + extra_len + comment_len;
+
+        if comp_size == u32::MAX as usize || local == u32::MAX as usize{
+            return Err(format!("zip: '{name}' en zip64, non supporté"));
+        }
+        let start = local + 30 + u16_at(bytes, local + 26)? as usize + u16_at(bytes, local + 28)? as usize;
+        let raw = bytes.get(start..start+comp_size).ok_or_else(|| format!("zip: '{name}' tronqué"))?;
+        let data = match method{
+            0 => raw.to_vec(),
+            8 => inflate(raw).map_err(|e| format!("zip: '{name}': {e}"))?,
+            m => return Err(format!("zip: '{name}': méthode de compression {m} non supportée")),
+        };
+        if data.len() != size || crc32(&data) != crc{
+            return Err(format!("zip: '{name}': taille ou crc invalide"));
+        }
+        out.push((name, data));
+    }
+    Ok(out)
+}
+
+struct Bits<'a>{
+    b: &'a [u8],
+    pos: usize, // en bits
+}
+
+impl Bits<'_>{
+    fn bit(&mut self) -> Result<u32, String>{
+        let byte = *self.b.get(self.pos >> 3).ok_or("deflate: flux tronqué")?;
+        let v = (byte >> (self.pos & 7)) & 1;
+        self.pos += 1;
+        Ok(v as u32)
+    }
+
+    fn bits(&mut self, n: u32) -> Result<u32, String>{
+        let mut v = 0;
+        for i in 0..n{
+            v |= self.bit()? << i;
+        }
+        Ok(v)
+    }
+}
+
+// huffman canonique: nombre de codes par longueur + symboles triés
+struct Huffman{
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman{
+    fn new(lengths: &[u8]) -> Huffman{
+        let mut counts = [0u16; 16];
+        for &l in lengths{
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offs = [0u16; 16];
+        for i in 1..16{
+            offs[i] = offs[i-1] + counts[i-1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (s, &l) in lengths.iter().enumerate(){
+            if l != 0{
+                symbols[offs[l as usize] as usize] = s as u16;
+                offs[l as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u16, String>{
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16{
+            code |= bits.bit()? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first{
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err("deflate: code huffman invalide".into())
+    }
+}
+
+const LEN_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LEN_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+// deflate brut (rfc 1951)
+pub(crate) fn inflate(data: &[u8]) -> Result<Vec<u8>, String>{
+    let mut bits = Bits { b: data, pos: 0 };
+    let mut out: Vec<u8> = Vec::new();
+    loop{
+        let last = bits.bit()?;
+        match bits.bits(2)?{
+            0 => {
+                bits.pos = bits.pos.div_ceil(8) * 8;
+                let i = bits.pos >> 3;
+                let len = u16_at(data, i)?;
+                if u16_at(data, i+2)? != !len{
+                    return Err("deflate: bloc stocké, LEN et NLEN incohérents".into());
+                }
+                let len = len as usize;
+                let raw = data.get(i+4..i+4+len).ok_or("deflate: bloc stocké tronqué")?;
+                out.extend_from_slice(raw);
+                bits.pos = (i + 4 + len) * 8;
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(&mut bits, &mut out, &Huffman::new(&lengths), &Huffman::new(&[5u8; 30]))?;
+            }
+            2 => {
+                let (hlit, hdist, hclen) = (bits.bits(5)? as usize + 257, bits.bits(5)? as usize + 1, bits.bits(4)? as usize + 4);
+                const ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+                let mut cl = [0u8; 19];
+                for &o in ORDER.iter().take(hclen){
+                    cl[o] = bits.bits(3)? as u8;
+                }
+                let cl = Huffman::new(&cl);
+                let mut lengths: Vec<u8> = Vec::with_capacity(hlit + hdist);
+                while lengths.len() < hlit + hdist{
+                    match cl.decode(&mut bits)?{
+                        s @ 0..=15 => lengths.push(s as u8),
+                        16 => {
+                            let prev = *lengths.last().ok_or("deflate: répétition sans longueur précédente")?;
+                            let n = 3 + bits.bits(2)? as usize;
+                            lengths.extend(std::iter::repeat_n(prev, n));
+                        }
+                        17 => {
+                            let n = 3 + bits.bits(3)? as usize;
+                            lengths.extend(std::iter::repeat_n(0, n));
+                        }
+                        _ => {
+                            let n = 11 + bits.bits(7)? as usize;
+                            lengths.extend(std::iter::repeat_n(0, n));
+                        }
+                    }
+                }
+                if lengths.len() != hlit + hdist{
+                    return Err("deflate: longueurs de codes invalides".into());
+                }
+                inflate_block(&mut bits, &mut out, &Huffman::new(&lengths[..hlit]), &Huffman::new(&lengths[hlit..]))?;
+            }
+            _ => return Err("deflate: type de bloc invalide".into()),
+        }
+        if last == 1{
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_block(bits: &mut Bits, out: &mut Vec<u8>, lit: &Huffman, dist: &Huffman) -> Result<(), String>{
+    loop{
+        let s = lit.decode(bits)? as usize;
+        match s{
+            0..=255 => out.push(s as u8),
+            256 => return Ok(()),
+            _ => {
+                let s = s - 257;
+                if s >= 29 { return Err("deflate: longueur invalide".into()); }
+                let len = LEN_BASE[s] as usize + bits.bits(LEN_EXTRA[s] as u32)? as usize;
+                let d = dist.decode(bits)? as usize;
+                if d >= 30 { return Err("deflate: distance invalide".into()); }
+                let d = DIST_BASE[d] as usize + bits.bits(DIST_EXTRA[d] as u32)? as usize;
+                if d > out.len() { return Err("deflate: distance hors du flux".into()); }
+                let start = out.len() - d;
+                for k in 0..len{
+                    out.push(out[start + k]);
+                }
+            }
+        }
+    }
+}
+
+// crc32 et inflate ne sont pas publics: les archives complètes sont testées dans tests/npy.rs
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn crc32_check_value(){
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn inflate_stored_and_fixed(){
+        // bloc stocké final de 3 octets, puis bloc fixe final vide (seul le code de fin 256)
+        assert_eq!(inflate(&[0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c']).unwrap(), b"abc");
+        assert_eq!(inflate(&[0x03, 0x00]).unwrap(), b"");
+        // LEN et NLEN incohérents
+        assert!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00, b'a', b'b', b'c']).is_err());
+    }
+}
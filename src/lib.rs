@@ -6,4 +6,5 @@ pub mod optim;
 pub mod nn;
 pub mod utils; 
 pub mod dataloader;
-pub mod data_examples;
\ No newline at end of file
+pub mod data_examples;
+pub mod io;
\ No newline at end of file
@@ -1,17 +1,24 @@
+use lamp::tensor::Tensor;
 use lamp::trace::Trace;
-use lamp::utils::params::get_params_id;
+use lamp::utils::params::{get_params_id, Pytree};
 use mnist::MnistBuilder;
 use lamp::autodiff::value_and_grad::value_and_grad;
 use lamp::dataloader::dataloader::DataLoader;
 use lamp::data_examples::mnist_data::MnistDataset;
 use lamp::data_examples::mnist_data::collate_mnist_xy_u8_to_tensors;
-use lamp::nn::functions::relu;
-use lamp::nn::layers::bind::ParamCursor;
+use lamp::nn::module::Module;
+use lamp::nn::layers::sequential::Sequential;
 use lamp::nn::layers::linear::Linear;
+use lamp::nn::layers::activations::Relu;
 use lamp::nn::losses::softmax_crossentropy;
 use lamp::optim::sgd;
+use lamp::optim::Optimizer;
 use lamp::ops::add;
 use lamp::nn::losses::l2_reg;
+use lamp::io::safetensors::save_params;
+use lamp::io::checkpoint::Checkpoint;
+use lamp::io::onnx::save_onnx;
+use std::path::Path;
 
 fn main() {
     /*
@@ -21,7 +28,6 @@ fn main() {
     TODO: meilleure utilistion dans le main (helper eval & train)
     TODO: cleanup code (surotut tenseur.rs)
     TODO: gpu matmul
-    TODO: Adamn
 
 
     et pour plus tard:
@@ -35,7 +41,7 @@ fn main() {
     let mn = MnistBuilder::new()
         .base_path("data")
         .label_format_digit()
-        .training_set_length(60_0)
+        .training_set_length(600)
         .test_set_length(50)
         .finalize();
 
@@ -51,45 +57,76 @@ fn main() {
 
 
 
-    let mut params = vec![ // TODO: retirer le concat et prendre un Vec<Vec<Tensor>>, un pour chaque layer ? 
-        Linear::init_kaiming(784, 200),
-        Linear::init_kaiming(200, 50), 
-        Linear::init_kaiming(50, 10)
-    ].concat();
-    let sgd = sgd::Sgd {lr: 0.1};
-
-    //TODO: ré écrire ICI..
-    fn forward_logits(tr: &mut Trace, pids: &[usize], x: usize) -> usize {
-        let mut cur = ParamCursor::new(pids);
-        // IL EST TRES IMPORTANT DE BIND DANS LE MEME ORDRE QUE PREVU 
-        let l1 = Linear::bind(&mut cur);
-        let l2 = Linear::bind(&mut cur);
-        let l3 = Linear::bind(&mut cur);
-
-        let h1 = l1.apply(tr, x);
-        let z1 = relu(tr, h1);
-        let h2 = l2.apply(tr, z1);
-        let z2 = relu(tr, h2);
-        l3.apply(tr, z2) // logits
+    // l'ordre des params est celui du Sequential, pour init comme pour bind
+    let mut model = Sequential::new()
+        .layer(Linear::new(784, 200))
+        .layer(Relu)
+        .layer(Linear::new(200, 50))
+        .layer(Relu)
+        .layer(Linear::new(50, 10));
+
+    let mut params = model.init_tree();
+    let mut sgd = sgd::Sgd {lr: 0.1};
+
+    // reprise d'un run interrompu (LAMP_RESUME=1): checkpoint écrit à la fin de chaque epoch
+    let ckpt_path = "mlp.ckpt.safetensors";
+    let mut start_epoch = 0;
+    let mut step = 0;
+    let resume = std::env::var_os("LAMP_RESUME").is_some_and(|v| v == "1");
+    if resume && Path::new(ckpt_path).exists(){
+        let ck = Checkpoint::load(ckpt_path, &params).unwrap();
+        ck.restore_optim(&mut sgd).unwrap();
+        train.load_state(ck.loader.clone().unwrap()).unwrap();
+        params = ck.params;
+        (start_epoch, step) = (ck.epoch, ck.step);
+        println!("reprise à l'epoch {start_epoch}");
     }
 
-    // TODO: faire une fonction model qui prend x, y, tr, pids et qui renvoie la fonction build tr pid pour pas avoir a copier coller a l'inférence aussi
-    for epoch in 0..10 {
-        train.reset_epoch();
+    // new() a déjà mélangé l'epoch 0, et une reprise en milieu d'epoch garde sa position
+    for epoch in start_epoch..10 {
         for (xb, yb) in &mut train {
             let (loss, grads) = value_and_grad(&params, |tr, pids| {
                 let x = tr.input(xb.clone());
                 let y = tr.input(yb.clone());
-                let logits = forward_logits(tr, pids, x);
+                model.bind_params(pids);
+                let logits = model.forward(tr, x);
                 let loss = softmax_crossentropy(tr, logits, y) ;
                 let l2 = l2_reg(tr, 0.001, pids);
-                add(tr, loss, l2)
+                let total = add(tr, loss, l2);
+                // LAMP_TRACE_DOT=mlp_trace.dot: au premier pas, ce que la loss a construit (dot -Tsvg mlp_trace.dot > mlp_trace.svg)
+                match std::env::var_os("LAMP_TRACE_DOT"){
+                    Some(path) if step == 0 => {
+                        print!("{}", tr.summary());
+                        let _ = std::fs::write(path, tr.to_dot(Some(total)));
+                    }
+                    _ => {}
+                }
+                total
             });
 
         println!("loss: {}", loss.data[0]);
-        params = sgd.update(&params, &grads); 
+        sgd.update_tree_(&mut params, &grads); 
+        step += 1;
         }
         println!("epoch {epoch} ok");
+        train.reset_epoch();
+        Checkpoint::new(epoch + 1, step, params.clone(), &sgd).loader(train.state()).save(ckpt_path).unwrap();
+    }
+    // run terminé: le checkpoint ne sert plus, une reprise repartirait de la dernière epoch sans rien entrainer
+    let _ = std::fs::remove_file(ckpt_path);
+
+    // sauvegarde des poids entrainés, rechargeables avec load_params("mlp.safetensors", &model.init_tree())
+    if let Err(e) = save_params("mlp.safetensors", &params){
+        println!("{e}");
+    }
+
+    // export du modèle (logits) pour les runtimes onnx, avec une shape de batch fixe
+    let onnx = save_onnx("mlp.onnx", &params, &[Tensor::zeros(&[20, rows*cols])], |tr, pids, xs| {
+        model.bind_params(pids);
+        model.forward(tr, xs[0])
+    });
+    if let Err(e) = onnx{
+        println!("{e}");
     }
 
     // maintenant, passons à l'inférence: 
@@ -99,11 +136,12 @@ fn main() {
 
     //TODO: utiliser une fonction inférence à la place..
     for (xb, yb) in &mut test {
-        let mut tr = Trace::new();
+        let mut tr = Trace::no_grad();
         
         let x = tr.input(xb.clone());
-        let pids = get_params_id(&mut tr,&params); 
-        let logits = forward_logits(&mut tr, &pids, x);
+        let pids = get_params_id(&mut tr, &params.leaves()); 
+        model.bind_params(&pids);
+        let logits = model.forward(&mut tr, x);
         let pred = tr.get_tensor(logits).argmax_last();      // [B]
         let y_true = yb.argmax_last(); // si one-hot
         
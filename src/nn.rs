@@ -1,3 +1,4 @@
 pub mod functions;
 pub mod losses; 
-pub mod layers;
\ No newline at end of file
+pub mod layers;
+pub mod module;
\ No newline at end of file
@@ -1,34 +1,101 @@
 use smallvec::SmallVec;
 
 use crate::tensor::Tensor; 
-use crate::trace::{Trace, NodeId};
-use crate::ops::hadamard_mul_direct;
+use crate::trace::{Trace, NodeId, OpKind};
+use crate::ops::{hadamard_mul_direct, hadamard_mul, sub, sum_last};
 
 
 use smallvec::smallvec;
 
+/*
+f_apply élément par élément, f_backwards est sa dérivée.
+sans dérivée seconde, le noeud n'a pas de vjp tracée: backward_traced (higher_order::grad, hessian, hvp...) 
+panique s'il le traverse. utiliser apply_twice pour dériver plusieurs fois.
+*/
 pub fn apply<F>(tr: &mut Trace, a_id: NodeId, f_apply: F, f_backwards: fn(f32) -> f32) -> NodeId
     where 
-    F: Fn(f32) -> f32, 
+    F: Fn(f32) -> f32 + Sync, 
+
+{
+    apply_node(tr, a_id, OpKind::Map, f_apply, f_backwards, None)
+}
+
+/*
+comme apply, mais avec la dérivée seconde: le gradient est alors lui meme un noeud (g * f'(a)), 
+ce qui permet de dériver deux fois (backward_traced). sans f_second, backward_traced panique sur ce noeud.
+*/
+pub fn apply_twice<F>(tr: &mut Trace, a_id: NodeId, f_apply: F, f_backwards: fn(f32) -> f32, f_second: fn(f32) -> f32) -> NodeId
+    where 
+    F: Fn(f32) -> f32 + Sync, 
+
+{
+    apply_node(tr, a_id, OpKind::Map, f_apply, f_backwards, Some(f_second))
+}
+
+fn apply_node<F>(tr: &mut Trace, a_id: NodeId, op: OpKind, f_apply: F, f_backwards: fn(f32) -> f32, f_second: Option<fn(f32) -> f32>) -> NodeId
+    where 
+    F: Fn(f32) -> f32 + Sync, 
 
 {
     let a= tr.get_tensor(a_id).clone();
     let c = a.apply(f_apply);
     
     
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, c, smallvec![a_id]);
+    }
+
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
         smallvec![(a_id, hadamard_mul_direct(&a.apply(f_backwards), g_out).sum_over_broadcasted_batches(&a.shape))] 
 
     }; 
+    let vjp_traced = f_second.map(|f_second| Box::new(move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let d = apply(tr, a_id, f_backwards, f_second);
+        smallvec![(a_id, hadamard_mul(tr, g_out, d))]
+    }) as crate::trace::TracedVjpFn);
 
-    tr.push(crate::trace::Node { value: c, parents_id: smallvec![a_id], vjp: Some(Box::new(vjp)), is_param: false })
+    let a_j = tr.get_tensor(a_id).clone();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        hadamard_mul_direct(&a_j.apply(f_backwards), &t[0])
+    };
+    tr.push(crate::trace::Node { op, value: c, parents_id: smallvec![a_id], vjp: Some(Box::new(vjp)), vjp_traced, jvp: Some(Box::new(jvp)), is_param: false })
 }
 
 pub fn tanh(tr: &mut Trace, a_id: NodeId) -> NodeId{
-    apply(tr, a_id, |x| x.tanh(), |x| 1f32-x.tanh()*x.tanh())
+    apply_node(tr, a_id, OpKind::Tanh, |x| x.tanh(), |x| 1f32-x.tanh()*x.tanh(), Some(|x| -2f32*x.tanh()*(1f32-x.tanh()*x.tanh())))
 }
 
 pub fn relu(tr: &mut Trace, a_id: NodeId) -> NodeId{
-    apply(tr, a_id, |x| x.max(0f32), |x| if x >= 0f32 {1f32} else{0f32})
+    apply_node(tr, a_id, OpKind::Relu, |x| x.max(0f32), |x| if x >= 0f32 {1f32} else{0f32}, Some(|_| 0f32))
 }
 
+// softmax sur le dernier axe. vjp: s * (g - sum_last(g*s))
+pub fn softmax(tr: &mut Trace, a_id: NodeId) -> NodeId{
+    let (_, s) = crate::nn::losses::softmax(tr.get_tensor(a_id));
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Softmax, s, smallvec![a_id]);
+    }
+
+    let s_c = s.clone();
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        let dot = (g_out*&s_c).sum_last();
+        smallvec![(a_id, &s_c*&(g_out - &dot))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        // s est recalculé sur la trace: le noeud de ce softmax n'a pas encore d'id quand la closure est créée
+        let s_id = softmax(tr, a_id);
+        let gs = hadamard_mul(tr, g_out, s_id);
+        let dot = sum_last(tr, gs);
+        let diff = sub(tr, g_out, dot);
+        smallvec![(a_id, hadamard_mul(tr, s_id, diff))]
+    };
+
+    let s_j = s.clone();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        let dot = (&t[0]*&s_j).sum_last();
+        &s_j*&(&t[0] - &dot)
+    };
+    tr.push(crate::trace::Node { op: OpKind::Softmax, value: s, parents_id: smallvec![a_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
@@ -1,2 +1,5 @@
 pub mod bind;
-pub mod linear;
\ No newline at end of file
+pub mod linear;
+pub mod conv2d;
+pub mod activations;
+pub mod sequential;
\ No newline at end of file
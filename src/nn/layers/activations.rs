@@ -0,0 +1,35 @@
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId};
+use crate::nn::layers::bind::ParamCursor;
+use crate::nn::module::Module;
+use crate::nn::functions::{relu, tanh};
+
+// activations sans params, pour les mettre dans un Sequential
+
+pub struct Relu;
+
+impl Module for Relu{
+    fn init(&self) -> Vec<Tensor>{ Vec::new() }
+
+    fn param_names(&self) -> Vec<String>{ Vec::new() }
+
+    fn bind(&mut self, _: &mut ParamCursor){}
+
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        relu(tr, x)
+    }
+}
+
+pub struct Tanh;
+
+impl Module for Tanh{
+    fn init(&self) -> Vec<Tensor>{ Vec::new() }
+
+    fn param_names(&self) -> Vec<String>{ Vec::new() }
+
+    fn bind(&mut self, _: &mut ParamCursor){}
+
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        tanh(tr, x)
+    }
+}
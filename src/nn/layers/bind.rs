@@ -9,6 +9,7 @@ impl <'a>ParamCursor<'a>{
     pub fn new (p: &'a [NodeId]) -> Self{Self {p, i:0}}
 
     pub fn take(&mut self) -> NodeId{
+        assert!(self.i < self.p.len(), "ParamCursor: plus de params à binder ({} déjà pris)", self.i);
         let id = self.p[self.i]; 
         self.i+=1; 
         id
@@ -0,0 +1,53 @@
+use crate::tensor::Tensor;
+use crate::utils::inits::kaiming_conv2d;
+use crate::{nn::layers::bind::ParamCursor, trace::NodeId};
+use crate::nn::module::Module;
+use crate::trace::Trace;
+use crate::ops::add; 
+use crate::ops::conv::{conv2d, Conv2dOpts}; 
+
+pub struct Conv2d{
+    pub in_ch: usize,
+    pub out_ch: usize,
+    pub kernel: (usize, usize),
+    pub opts: Conv2dOpts,
+    pub w: Option<NodeId>, // (O, C, KH, KW), None tant que pas bindé
+    pub b: Option<NodeId>, // (O, 1, 1) pour broadcaster sur (N, O, OH, OW)
+}
+
+impl Conv2d{
+    pub fn new(in_ch: usize, out_ch: usize, kernel: (usize, usize), opts: Conv2dOpts) -> Conv2d{
+        Conv2d { in_ch, out_ch, kernel, opts, w: None, b: None }
+    }
+
+    pub fn init_kaiming(in_ch: usize, out_ch: usize, kernel: (usize, usize)) -> Vec<Tensor>{
+        vec![kaiming_conv2d(in_ch, out_ch, kernel.0, kernel.1), Tensor::zeros(&[out_ch, 1, 1])]
+    }
+
+    pub fn apply(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        self.forward(tr, x)
+    }
+}
+
+impl Module for Conv2d{
+    fn init(&self) -> Vec<Tensor>{
+        Conv2d::init_kaiming(self.in_ch, self.out_ch, self.kernel)
+    }
+
+    fn param_names(&self) -> Vec<String>{
+        vec!["w".to_string(), "b".to_string()]
+    }
+
+    fn bind(&mut self, cur: &mut ParamCursor){
+        let (w, b) = cur.take2(); 
+        self.w = Some(w);
+        self.b = Some(b);
+    }
+
+    // x: (N, C, H, W) => conv(x, w) + b: (N, O, OH, OW)
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        let (Some(w), Some(b)) = (self.w, self.b) else {panic!("Conv2d: forward avant bind")};
+        let y = conv2d(tr, x, w, self.opts); 
+        add(tr, y, b)
+    }
+}
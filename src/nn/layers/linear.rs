@@ -1,29 +1,58 @@
 use crate::tensor::Tensor;
 use crate::utils::inits::kaiming;
 use crate::{nn::layers::bind::ParamCursor, trace::NodeId};
+use crate::nn::module::Module;
 use crate::trace::Trace;
 use crate::ops::add; 
 use crate::ops::matmul; 
 pub struct Linear{
-    pub w: NodeId,
-    pub b: NodeId,
+    pub in_dim: usize,
+    pub out_dim: usize,
+    pub w: Option<NodeId>, // None tant que pas bindé
+    pub b: Option<NodeId>,
 }
 
 impl Linear{
+    pub fn new(in_dim: usize, out_dim: usize) -> Linear{
+        Linear { in_dim, out_dim, w: None, b: None }
+    }
+
+    pub fn init_kaiming(in_dim: usize, out_dim: usize)-> Vec<Tensor>{
+        vec![kaiming(in_dim, out_dim), Tensor::zeros(&[out_dim])]
+
+    }
+
+    // api d'avant Module, gardée telle quelle: les dims ne sont pas connues ici (0), ce Linear ne sert pas à init
     pub fn bind(cur: &mut ParamCursor) -> Linear{
-        let (w, b) = cur.take2(); 
-        Linear{w, b}
+        let mut l = Linear::new(0, 0);
+        Module::bind(&mut l, cur);
+        l
     }
 
-    // x.w + b
     pub fn apply(&self, tr: &mut Trace, x: NodeId) -> NodeId{
-        let x_dot_w = matmul(tr, x, self.w); 
-        add(tr, x_dot_w, self.b)
+        self.forward(tr, x)
+    }
+}
+
+impl Module for Linear{
+    fn init(&self) -> Vec<Tensor>{
+        Linear::init_kaiming(self.in_dim, self.out_dim)
     }
 
-    pub fn init_kaiming(in_dim: usize, out_dim: usize)-> Vec<Tensor>{
-        vec![kaiming(in_dim, out_dim), Tensor::zeros(&[out_dim])]
+    fn param_names(&self) -> Vec<String>{
+        vec!["w".to_string(), "b".to_string()]
+    }
 
+    fn bind(&mut self, cur: &mut ParamCursor){
+        let (w, b) = cur.take2(); 
+        self.w = Some(w);
+        self.b = Some(b);
     }
-}
 
+    // x.w + b
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        let (Some(w), Some(b)) = (self.w, self.b) else {panic!("Linear: forward avant bind")};
+        let x_dot_w = matmul(tr, x, w); 
+        add(tr, x_dot_w, b)
+    }
+}
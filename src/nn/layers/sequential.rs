@@ -0,0 +1,55 @@
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId};
+use crate::nn::layers::bind::ParamCursor;
+use crate::nn::module::Module;
+
+/*
+enchaine des modules. init et bind parcourent les couches dans le meme ordre, donc plus de risque de décalage:
+
+    let mut model = Sequential::new()
+        .layer(Linear::new(784, 200))
+        .layer(Relu)
+        .layer(Linear::new(200, 10));
+    let params = model.init_tree(); // ParamTree: "layer0.w", "layer0.b", "layer2.w"...
+    ...
+    model.bind_params(pids);
+    let logits = model.forward(tr, x);
+*/
+#[derive(Default)]
+pub struct Sequential{
+    pub layers: Vec<Box<dyn Module>>,
+}
+
+impl Sequential{
+    pub fn new() -> Sequential{
+        Sequential { layers: Vec::new() }
+    }
+
+    pub fn layer(mut self, layer: impl Module + 'static) -> Self{
+        self.layers.push(Box::new(layer));
+        self
+    }
+}
+
+impl Module for Sequential{
+    fn init(&self) -> Vec<Tensor>{
+        self.layers.iter().flat_map(|l| l.init()).collect()
+    }
+
+    // "layer{i}.w", i = position dans le Sequential (les activations comptent, comme dans pytorch)
+    fn param_names(&self) -> Vec<String>{
+        self.layers.iter().enumerate()
+            .flat_map(|(i, l)| l.param_names().into_iter().map(move |n| format!("layer{i}.{n}")))
+            .collect()
+    }
+
+    fn bind(&mut self, cur: &mut ParamCursor){
+        for l in self.layers.iter_mut(){
+            l.bind(cur);
+        }
+    }
+
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId{
+        self.layers.iter().fold(x, |h, l| l.forward(tr, h))
+    }
+}
@@ -2,20 +2,11 @@
 use smallvec::SmallVec;
 
 use crate::nn::functions;
-use crate::nn::functions::apply;
-use crate::ops::hadamard_mul;
-use crate::tensor;
 use crate::tensor::Tensor; 
-use crate::tensor::Numel;
-use crate::trace::{Trace, NodeId, Node};
+use crate::trace::{Trace, NodeId, Node, OpKind};
 use crate::ops::hadamard_mul_direct;
-use crate::ops::linalg::matmul;
-use crate::ops::sub;
+use crate::ops::{sub, hadamard_mul, scale};
 use core::f32;
-use std::f32::NEG_INFINITY;
-use std::result;
-use std::sync::Arc; 
-use std::ops::Add;
 use smallvec::smallvec;
 use crate::ops::shapes::mean_all;
 
@@ -28,7 +19,7 @@ pub fn mse(tr: &mut Trace, pred_id: NodeId, target_id: NodeId) -> NodeId{ // A c
     let diff_id = sub(tr, pred_id, target_id);
 
     // on utilise pas hadamard mul car c'est un carré la..
-    let square_id = functions::apply(tr, diff_id, |x| x*x, |x| 2f32*x);
+    let square_id = functions::apply_twice(tr, diff_id, |x| x*x, |x| 2f32*x, |_| 2f32);
     
     mean_all(tr, square_id)
 }
@@ -65,6 +56,10 @@ pub fn softmax_crossentropy(tr: &mut Trace, logits_id: NodeId, target_id: NodeId
 
     let value = &lse - &zy; 
 
+    if !tr.grad_enabled(){
+        let smxcpy = tr.push_no_grad(OpKind::SoftmaxCrossEntropy, value, smallvec![logits_id]);
+        return mean_all(tr, smxcpy);
+    }
 
     let soft_c = softmaxed.clone();
     let y_c = y.clone();
@@ -73,7 +68,18 @@ pub fn softmax_crossentropy(tr: &mut Trace, logits_id: NodeId, target_id: NodeId
         let diff = &soft_c - &y_c;
         smallvec![(logits_id, &diff*g_out)]
     };
-    let smxcpy = tr.push(Node { value: value, parents_id: smallvec![logits_id], vjp: Some(Box::new(vjp)), is_param: false });
+    // la cible n'est pas dérivée (comme dans vjp)
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let s = functions::softmax(tr, logits_id);
+        let diff = sub(tr, s, target_id);
+        smallvec![(logits_id, hadamard_mul(tr, diff, g_out))]
+    };
+    // d(lse - <logits, y>) = <softmax - y, dlogits>
+    let (soft_j, y_j) = (softmaxed.clone(), tr.get_tensor(target_id).clone());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&(&soft_j - &y_j)*&t[0]).sum_last()
+    };
+    let smxcpy = tr.push(Node { op: OpKind::SoftmaxCrossEntropy, value, parents_id: smallvec![logits_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false });
     
     mean_all(tr, smxcpy)
 }
@@ -82,23 +88,41 @@ pub fn l2_reg(tr: &mut Trace, lambda: f32, pids_ref: &[usize]) -> NodeId{
 
     let l2norm: f32 = pids_ref.iter().map(|&id| tr.get_tensor(id).apply(|x|x*x*0.5).sum_all().data[0] ).sum();
 
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::L2Reg(lambda), Tensor::from_vec(&[lambda*l2norm], &[]).unwrap(), pids_ref.iter().copied().collect());
+    }
+
     let mut parents_val = Vec::with_capacity(pids_ref.len());
     for &pid in pids_ref{
-        parents_val.push(tr.get_tensor(pid).clone());
+        parents_val.push((pid, tr.get_tensor(pid).clone()));
     }
 
 
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
         
         let mut res : SmallVec <[(NodeId, Tensor); 2]> = SmallVec::with_capacity(parents_val.len());
-        for (pid, tensor) in parents_val.iter().enumerate(){
+        for (pid, tensor) in parents_val.iter(){
             let grad = tensor.apply(|x| x*lambda); 
             let prod = hadamard_mul_direct(&grad, g_out);
-            res.push((pid,prod));
+            res.push((*pid,prod));
         }
         res
 
     };
-    tr.push(Node { value: Tensor::from_vec(&[lambda*l2norm], &[]).unwrap()
-        , parents_id: smallvec![], vjp: Some(Box::new(vjp)), is_param: false })
+    let pids = pids_ref.to_vec();
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let mut res : SmallVec <[(NodeId, NodeId); 2]> = SmallVec::with_capacity(pids.len());
+        for &pid in pids.iter(){
+            let grad = scale(tr, pid, lambda);
+            res.push((pid, hadamard_mul(tr, grad, g_out)));
+        }
+        res
+    };
+    let params_j: Vec<Tensor> = pids_ref.iter().map(|&pid| tr.get_tensor(pid).clone()).collect();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        let dot: f32 = params_j.iter().zip(t.iter()).map(|(p, tp)| (p*tp).sum_all().data[0]).sum();
+        Tensor::from_vec(&[lambda*dot], &[]).unwrap()
+    };
+    tr.push(Node { op: OpKind::L2Reg(lambda), value: Tensor::from_vec(&[lambda*l2norm], &[]).unwrap()
+        , parents_id: pids_ref.iter().copied().collect(), vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
 }
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId};
+use crate::nn::layers::bind::ParamCursor;
+use crate::utils::params::ParamTree;
+
+/*
+un modèle défini une seule fois, pour l'entrainement comme pour l'inférence:
+    init()  => les tenseurs des params, dans l'ordre ou bind les consommera
+    param_names() => leurs noms, meme ordre ("w", "b", ou "layer0.w" dans un Sequential)
+    bind()  => récupère les NodeId des params sur la trace (meme ordre que init)
+    forward => le calcul, une fois bindé
+*/
+pub trait Module {
+    fn init(&self) -> Vec<Tensor>;
+
+    fn param_names(&self) -> Vec<String>;
+
+    fn bind(&mut self, cur: &mut ParamCursor);
+
+    fn forward(&self, tr: &mut Trace, x: NodeId) -> NodeId;
+
+    // init nommé: les feuilles de l'arbre sont dans l'ordre de init, donc compatibles avec bind_params
+    fn init_tree(&self) -> ParamTree{
+        let (names, params) = (self.param_names(), self.init());
+        assert_eq!(names.len(), params.len(), "module: param_names et init n'ont pas la meme longueur");
+        ParamTree::from_named(names.into_iter().zip(params).collect()).unwrap()
+    }
+
+    // bind sur tous les params de la trace, et vérifie qu'il n'en reste aucun de non consommé
+    fn bind_params(&mut self, pids: &[NodeId]){
+        let mut cur = ParamCursor::new(pids);
+        self.bind(&mut cur);
+        assert_eq!(cur.remaining(), 0, "module: {} params non consommés par bind, init et bind ne correspondent pas", cur.remaining());
+    }
+}
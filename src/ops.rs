@@ -1,8 +1,14 @@
-
 pub mod elementwise;
 pub mod linalg;
 pub mod shapes; 
+pub mod conv;
+pub mod pool;
+pub mod reduce;
+mod gemm;
 
 pub use elementwise::*;
 pub use linalg::*;
-pub use shapes::*;
\ No newline at end of file
+pub use shapes::*;
+pub use conv::*;
+pub use pool::*;
+pub use reduce::*;
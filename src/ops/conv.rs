@@ -0,0 +1,244 @@
+use smallvec::{smallvec, SmallVec};
+
+use crate::tensor::Tensor;
+use crate::trace::{Trace, NodeId, Node, OpKind};
+
+/*
+conv2d (en fait une cross-correlation, comme pytorch): 
+    x: (N, C, H, W), w: (O, C, KH, KW) => y: (N, O, OH, OW)
+    y[n, o, i, j] = sum_{c, u, v} x[n, c, i*s - p + u*d, j*s - p + v*d] * w[o, c, u, v]
+les positions qui tombent dans le padding valent 0.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Conv2dOpts{
+    pub stride: (usize, usize), 
+    pub padding: (usize, usize), 
+    pub dilation: (usize, usize),
+}
+
+impl Default for Conv2dOpts{
+    fn default() -> Self {
+        Conv2dOpts { stride: (1, 1), padding: (0, 0), dilation: (1, 1) }
+    }
+}
+
+impl Conv2dOpts{
+    pub fn stride(mut self, sh: usize, sw: usize) -> Self{
+        self.stride = (sh, sw); 
+        self
+    }
+    pub fn padding(mut self, ph: usize, pw: usize) -> Self{
+        self.padding = (ph, pw); 
+        self
+    }
+    pub fn dilation(mut self, dh: usize, dw: usize) -> Self{
+        self.dilation = (dh, dw); 
+        self
+    }
+}
+
+// taille de sortie le long d'un axe
+pub fn conv_out_dim(input: usize, kernel: usize, stride: usize, padding: usize, dilation: usize) -> usize{
+    assert!(kernel > 0, "conv: noyau de taille 0");
+    assert!(stride > 0 && dilation > 0, "conv: stride et dilation doivent etre > 0");
+    let span = dilation*(kernel-1) + 1; 
+    assert!(input + 2*padding >= span, "conv: le noyau ({kernel}, dilation {dilation}) depasse l'entree ({input}, padding {padding})");
+    (input + 2*padding - span)/stride + 1
+}
+
+// position dans l'entrée (None si on tombe dans le padding)
+#[inline(always)]
+fn in_pos(out: usize, k: usize, stride: usize, padding: usize, dilation: usize, size: usize) -> Option<usize>{
+    let pos = (out*stride + k*dilation) as isize - padding as isize; 
+    if pos < 0 || pos as usize >= size {None} else {Some(pos as usize)}
+}
+
+fn conv2d_check(x: &Tensor, w: &Tensor){
+    assert_eq!(x.shape.len(), 4, "conv2d: x doit etre (N, C, H, W), shape={:?}", x.shape);
+    assert_eq!(w.shape.len(), 4, "conv2d: w doit etre (O, C, KH, KW), shape={:?}", w.shape);
+    assert_eq!(x.shape[1], w.shape[1], "conv2d: nombre de canaux incompatibles x={:?} w={:?}", x.shape, w.shape);
+}
+
+pub fn conv2d_out_shape(x_shape: &[usize], w_shape: &[usize], opts: &Conv2dOpts) -> Vec<usize>{
+    let oh = conv_out_dim(x_shape[2], w_shape[2], opts.stride.0, opts.padding.0, opts.dilation.0);
+    let ow = conv_out_dim(x_shape[3], w_shape[3], opts.stride.1, opts.padding.1, opts.dilation.1);
+    vec![x_shape[0], w_shape[0], oh, ow]
+}
+
+// appelle f(y_lin, x_lin, w_lin) pour chaque produit x*w qui intervient dans la convolution (indices contigus)
+fn for_each_tap(x_shape: &[usize], w_shape: &[usize], oh: usize, ow: usize, opts: &Conv2dOpts, mut f: impl FnMut(usize, usize, usize)){
+    let (n, c, h, wd) = (x_shape[0], x_shape[1], x_shape[2], x_shape[3]);
+    let (o, kh, kw) = (w_shape[0], w_shape[2], w_shape[3]);
+    for ni in 0..n{
+        for oi in 0..o{
+            for i in 0..oh{
+                for j in 0..ow{
+                    let y_lin = ((ni*o + oi)*oh + i)*ow + j;
+                    for ci in 0..c{
+                        for u in 0..kh{
+                            let Some(hi) = in_pos(i, u, opts.stride.0, opts.padding.0, opts.dilation.0, h) else {continue};
+                            for v in 0..kw{
+                                let Some(wi) = in_pos(j, v, opts.stride.1, opts.padding.1, opts.dilation.1, wd) else {continue};
+                                f(y_lin, ((ni*c + ci)*h + hi)*wd + wi, ((oi*c + ci)*kh + u)*kw + v);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn conv2d_direct(x: &Tensor, w: &Tensor, opts: &Conv2dOpts) -> Tensor{
+    conv2d_check(x, w);
+    let out_shape = conv2d_out_shape(&x.shape, &w.shape, opts);
+
+    // on passe en contigu une fois pour toutes, plutot que de refaire le calcul des strides a chaque multiplication
+    let xc = x.contiguous(); 
+    let wc = w.contiguous(); 
+    let (xd, wdat) = (&xc.data[..], &wc.data[..]);
+
+    let mut y = vec![0f32; out_shape.iter().product()];
+    for_each_tap(&x.shape, &w.shape, out_shape[2], out_shape[3], opts, |y_lin, x_lin, w_lin| {
+        y[y_lin] += xd[x_lin]*wdat[w_lin];
+    });
+    Tensor::from_owned(y, &out_shape).unwrap()
+}
+
+// dL/dx a partir de g_out: (N, O, OH, OW) (transposée de la convolution par rapport à x)
+pub fn conv2d_input_grad_direct(g_out: &Tensor, w: &Tensor, x_shape: &[usize], opts: &Conv2dOpts) -> Tensor{
+    let gc = g_out.contiguous(); 
+    let wc = w.contiguous(); 
+    let (gd, wdat) = (&gc.data[..], &wc.data[..]);
+
+    let mut gx = vec![0f32; x_shape.iter().product()];
+    for_each_tap(x_shape, &w.shape, g_out.shape[2], g_out.shape[3], opts, |y_lin, x_lin, w_lin| {
+        gx[x_lin] += gd[y_lin]*wdat[w_lin];
+    });
+    Tensor::from_owned(gx, x_shape).unwrap()
+}
+
+// dL/dw a partir de g_out: (N, O, OH, OW)
+pub fn conv2d_kernel_grad_direct(x: &Tensor, g_out: &Tensor, w_shape: &[usize], opts: &Conv2dOpts) -> Tensor{
+    let xc = x.contiguous(); 
+    let gc = g_out.contiguous(); 
+    let (xd, gd) = (&xc.data[..], &gc.data[..]);
+
+    let mut gw = vec![0f32; w_shape.iter().product()];
+    for_each_tap(&x.shape, w_shape, g_out.shape[2], g_out.shape[3], opts, |y_lin, x_lin, w_lin| {
+        gw[w_lin] += gd[y_lin]*xd[x_lin];
+    });
+    Tensor::from_owned(gw, w_shape).unwrap()
+}
+
+// renvoie (dL/dx, dL/dw) en un seul passage
+pub fn conv2d_backward(x: &Tensor, w: &Tensor, g_out: &Tensor, opts: &Conv2dOpts) -> (Tensor, Tensor){
+    let xc = x.contiguous(); 
+    let wc = w.contiguous(); 
+    let gc = g_out.contiguous(); 
+    let (xd, wdat, gd) = (&xc.data[..], &wc.data[..], &gc.data[..]);
+
+    let mut gx = vec![0f32; x.shape.iter().product()];
+    let mut gw = vec![0f32; w.shape.iter().product()];
+    for_each_tap(&x.shape, &w.shape, g_out.shape[2], g_out.shape[3], opts, |y_lin, x_lin, w_lin| {
+        let g = gd[y_lin];
+        gx[x_lin] += g*wdat[w_lin];
+        gw[w_lin] += g*xd[x_lin];
+    });
+    (Tensor::from_owned(gx, &x.shape).unwrap(), Tensor::from_owned(gw, &w.shape).unwrap())
+}
+
+/*
+y = conv(x, w) est bilinéaire, ses deux vjp le sont aussi: 
+    A(g, w) = dL/dx     (conv2d_input_grad)
+    B(x, g) = dL/dw     (conv2d_kernel_grad)
+et les adjoints de A et B s'écrivent avec conv, A et B. ces trois ops suffisent donc pour dériver autant de fois qu'on veut.
+*/
+pub fn conv2d(tr: &mut Trace, x_id: NodeId, w_id: NodeId, opts: Conv2dOpts) -> NodeId{
+    let x = tr.get_tensor(x_id).clone();
+    let w = tr.get_tensor(w_id).clone();
+
+    let y = conv2d_direct(&x, &w, &opts);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Conv2d(opts), y, smallvec![x_id, w_id]);
+    }
+
+    let y_shapes = (x.shape.clone(), w.shape.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        let (gx, gw) = conv2d_backward(&x, &w, g_out, &opts);
+        smallvec![(x_id, gx), (w_id, gw)]
+    };
+    let (x_shape, w_shape) = (y_shapes.0, y_shapes.1);
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let gx = conv2d_input_grad(tr, g_out, w_id, &x_shape, opts);
+        let gw = conv2d_kernel_grad(tr, x_id, g_out, &w_shape, opts);
+        smallvec![(x_id, gx), (w_id, gw)]
+    };
+
+    let (jx, jw) = (tr.get_tensor(x_id).clone(), tr.get_tensor(w_id).clone());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &conv2d_direct(&t[0], &jw, &opts) + &conv2d_direct(&jx, &t[1], &opts)
+    };
+    tr.push(Node { op: OpKind::Conv2d(opts), value: y, parents_id: smallvec![x_id, w_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// A(g, w), de shape x_shape
+pub fn conv2d_input_grad(tr: &mut Trace, g_id: NodeId, w_id: NodeId, x_shape: &[usize], opts: Conv2dOpts) -> NodeId{
+    let g = tr.get_tensor(g_id).clone();
+    let w = tr.get_tensor(w_id).clone();
+    let w_shape = w.shape.clone();
+
+    let y = conv2d_input_grad_direct(&g, &w, x_shape, &opts);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Conv2dInputGrad(opts), y, smallvec![g_id, w_id]);
+    }
+
+    // <h, A(g, w)> = <g, conv(h, w)> = <w, B(h, g)>
+    let vjp = move |h: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(g_id, conv2d_direct(h, &w, &opts)), (w_id, conv2d_kernel_grad_direct(h, &g, &w.shape, &opts))]
+    };
+    let vjp_traced = move |tr: &mut Trace, h: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let gg = conv2d(tr, h, w_id, opts);
+        let gw = conv2d_kernel_grad(tr, h, g_id, &w_shape, opts);
+        smallvec![(g_id, gg), (w_id, gw)]
+    };
+
+    let (jg, jw, jx_shape) = (tr.get_tensor(g_id).clone(), tr.get_tensor(w_id).clone(), x_shape.to_vec());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &conv2d_input_grad_direct(&t[0], &jw, &jx_shape, &opts) + &conv2d_input_grad_direct(&jg, &t[1], &jx_shape, &opts)
+    };
+    tr.push(Node { op: OpKind::Conv2dInputGrad(opts), value: y, parents_id: smallvec![g_id, w_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// B(x, g), de shape w_shape
+pub fn conv2d_kernel_grad(tr: &mut Trace, x_id: NodeId, g_id: NodeId, w_shape: &[usize], opts: Conv2dOpts) -> NodeId{
+    let x = tr.get_tensor(x_id).clone();
+    let g = tr.get_tensor(g_id).clone();
+    let x_shape = x.shape.clone();
+
+    let y = conv2d_kernel_grad_direct(&x, &g, w_shape, &opts);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Conv2dKernelGrad(opts), y, smallvec![x_id, g_id]);
+    }
+
+    // <h, B(x, g)> = <x, A(g, h)> = <g, conv(x, h)>
+    let vjp = move |h: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, conv2d_input_grad_direct(&g, h, &x.shape, &opts)), (g_id, conv2d_direct(&x, h, &opts))]
+    };
+    let vjp_traced = move |tr: &mut Trace, h: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let gx = conv2d_input_grad(tr, g_id, h, &x_shape, opts);
+        let gg = conv2d(tr, x_id, h, opts);
+        smallvec![(x_id, gx), (g_id, gg)]
+    };
+
+    let (jx, jg, jw_shape) = (tr.get_tensor(x_id).clone(), tr.get_tensor(g_id).clone(), w_shape.to_vec());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &conv2d_kernel_grad_direct(&t[0], &jg, &jw_shape, &opts) + &conv2d_kernel_grad_direct(&jx, &t[1], &jw_shape, &opts)
+    };
+    tr.push(Node { op: OpKind::Conv2dKernelGrad(opts), value: y, parents_id: smallvec![x_id, g_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
@@ -1,26 +1,15 @@
 use smallvec::SmallVec;
 
 use crate::tensor::Tensor; 
-use crate::tensor::Numel;
-use crate::trace::{Trace, NodeId, Node};
-use std::sync::Arc; 
+use crate::tensor::strided;
+use crate::trace::{Trace, NodeId, Node, OpKind};
+use crate::ops::shapes::sum_to_shape;
 use std::ops::{Add, Sub, Div, Mul};
 use smallvec::smallvec;
 
 
 pub fn hadamard_mul_direct(a: &Tensor, b: &Tensor ) -> Tensor{
-
-    let out_shape = Tensor::broadcast_shape(&a.shape, &b.shape).unwrap();
- //   println!("a: {}, b: {} out_shape: {:?}", a, b, out_shape);
-    let a_broad = a.broadcast_view(&out_shape).unwrap();
-    let b_broad = b.broadcast_view(&out_shape).unwrap();
- //   println!("a broad: {} \n b broad : {}", a_broad, b_broad);
-    let mut c = Vec::with_capacity(out_shape.numel());
-    for lin in 0..out_shape.numel(){
-        c.push(a_broad.get_from_lin(lin)*b_broad.get_from_lin(lin));
-    }
-    
-    Tensor::new(Arc::new(c), &out_shape, 0)
+    strided::zip_map(a, b, |x, y| x*y)
 }
 
 pub fn hadamard_mul(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{
@@ -31,14 +20,28 @@ pub fn hadamard_mul(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{
 
     let result_product = hadamard_mul_direct(&va, &vb);
 
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Mul, result_product, smallvec![a, b]);
+    }
+
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
-        let ga = hadamard_mul_direct(&g_out, &vb).sum_over_broadcasted_batches(&va.shape);
-        let gb = hadamard_mul_direct(&g_out, &va).sum_over_broadcasted_batches(&vb.shape);
+        let ga = hadamard_mul_direct(g_out, &vb).sum_over_broadcasted_batches(&va.shape);
+        let gb = hadamard_mul_direct(g_out, &va).sum_over_broadcasted_batches(&vb.shape);
         smallvec![(a, ga), (b, gb)]
 
     }; 
+    let (a_shape, b_shape) = (tr.get_tensor(a).shape.clone(), tr.get_tensor(b).shape.clone());
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let ga = hadamard_mul(tr, g_out, b);
+        let gb = hadamard_mul(tr, g_out, a);
+        smallvec![(a, sum_to_shape(tr, ga, &a_shape)), (b, sum_to_shape(tr, gb, &b_shape))]
+    };
 
-    tr.push(Node { value: result_product, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), is_param: false })
+    let (ja, jb) = (tr.get_tensor(a).clone(), tr.get_tensor(b).clone());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &hadamard_mul_direct(&t[0], &jb) + &hadamard_mul_direct(&ja, &t[1])
+    };
+    tr.push(Node { op: OpKind::Mul, value: result_product, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
 }
 
 pub fn add(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{
@@ -46,71 +49,111 @@ pub fn add(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{
     let vb = tr.get_tensor(b).clone(); 
 
     let res = &va+&vb; 
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Add, res, smallvec![a, b]);
+    }
+
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
         let ga = g_out.sum_over_broadcasted_batches(&va.shape); // TODO: check si bien ok de faire ca sur ref vs sur non ref (& vs non &)
         let gb = g_out.sum_over_broadcasted_batches(&vb.shape);
 
         smallvec![(a, ga), (b, gb)]
     };
-    tr.push(Node { value: res, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), is_param: false })
+    let (a_shape, b_shape) = (tr.get_tensor(a).shape.clone(), tr.get_tensor(b).shape.clone());
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(a, sum_to_shape(tr, g_out, &a_shape)), (b, sum_to_shape(tr, g_out, &b_shape))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &t[0] + &t[1]
+    };
+    tr.push(Node { op: OpKind::Add, value: res, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
     
 }
 
 
-pub fn sub(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{ // TODO: check si c'est le bon endroit ou multiplier par moins 1
+pub fn sub(tr: &mut Trace, a: NodeId, b: NodeId) -> NodeId{
     let va = tr.get_tensor(a).clone();
     let vb = tr.get_tensor(b).clone(); 
 
     let res = &va-&vb; 
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Sub, res, smallvec![a, b]);
+    }
+
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
         let ga = g_out.sum_over_broadcasted_batches(&va.shape); // TODO: check si bien ok de faire ca sur ref vs sur non ref (& vs non &)
-        let gb = g_out.sum_over_broadcasted_batches(&vb.shape).apply(|x| x*(-1f32));
+        let gb = g_out.sum_over_broadcasted_batches(&vb.shape).apply(|x| -x);
 
         smallvec![(a, ga), (b, gb)]
     };
-    tr.push(Node { value: res, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), is_param: false })
+    let (a_shape, b_shape) = (tr.get_tensor(a).shape.clone(), tr.get_tensor(b).shape.clone());
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let gb = sum_to_shape(tr, g_out, &b_shape);
+        smallvec![(a, sum_to_shape(tr, g_out, &a_shape)), (b, scale(tr, gb, -1f32))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &t[0] - &t[1]
+    };
+    tr.push(Node { op: OpKind::Sub, value: res, parents_id: smallvec![a, b], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
     
 }
 
-impl Add for &Tensor{
-    type Output = Tensor;
-    fn add(self, b: &Tensor) -> Tensor{
+// c*x, c constant (pas un noeud)
+pub fn scale(tr: &mut Trace, a: NodeId, c: f32) -> NodeId{
+    let res = tr.get_tensor(a).apply(|x| x*c);
 
-        let broadcast_shape = Tensor::broadcast_shape(&self.shape, &b.shape).unwrap();
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Scale(c), res, smallvec![a]);
+    }
 
-        
-        
-        let sz = broadcast_shape.numel();
-        let mut vec = Vec::with_capacity(sz);       
-        let a_b = self.broadcast_view(&broadcast_shape).unwrap();
-        let b_b = b.broadcast_view(&broadcast_shape).unwrap();
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(a, g_out.apply(|x| x*c))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(a, scale(tr, g_out, c))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].apply(|x| x*c)
+    };
+    tr.push(Node { op: OpKind::Scale(c), value: res, parents_id: smallvec![a], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
 
+// identité en forward, coupe le gradient: réseaux cibles, teachers EMA, ... 
+// pas de vjp donc le backward s'arrete ici, et la tangente (jvp) est nulle
+pub fn stop_gradient(tr: &mut Trace, a: NodeId) -> NodeId{
+    let res = tr.get_tensor(a).clone();
 
-       // println!("shape vers qui broadcast : {:?} \n, a_b: {}\n, b_b : {},\n a: {a}")
-        for lin in 0..sz{
-            vec.push(a_b.get_from_lin(lin)+ b_b.get_from_lin(lin));
-            
-        }
-        Tensor { data: Arc::new(vec), shape: broadcast_shape, strides: a_b.strides, offset: 0 }
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::StopGradient, res, smallvec![a]);
     }
-}
 
+    let shape = res.shape.clone();
+    let jvp = move |_: &[Tensor]| -> Tensor{
+        Tensor::zeros(&shape)
+    };
+    tr.push(Node { op: OpKind::StopGradient, value: res, parents_id: smallvec![a], vjp: None, vjp_traced: None, jvp: Some(Box::new(jvp)), is_param: false })
+}
 
+impl Add for &Tensor{
+    type Output = Tensor;
+    fn add(self, b: &Tensor) -> Tensor{
+        strided::zip_map(self, b, |x, y| x+y)
+    }
+}
 
 impl Sub for &Tensor{
     type Output = Tensor;
     fn sub(self, b: &Tensor) -> Tensor{
-
-        self+ &b.apply(|x| x*-1f32)
+        strided::zip_map(self, b, |x, y| x-y)
     }
 }
 
-
-
 impl Div for &Tensor{
     type Output = Tensor;
     fn div(self, b: &Tensor) -> Tensor{
-        hadamard_mul_direct(self, &b.apply(|x| 1f32/x))
+        strided::zip_map(self, b, |x, y| x/y)
     }
 }
 
@@ -120,6 +163,3 @@ impl Mul for &Tensor{
         hadamard_mul_direct(self, b)
     }
 }
-
-
-
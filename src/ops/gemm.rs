@@ -0,0 +1,122 @@
+/*
+produit matriciel par blocs, façon BLIS:
+    - B est découpé en blocs (KC, NC) recopiés ("packés") en panneaux de NR colonnes contigus,
+    - A en blocs (MC, KC) packés en panneaux de MR lignes,
+    - le micro noyau calcule un bloc (MR, NR) de C dans des accumulateurs locaux (des registres après
+      vectorisation automatique), en lisant les deux panneaux de facon purement séquentielle.
+le packing lit A et B via leurs strides: les vues transposées / broadcastées ne sont jamais copiées en entier,
+et quand les lignes sont contiguës (cs == 1) la copie se fait par tranches.
+*/
+
+pub(crate) const MR: usize = 4;
+const NR: usize = 16;
+const MC: usize = 64; // multiple de MR
+const KC: usize = 256;
+const NC: usize = 1024; // multiple de NR
+
+// matrice 2d vue à travers des strides (ligne, colonne) dans un buffer
+#[derive(Clone, Copy)]
+pub(crate) struct MatRef<'a>{
+    pub data: &'a [f32],
+    pub offset: usize,
+    pub rs: usize,
+    pub cs: usize,
+}
+
+impl<'a> MatRef<'a>{
+    #[inline(always)]
+    fn at(&self, i: usize, j: usize) -> f32{
+        self.data[self.offset + i*self.rs + j*self.cs]
+    }
+
+    // sous matrice qui commence à la ligne i
+    pub fn rows_from(&self, i: usize) -> MatRef<'a>{
+        MatRef { offset: self.offset + i*self.rs, ..*self }
+    }
+}
+
+// panneaux de NR colonnes: bp[q*NR*kc + p*NR + j] = B[pc+p, jc+q*NR+j], complété par des zéros
+fn pack_b(bp: &mut [f32], b: MatRef, pc: usize, kc: usize, jc: usize, nc: usize){
+    for (q, panel) in bp.chunks_exact_mut(NR*kc).take(nc.div_ceil(NR)).enumerate(){
+        let j0 = jc + q*NR;
+        let nr = NR.min(jc + nc - j0);
+        for (p, row) in panel.chunks_exact_mut(NR).enumerate(){
+            if b.cs == 1{
+                let start = b.offset + (pc+p)*b.rs + j0;
+                row[..nr].copy_from_slice(&b.data[start..start+nr]);
+            }else{
+                for (j, x) in row[..nr].iter_mut().enumerate(){
+                    *x = b.at(pc+p, j0+j);
+                }
+            }
+            row[nr..].fill(0.0);
+        }
+    }
+}
+
+// panneaux de MR lignes: ap[r*MR*kc + p*MR + i] = A[ic+r*MR+i, pc+p], complété par des zéros
+fn pack_a(ap: &mut [f32], a: MatRef, ic: usize, mc: usize, pc: usize, kc: usize){
+    for (r, panel) in ap.chunks_exact_mut(MR*kc).take(mc.div_ceil(MR)).enumerate(){
+        let i0 = ic + r*MR;
+        let mr = MR.min(ic + mc - i0);
+        for (p, col) in panel.chunks_exact_mut(MR).enumerate(){
+            for (i, x) in col.iter_mut().enumerate(){
+                *x = if i < mr {a.at(i0+i, pc+p)} else {0.0};
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn micro_kernel(ap: &[f32], bp: &[f32]) -> [[f32; NR]; MR]{
+    let mut acc = [[0f32; NR]; MR];
+    for (a, b) in ap.chunks_exact(MR).zip(bp.chunks_exact(NR)){
+        // tailles connues à la compilation: pas de bounds check, la boucle j se vectorise
+        let a: &[f32; MR] = a.try_into().unwrap();
+        let b: &[f32; NR] = b.try_into().unwrap();
+        for i in 0..MR{
+            for j in 0..NR{
+                acc[i][j] += a[i]*b[j];
+            }
+        }
+    }
+    acc
+}
+
+// c (m, n), contigu ligne par ligne = a (m, k) @ b (k, n). c est écrasé
+pub(crate) fn gemm(m: usize, k: usize, n: usize, a: MatRef, b: MatRef, c: &mut [f32]){
+    assert_eq!(c.len(), m*n, "gemm: c doit avoir m*n éléments");
+    c.fill(0.0);
+    if m == 0 || n == 0 || k == 0{
+        return;
+    }
+    let mut bp = vec![0f32; KC.min(k) * NC.min(n.div_ceil(NR)*NR)];
+    let mut ap = vec![0f32; MC.min(m.div_ceil(MR)*MR) * KC.min(k)];
+
+    for jc in (0..n).step_by(NC){
+        let nc = NC.min(n - jc);
+        for pc in (0..k).step_by(KC){
+            let kc = KC.min(k - pc);
+            pack_b(&mut bp, b, pc, kc, jc, nc);
+            for ic in (0..m).step_by(MC){
+                let mc = MC.min(m - ic);
+                pack_a(&mut ap, a, ic, mc, pc, kc);
+
+                for jr in (0..nc).step_by(NR){
+                    let nr = NR.min(nc - jr);
+                    let bpanel = &bp[jr*kc..(jr+NR)*kc];
+                    for ir in (0..mc).step_by(MR){
+                        let mr = MR.min(mc - ir);
+                        let acc = micro_kernel(&ap[ir*kc..(ir+MR)*kc], bpanel);
+                        for (i, acc_row) in acc.iter().enumerate().take(mr){
+                            let start = (ic+ir+i)*n + jc + jr;
+                            for (x, &v) in c[start..start+nr].iter_mut().zip(acc_row){
+                                *x += v;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
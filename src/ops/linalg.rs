@@ -4,32 +4,15 @@ use smallvec::{smallvec, SmallVec};
 
 use crate::tensor::Tensor; 
 use crate::tensor::Numel;
-use crate::trace::{Trace, NodeId};
+use crate::trace::{Trace, NodeId, OpKind};
+use crate::ops::shapes::{sum_to_shape, mat_transpose, unsqueeze};
+use crate::ops::gemm::{gemm, MatRef, MR};
+use crate::utils::parallel;
 
 
-#[inline(always)]
-fn matmul2d_vec(a: &Tensor, b: &Tensor) -> Vec<f32>{
-    assert_eq!(a.shape.len(), 2, "matmul2d: A doit être 2D, shape={:?}", a.shape);
-    assert_eq!(b.shape.len(), 2, "matmul2d: B doit être 2D, shape={:?}", b.shape);
+// en dessous (en multiplications-additions), lancer des threads coute plus que le calcul lui meme
+const PAR_MIN_FLOPS: usize = 1 << 18;
 
-    let (m, p) = (a.shape[0], a.shape[1]); // A: (m, n)
-    let (p2, n) = (b.shape[0], b.shape[1]); // B: (n, p)
-    assert_eq!(p, p2, "matmul2d: dimensions incompatibles");
-
-
-
-    let mut c = Vec::with_capacity(m*n);
-    for i in 0..m{
-        for j in 0..n{
-            let mut sum = 0.0;
-            for k in 0..p{
-                sum+=a.get2(i, k)*b.get2(k, j); 
-            }
-            c.push(sum);
-        }
-    }
-    c
-}
 fn tensor_mul_helper(a : &Tensor, b: &Tensor) -> Tensor{
     // hack pour que ce soit plus simple : 
     let a_order = a.shape.len(); 
@@ -52,7 +35,7 @@ fn tensor_mul_helper(a : &Tensor, b: &Tensor) -> Tensor{
 
 
 
-    let batch = Tensor::broadcast_shape(&batch_a, &batch_b).unwrap();
+    let batch = Tensor::broadcast_shape(batch_a, batch_b).unwrap();
 
     let mut out_shape = batch.clone(); 
     out_shape.push(m); 
@@ -69,19 +52,40 @@ fn tensor_mul_helper(a : &Tensor, b: &Tensor) -> Tensor{
   //  println!("broadcasted succesfully! Tensor a_b : {}, \n Tensor b_b : {}", a_b, b_b);
     // assert_eq!(a_b.shape[a_order-1], b_b.shape[b_order-2], "dimensions non ok pour multiplication des tenseurs (2 dernieres couches)");
 
-    let mut c = Vec::with_capacity(out_shape.numel());
-
-    let lin_max = batch.numel();
-   // println!("{}", lin_max);
-    for lin in 0..lin_max{ // iterer a travers les batch
-        let idx_from_lin = Tensor::idx_from_lin(&batch, lin);
-        let a_b_2d = a_b.vue2d(a_b.batch_offset(&idx_from_lin));
-        let b_b_2d = b_b.vue2d(b_b.batch_offset(&idx_from_lin));
-      //  println!("A vue 2d: {} \n, B vue 2d: {}, \n, lin: {}", a_b_2d, b_b_2d, lin);
-        c.extend(matmul2d_vec(&a_b_2d, &b_b_2d));
- 
+    let nb = batch.numel();
+    let mut c = vec![0f32; out_shape.numel()];
+    if c.is_empty(){
+        return Tensor::from_owned(c, &out_shape).unwrap();
     }
-    Tensor::from_vec(&c, &out_shape).unwrap()
+
+    // matrices 2d du batch numéro lin, vues à travers les strides (broadcast => stride 0, transposée => strides échangés)
+    let r = out_shape.len();
+    let mats = |lin: usize| -> (MatRef, MatRef){
+        let idx = Tensor::idx_from_lin(&batch, lin);
+        let am = MatRef { data: &a_b.data, offset: a_b.offset + a_b.batch_offset(&idx), rs: a_b.strides[r-2], cs: a_b.strides[r-1] };
+        let bm = MatRef { data: &b_b.data, offset: b_b.offset + b_b.batch_offset(&idx), rs: b_b.strides[r-2], cs: b_b.strides[r-1] };
+        (am, bm)
+    };
+
+    // un morceau de travail par matrice du batch, redécoupé en blocs de lignes si le batch ne suffit pas à occuper les threads
+    let threads = if nb*m*n*p >= PAR_MIN_FLOPS {parallel::num_threads()} else {1};
+    let rows = if nb >= threads {m} else {m.div_ceil(threads.div_ceil(nb)).next_multiple_of(MR)};
+    let mut items = Vec::new();
+    for (lin, c_mat) in c.chunks_mut(m*n).enumerate(){
+        for (blk, c_rows) in c_mat.chunks_mut(rows*n).enumerate(){
+            items.push((lin, blk*rows, c_rows));
+        }
+    }
+    let work = |(lin, i0, c_rows): (usize, usize, &mut [f32])| {
+        let (am, bm) = mats(lin);
+        gemm(c_rows.len()/n, p, n, am.rows_from(i0), bm, c_rows);
+    };
+    if threads > 1{
+        parallel::for_each(items, work);
+    }else{
+        items.into_iter().for_each(work);
+    }
+    Tensor::from_owned(c, &out_shape).unwrap()
 
 }
 
@@ -140,11 +144,15 @@ pub fn matmul(tr: &mut Trace, a_id: NodeId, b_id: NodeId) -> NodeId{
 
     let c = tensor_mul(&a, &b);// moyen écrit comme ca. TODO: clean ce truc
     
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::MatMul, c, smallvec![a_id, b_id]);
+    }
+
     let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
 
 
         if a_rank == 1 && b_rank >=2{   
-            let ga = tensor_mul(&g_out, &b.mat_transpose()).sum_over_broadcasted_batches(&a.shape);
+            let ga = tensor_mul(g_out, &b.mat_transpose()).sum_over_broadcasted_batches(&a.shape);
 
             let a_col = a.unsqueeze_view(1); // ona du (M, 1)
             // (M, 1) @ (..., 1 N) => il faut ajouter  1 a gout en avant dernier
@@ -154,7 +162,7 @@ pub fn matmul(tr: &mut Trace, a_id: NodeId, b_id: NodeId) -> NodeId{
             smallvec![(a_id, ga), (b_id, gb)]
 
         }else if a_rank >=2 && b_rank == 1{
-            let gb = tensor_mul(&a.mat_transpose(), &g_out).sum_over_broadcasted_batches(&b.shape);
+            let gb = tensor_mul(&a.mat_transpose(), g_out).sum_over_broadcasted_batches(&b.shape);
 
             let b_lin = b.unsqueeze_view(0); // on a du (1, M)
             let g_out_fixed = g_out.unsqueeze_view(g_out.shape.len());
@@ -164,13 +172,43 @@ pub fn matmul(tr: &mut Trace, a_id: NodeId, b_id: NodeId) -> NodeId{
             smallvec![(a_id, ga), (b_id, gb)]
 
         }else{
-            let ga = tensor_mul(&g_out, &b.mat_transpose()).sum_over_broadcasted_batches(&a.shape);
-            let gb = tensor_mul(&a.mat_transpose(), &g_out).sum_over_broadcasted_batches(&b.shape);
+            let ga = tensor_mul(g_out, &b.mat_transpose()).sum_over_broadcasted_batches(&a.shape);
+            let gb = tensor_mul(&a.mat_transpose(), g_out).sum_over_broadcasted_batches(&b.shape);
             
             smallvec![(a_id, ga), (b_id, gb)]
         }
 
     }; 
 
-    tr.push(crate::trace::Node { value: c, parents_id: smallvec![a_id, b_id], vjp: Some(Box::new(vjp)), is_param: false })
+    // meme chose que vjp, mais avec des noeuds
+    let (a_shape, b_shape) = (tr.get_tensor(a_id).shape.clone(), tr.get_tensor(b_id).shape.clone());
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let (ga, gb) = if a_rank == 1 && b_rank >= 2{
+            let bt = mat_transpose(tr, b_id);
+            let ga = matmul(tr, g_out, bt);
+            let a_col = unsqueeze(tr, a_id, 1);
+            let g_rank = tr.get_tensor(g_out).shape.len();
+            let g_fixed = unsqueeze(tr, g_out, g_rank-1);
+            (ga, matmul(tr, a_col, g_fixed))
+        }else if a_rank >= 2 && b_rank == 1{
+            let at = mat_transpose(tr, a_id);
+            let gb = matmul(tr, at, g_out);
+            let b_lin = unsqueeze(tr, b_id, 0);
+            let g_rank = tr.get_tensor(g_out).shape.len();
+            let g_fixed = unsqueeze(tr, g_out, g_rank);
+            (matmul(tr, g_fixed, b_lin), gb)
+        }else{
+            let bt = mat_transpose(tr, b_id);
+            let at = mat_transpose(tr, a_id);
+            (matmul(tr, g_out, bt), matmul(tr, at, g_out))
+        };
+        smallvec![(a_id, sum_to_shape(tr, ga, &a_shape)), (b_id, sum_to_shape(tr, gb, &b_shape))]
+    };
+
+    // d(a@b) = da@b + a@db
+    let (ja, jb) = (tr.get_tensor(a_id).clone(), tr.get_tensor(b_id).clone());
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        &tensor_mul(&t[0], &jb) + &tensor_mul(&ja, &t[1])
+    };
+    tr.push(crate::trace::Node { op: OpKind::MatMul, value: c, parents_id: smallvec![a_id, b_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
 }
\ No newline at end of file
@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use smallvec::{smallvec, SmallVec};
+
+use crate::tensor::Tensor;
+use crate::tensor::Numel;
+use crate::trace::{Trace, NodeId, Node, OpKind};
+use crate::ops::conv::conv_out_dim;
+
+/*
+pooling sur des tenseurs NCHW: x: (N, C, H, W) => y: (N, C, OH, OW).
+chaque fenetre est donnée par les bornes [h0, h1) x [w0, w1) dans l'entrée (padding déjà retiré).
+on lit x avec get() pour respecter strides/offset: x peut etre une vue (transposée, broadcastée...).
+*/
+
+fn pool_check(x: &Tensor, name: &str){
+    assert_eq!(x.shape.len(), 4, "{name}: x doit etre (N, C, H, W), shape={:?}", x.shape);
+}
+
+// fenetre [start, end) dans l'entrée pour la position de sortie `out`, coupée au bord
+#[inline(always)]
+fn window(out: usize, kernel: usize, stride: usize, padding: usize, size: usize) -> (usize, usize){
+    let start = (out*stride) as isize - padding as isize; 
+    let end = (start + kernel as isize).min(size as isize);
+    (start.max(0) as usize, end.max(0) as usize)
+}
+
+// bornes pytorch pour l'adaptive pooling: [floor(i*H/OH), ceil((i+1)*H/OH))
+#[inline(always)]
+fn adaptive_window(out: usize, out_size: usize, size: usize) -> (usize, usize){
+    (out*size/out_size, ((out+1)*size).div_ceil(out_size))
+}
+
+pub fn max_pool2d_direct(x: &Tensor, kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize)) -> (Tensor, Vec<usize>){
+    pool_check(x, "max_pool2d");
+    assert!(padding.0*2 <= kernel.0 && padding.1*2 <= kernel.1, "max_pool2d: le padding doit etre <= kernel/2");
+    let (n, c, h, w) = (x.shape[0], x.shape[1], x.shape[2], x.shape[3]);
+    let oh = conv_out_dim(h, kernel.0, stride.0, padding.0, 1);
+    let ow = conv_out_dim(w, kernel.1, stride.1, padding.1, 1);
+
+    let mut y = Vec::with_capacity(n*c*oh*ow);
+    let mut args = Vec::with_capacity(n*c*oh*ow); // indice linéaire (contigu, dans x.shape) du max
+    for ni in 0..n{
+        for ci in 0..c{
+            for i in 0..oh{
+                let (h0, h1) = window(i, kernel.0, stride.0, padding.0, h);
+                for j in 0..ow{
+                    let (w0, w1) = window(j, kernel.1, stride.1, padding.1, w);
+                    let mut best = f32::NEG_INFINITY; 
+                    let mut arg = ((ni*c + ci)*h + h0)*w + w0;
+                    for hi in h0..h1{
+                        for wi in w0..w1{
+                            let v = x.get(&[ni, ci, hi, wi]);
+                            if v > best{
+                                best = v; 
+                                arg = ((ni*c + ci)*h + hi)*w + wi;
+                            }
+                        }
+                    }
+                    y.push(best);
+                    args.push(arg);
+                }
+            }
+        }
+    }
+    (Tensor::from_owned(y, &[n, c, oh, ow]).unwrap(), args)
+}
+
+pub fn max_pool2d(tr: &mut Trace, x_id: NodeId, kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize)) -> NodeId{
+    let x = tr.get_tensor(x_id); 
+    let x_shape = x.shape.clone();
+    let (y, args) = max_pool2d_direct(x, kernel, stride, padding);
+    let args = Arc::new(args);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::MaxPool2d{kernel, stride, padding}, y, smallvec![x_id]);
+    }
+
+    let (args_t, args_j) = (args.clone(), args.clone());
+    let x_shape_t = x_shape.clone();
+
+    // tout le gradient de la fenetre part sur l'argmax
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, scatter_add_direct(g_out, &args, &x_shape))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, scatter_add(tr, g_out, args_t.clone(), &x_shape_t))]
+    };
+    // on garde les memes argmax: la tangente est prise aux memes positions
+    let y_shape = y.shape.clone();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        gather_direct(&t[0], &args_j, &y_shape)
+    };
+    tr.push(Node { op: OpKind::MaxPool2d{kernel, stride, padding}, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+/*
+scatter_add et gather (avec les memes indices) sont adjoints: c'est la vjp du max pooling, et la vjp de cette vjp.
+    scatter_add: out[idx[lin]] += g[lin]     (out de shape out_shape)
+    gather:      out[lin] = h[idx[lin]]      (out de shape out_shape)
+*/
+fn scatter_add_direct(g: &Tensor, idx: &[usize], out_shape: &[usize]) -> Tensor{
+    let mut out = vec![0f32; out_shape.numel()];
+    for (lin, &i) in idx.iter().enumerate(){
+        out[i] += g.get_from_lin(lin);
+    }
+    Tensor::from_owned(out, out_shape).unwrap()
+}
+
+fn gather_direct(h: &Tensor, idx: &[usize], out_shape: &[usize]) -> Tensor{
+    let h = h.contiguous();
+    Tensor::from_owned(idx.iter().map(|&i| h.data[i]).collect(), out_shape).unwrap()
+}
+
+fn scatter_add(tr: &mut Trace, g_id: NodeId, idx: Arc<Vec<usize>>, out_shape: &[usize]) -> NodeId{
+    let g_shape = tr.get_tensor(g_id).shape.clone();
+    let y = scatter_add_direct(tr.get_tensor(g_id), &idx, out_shape);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::PoolScatter, y, smallvec![g_id]);
+    }
+
+    let (idx_t, g_shape_t) = (idx.clone(), g_shape.clone());
+    let (idx_j, out_shape_j) = (idx.clone(), out_shape.to_vec());
+
+    let vjp = move |h: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(g_id, gather_direct(h, &idx, &g_shape))]
+    };
+    let vjp_traced = move |tr: &mut Trace, h: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(g_id, gather(tr, h, idx_t.clone(), &g_shape_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        scatter_add_direct(&t[0], &idx_j, &out_shape_j)
+    };
+    tr.push(Node { op: OpKind::PoolScatter, value: y, parents_id: smallvec![g_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+fn gather(tr: &mut Trace, h_id: NodeId, idx: Arc<Vec<usize>>, out_shape: &[usize]) -> NodeId{
+    let h_shape = tr.get_tensor(h_id).shape.clone();
+    let y = gather_direct(tr.get_tensor(h_id), &idx, out_shape);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::PoolGather, y, smallvec![h_id]);
+    }
+
+    let (idx_t, h_shape_t) = (idx.clone(), h_shape.clone());
+    let (idx_j, out_shape_j) = (idx.clone(), out_shape.to_vec());
+
+    let vjp = move |g: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(h_id, scatter_add_direct(g, &idx, &h_shape))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(h_id, scatter_add(tr, g, idx_t.clone(), &h_shape_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        gather_direct(&t[0], &idx_j, &out_shape_j)
+    };
+    tr.push(Node { op: OpKind::PoolGather, value: y, parents_id: smallvec![h_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// pour chaque sortie (i, j): sa fenetre ((h0, h1), (w0, w1)) et le diviseur
+type PoolWindows = Arc<dyn Fn(usize, usize) -> ((usize, usize), (usize, usize), f32) + Send + Sync>;
+
+fn avg_pool_windows(x: &Tensor, oh: usize, ow: usize, win: &PoolWindows) -> Tensor{
+    let (n, c) = (x.shape[0], x.shape[1]);
+    let mut y = Vec::with_capacity(n*c*oh*ow);
+    for ni in 0..n{
+        for ci in 0..c{
+            for i in 0..oh{
+                for j in 0..ow{
+                    let ((h0, h1), (w0, w1), div) = win(i, j);
+                    let mut sum = 0f32; 
+                    for hi in h0..h1{
+                        for wi in w0..w1{
+                            sum += x.get(&[ni, ci, hi, wi]);
+                        }
+                    }
+                    y.push(sum/div);
+                }
+            }
+        }
+    }
+    Tensor::from_owned(y, &[n, c, oh, ow]).unwrap()
+}
+
+fn avg_pool_backward(x_shape: &[usize], g_out: &Tensor, win: &PoolWindows) -> Tensor{
+    let (n, c, h, w) = (x_shape[0], x_shape[1], x_shape[2], x_shape[3]);
+    let (oh, ow) = (g_out.shape[2], g_out.shape[3]);
+    let mut gx = vec![0f32; x_shape.numel()];
+    for ni in 0..n{
+        for ci in 0..c{
+            for i in 0..oh{
+                for j in 0..ow{
+                    let ((h0, h1), (w0, w1), div) = win(i, j);
+                    // le gradient est réparti uniformément sur la fenetre
+                    let g = g_out.get(&[ni, ci, i, j])/div; 
+                    for hi in h0..h1{
+                        for wi in w0..w1{
+                            gx[((ni*c + ci)*h + hi)*w + wi] += g;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Tensor::from_owned(gx, x_shape).unwrap()
+}
+
+// l'average pooling est linéaire: sa vjp (avg_pool_backward) a pour adjoint le pooling lui meme
+fn push_avg_pool(tr: &mut Trace, x_id: NodeId, oh: usize, ow: usize, win: PoolWindows, op: OpKind) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let x_shape = x.shape.clone();
+    let y = avg_pool_windows(x, oh, ow, &win);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let (win_t, x_shape_t, op_t) = (win.clone(), x_shape.clone(), op.clone());
+    let win_j = win.clone();
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, avg_pool_backward(&x_shape, g_out, &win))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, push_avg_pool_backward(tr, g_out, &x_shape_t, win_t.clone(), op_t.clone()))]
+    };
+    // linéaire: meme op sur la tangente
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        avg_pool_windows(&t[0], oh, ow, &win_j)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// fwd_op: le pooling d'origine, recréé par la vjp tracée
+fn push_avg_pool_backward(tr: &mut Trace, g_id: NodeId, x_shape: &[usize], win: PoolWindows, fwd_op: OpKind) -> NodeId{
+    let g_shape = tr.get_tensor(g_id).shape.clone();
+    let y = avg_pool_backward(x_shape, tr.get_tensor(g_id), &win);
+    let (oh, ow) = (g_shape[2], g_shape[3]);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::AvgPoolGrad, y, smallvec![g_id]);
+    }
+
+    let (win_t, win_j) = (win.clone(), win.clone());
+
+    let vjp = move |h: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(g_id, avg_pool_windows(h, oh, ow, &win))]
+    };
+    let vjp_traced = move |tr: &mut Trace, h: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(g_id, push_avg_pool(tr, h, oh, ow, win_t.clone(), fwd_op.clone()))]
+    };
+    let x_shape_j = x_shape.to_vec();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        avg_pool_backward(&x_shape_j, &t[0], &win_j)
+    };
+    tr.push(Node { op: OpKind::AvgPoolGrad, value: y, parents_id: smallvec![g_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// le padding compte comme des zéros: on divise toujours par kh*kw (count_include_pad de pytorch)
+pub fn avg_pool2d(tr: &mut Trace, x_id: NodeId, kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize)) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    pool_check(x, "avg_pool2d");
+    let (h, w) = (x.shape[2], x.shape[3]);
+    let oh = conv_out_dim(h, kernel.0, stride.0, padding.0, 1);
+    let ow = conv_out_dim(w, kernel.1, stride.1, padding.1, 1);
+    let div = (kernel.0*kernel.1) as f32;
+
+    push_avg_pool(tr, x_id, oh, ow, Arc::new(move |i, j| (
+        window(i, kernel.0, stride.0, padding.0, h), 
+        window(j, kernel.1, stride.1, padding.1, w), 
+        div
+    )), OpKind::AvgPool2d { kernel, stride, padding })
+}
+
+// (N, C, H, W) => (N, C, OH, OW), fenetres de tailles variables qui recouvrent toute l'entrée
+pub fn adaptive_avg_pool2d(tr: &mut Trace, x_id: NodeId, out_size: (usize, usize)) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    pool_check(x, "adaptive_avg_pool2d");
+    let (h, w) = (x.shape[2], x.shape[3]);
+    let (oh, ow) = out_size;
+    assert!(oh > 0 && ow > 0, "adaptive_avg_pool2d: taille de sortie nulle");
+
+    push_avg_pool(tr, x_id, oh, ow, Arc::new(move |i, j| {
+        let wh = adaptive_window(i, oh, h);
+        let ww = adaptive_window(j, ow, w);
+        (wh, ww, ((wh.1-wh.0)*(ww.1-ww.0)) as f32)
+    }), OpKind::AdaptiveAvgPool2d(out_size))
+}
+
+// (N, C, H, W) => (N, C, 1, 1)
+pub fn global_avg_pool2d(tr: &mut Trace, x_id: NodeId) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    pool_check(x, "global_avg_pool2d");
+    let (h, w) = (x.shape[2], x.shape[3]);
+    let div = (h*w) as f32;
+
+    push_avg_pool(tr, x_id, 1, 1, Arc::new(move |_, _| ((0, h), (0, w), div)), OpKind::GlobalAvgPool2d)
+}
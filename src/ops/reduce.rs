@@ -0,0 +1,380 @@
+use smallvec::{smallvec, SmallVec};
+
+use crate::tensor::Tensor;
+use crate::tensor::strided;
+use crate::trace::{Trace, NodeId, Node, OpKind};
+use crate::ops::elementwise::{add, hadamard_mul, scale, sub};
+use crate::ops::shapes::{broadcast_to, unsqueeze};
+use crate::nn::functions::apply_twice;
+
+/*
+réductions sur des axes quelconques (cf Tensor::sum ...). axes vide = tous les axes, et les axes sont normalisés
+(triés, explicites) dans l'OpKind. le gradient de la sortie est remis à la shape de l'entrée: on réinsère les
+axes réduits (si !keepdim) puis on broadcaste, comme pour sum_all / sum_last.
+*/
+
+// sortie (keepdim ou non) => shape keepdim
+fn keep(t: &Tensor, axes: &[usize], keepdim: bool) -> Tensor{
+    if keepdim{
+        return t.clone();
+    }
+    axes.iter().fold(t.clone(), |t, &a| t.unsqueeze_view(a))
+}
+
+fn expand_back(g: &Tensor, x_shape: &[usize], axes: &[usize], keepdim: bool) -> Tensor{
+    keep(g, axes, keepdim).broadcast_view(x_shape).unwrap()
+}
+
+fn expand_back_traced(tr: &mut Trace, g: NodeId, x_shape: &[usize], axes: &[usize], keepdim: bool) -> NodeId{
+    let mut g = g;
+    if !keepdim{
+        for &a in axes{
+            g = unsqueeze(tr, g, a);
+        }
+    }
+    broadcast_to(tr, g, x_shape)
+}
+
+fn recip(tr: &mut Trace, x: NodeId) -> NodeId{
+    apply_twice(tr, x, |v| 1f32/v, |v| -1f32/(v*v), |v| 2f32/(v*v*v))
+}
+
+fn exp(tr: &mut Trace, x: NodeId) -> NodeId{
+    apply_twice(tr, x, f32::exp, f32::exp, f32::exp)
+}
+
+pub fn sum(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.sum(&axes, keepdim);
+    let op = OpKind::Sum { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let x_shape = x.shape.clone();
+    let (x_shape_t, axes_t, axes_j) = (x_shape.clone(), axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, expand_back(g_out, &x_shape, &axes, keepdim))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, expand_back_traced(tr, g_out, &x_shape_t, &axes_t, keepdim))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn mean(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.mean(&axes, keepdim);
+    let op = OpKind::Mean { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let n = x.reduced_numel(&axes) as f32;
+    let x_shape = x.shape.clone();
+    let (x_shape_t, axes_t, axes_j) = (x_shape.clone(), axes.clone(), axes.clone());
+
+    // d/dx mean = 1/n sur chaque élément du bloc
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, expand_back(&g_out.apply(|g| g/n), &x_shape, &axes, keepdim))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let g = scale(tr, g_out, 1f32/n);
+        smallvec![(x_id, expand_back_traced(tr, g, &x_shape_t, &axes_t, keepdim))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].mean(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn max(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    extremum(tr, x_id, axes, keepdim, true)
+}
+
+pub fn min(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    extremum(tr, x_id, axes, keepdim, false)
+}
+
+/*
+max / min: le gradient va aux éléments qui atteignent l'extremum, partagé à parts égales en cas d'égalité.
+ces poids sont constants par morceaux: la vjp tracée les met dans un noeud constant (dérivée seconde nulle).
+*/
+fn extremum(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool, is_max: bool) -> NodeId{
+    let x = tr.get_tensor(x_id).clone();
+    let axes = x.reduce_axes(axes);
+    let y = if is_max {x.max(&axes, keepdim)} else {x.min(&axes, keepdim)};
+    let op = if is_max {OpKind::Max { axes: axes.clone(), keepdim }} else {OpKind::Min { axes: axes.clone(), keepdim }};
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let hit = strided::zip_map(&x, &keep(&y, &axes, keepdim), |v, m| if v == m {1f32} else {0f32});
+    let w = &hit / &hit.sum(&axes, true);
+    let (w_t, w_j) = (w.clone(), w.clone());
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, &expand_back(g_out, &w.shape, &axes, keepdim) * &w)]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let g = expand_back_traced(tr, g_out, &w_t.shape, &axes_t, keepdim);
+        let w = tr.input(w_t.clone());
+        smallvec![(x_id, hadamard_mul(tr, g, w))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &w_j).sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+/*
+d/dx_i prod = produit des autres éléments du bloc. calculé sans division par x_i, pour rester juste avec des zéros:
+    aucun zéro dans le bloc: prod/x_i
+    un seul zéro:            produit des non nuls pour ce zéro, 0 ailleurs
+    plusieurs:               0 partout
+*/
+fn prod_others(x: &Tensor, axes: &[usize]) -> Tensor{
+    let nonzero_prod = x.apply(|v| if v == 0f32 {1f32} else {v}).prod(axes, true);
+    let zeros = x.apply(|v| if v == 0f32 {1f32} else {0f32}).sum(axes, true);
+    let others = strided::zip_map(x, &nonzero_prod, |v, p| if v == 0f32 {p} else {p/v});
+    let mask = strided::zip_map(x, &zeros, |v, z| if z == 0f32 || (z == 1f32 && v == 0f32) {1f32} else {0f32});
+    &others * &mask
+}
+
+/*
+meme décomposition sur la trace, dérivable à son tour. les masques (où sont les zéros) sont des constantes:
+    nz = x avec les zéros remplacés par 1, p = prod(nz): p/nz ne divise jamais par zéro
+    q = 1 si le bloc n'a pas de zéro, ou si x_i est son seul zéro; x_k si x_k est le seul zéro et i != k; 0 sinon
+prod des autres = p/nz * q. exact à tous les ordres tant qu'un bloc a au plus un zéro; au dela, les dérivées
+secondes qui passent par deux zéros sont perdues (les valeurs et le gradient restent exacts).
+*/
+fn prod_others_traced(tr: &mut Trace, x_id: NodeId, axes: &[usize]) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let x_shape = x.shape.clone();
+    let is_zero = x.apply(|v| if v == 0f32 {1f32} else {0f32});
+    let zeros = is_zero.sum(axes, true);
+    let a = strided::zip_map(x, &zeros, |v, z| if z == 0f32 || (z == 1f32 && v == 0f32) {1f32} else {0f32});
+    let b = strided::zip_map(x, &zeros, |v, z| if z == 1f32 && v != 0f32 {1f32} else {0f32});
+    let nonzero = is_zero.apply(|z| 1f32 - z);
+
+    let (is_zero, nonzero, a, b) = (tr.input(is_zero), tr.input(nonzero), tr.input(a), tr.input(b));
+    let kept = hadamard_mul(tr, x_id, nonzero);
+    let nz = add(tr, kept, is_zero);
+    let p = prod(tr, nz, axes, true);
+    let p = broadcast_to(tr, p, &x_shape);
+    let inv = recip(tr, nz);
+    let others = hadamard_mul(tr, p, inv);
+
+    // le seul zéro du bloc, s'il y en a un: somme des x_k masqués
+    let z = hadamard_mul(tr, x_id, is_zero);
+    let z = sum(tr, z, axes, true);
+    let z = broadcast_to(tr, z, &x_shape);
+    let bz = hadamard_mul(tr, b, z);
+    let q = add(tr, a, bz);
+    hadamard_mul(tr, others, q)
+}
+
+pub fn prod(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.prod(&axes, keepdim);
+    let op = OpKind::Prod { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let others = prod_others(x, &axes);
+    let others_j = others.clone();
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, &expand_back(g_out, &others.shape, &axes, keepdim) * &others)]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let x_shape = tr.get_tensor(x_id).shape.clone();
+        let g = expand_back_traced(tr, g_out, &x_shape, &axes_t, keepdim);
+        let others = prod_others_traced(tr, x_id, &axes_t);
+        smallvec![(x_id, hadamard_mul(tr, g, others))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &others_j).sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// d/dx_i var = 2 (x_i - mean)/n
+pub fn var(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.var(&axes, keepdim);
+    let op = OpKind::Var { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let n = x.reduced_numel(&axes) as f32;
+    let d = x - &x.mean(&axes, true);
+    let d_j = d.clone();
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        let g = expand_back(g_out, &d.shape, &axes, keepdim);
+        smallvec![(x_id, strided::zip_map(&g, &d, |g, d| 2f32*g*d/n))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let x_shape = tr.get_tensor(x_id).shape.clone();
+        let g = expand_back_traced(tr, g_out, &x_shape, &axes_t, keepdim);
+        let m = mean(tr, x_id, &axes_t, true);
+        let d = sub(tr, x_id, m);
+        let d = scale(tr, d, 2f32/n);
+        smallvec![(x_id, hadamard_mul(tr, g, d))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &d_j).sum(&axes_j, keepdim).apply(|v| 2f32*v/n)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// d/dx_i std = (x_i - mean)/(n std)
+pub fn std(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.std(&axes, keepdim);
+    let op = OpKind::Std { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let n = x.reduced_numel(&axes) as f32;
+    let d = x - &x.mean(&axes, true);
+    let w = &d / &keep(&y, &axes, keepdim).apply(|s| s*n);
+    let w_j = w.clone();
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, &expand_back(g_out, &w.shape, &axes, keepdim) * &w)]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let x_shape = tr.get_tensor(x_id).shape.clone();
+        let g = expand_back_traced(tr, g_out, &x_shape, &axes_t, keepdim);
+        let m = mean(tr, x_id, &axes_t, true);
+        let d = sub(tr, x_id, m);
+        let d = scale(tr, d, 1f32/n);
+        let s = std(tr, x_id, &axes_t, true);
+        let inv = recip(tr, s);
+        let gd = hadamard_mul(tr, g, d);
+        smallvec![(x_id, hadamard_mul(tr, gd, inv))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &w_j).sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// d/dx_i logsumexp = softmax du bloc = exp(x_i - lse)
+pub fn logsumexp(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.logsumexp(&axes, keepdim);
+    let op = OpKind::LogSumExp { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let soft = strided::zip_map(x, &keep(&y, &axes, keepdim), |v, l| (v-l).exp());
+    let soft_j = soft.clone();
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, &expand_back(g_out, &soft.shape, &axes, keepdim) * &soft)]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let x_shape = tr.get_tensor(x_id).shape.clone();
+        let g = expand_back_traced(tr, g_out, &x_shape, &axes_t, keepdim);
+        let l = logsumexp(tr, x_id, &axes_t, true);
+        let d = sub(tr, x_id, l);
+        let s = exp(tr, d);
+        smallvec![(x_id, hadamard_mul(tr, g, s))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &soft_j).sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// d/dx_i |x| = x_i/|x|, pris nul quand la norme du bloc est nulle
+pub fn norm(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = x.norm(&axes, keepdim);
+    let op = OpKind::Norm { axes: axes.clone(), keepdim };
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let w = strided::zip_map(x, &keep(&y, &axes, keepdim), |v, n| if n == 0f32 {0f32} else {v/n});
+    let w_j = w.clone();
+    let (axes_t, axes_j) = (axes.clone(), axes.clone());
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, &expand_back(g_out, &w.shape, &axes, keepdim) * &w)]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let x_shape = tr.get_tensor(x_id).shape.clone();
+        let g = expand_back_traced(tr, g_out, &x_shape, &axes_t, keepdim);
+        // norme nulle => x nul sur le bloc: on divise par 1 et le gradient vaut 0, comme dans vjp
+        let n = norm(tr, x_id, &axes_t, true);
+        let is_zero = tr.get_tensor(n).apply(|v| if v == 0f32 {1f32} else {0f32});
+        let is_zero = tr.input(is_zero);
+        let n = add(tr, n, is_zero);
+        let inv = recip(tr, n);
+        let inv = broadcast_to(tr, inv, &x_shape);
+        let gx = hadamard_mul(tr, g, x_id);
+        smallvec![(x_id, hadamard_mul(tr, gx, inv))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        (&t[0] * &w_j).sum(&axes_j, keepdim)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn argmax(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    arg_extremum(tr, x_id, axes, keepdim, true)
+}
+
+pub fn argmin(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool) -> NodeId{
+    arg_extremum(tr, x_id, axes, keepdim, false)
+}
+
+// indices (en f32): constants par morceaux, donc pas de vjp (le backward s'arrete ici) et une tangente nulle, comme stop_gradient
+fn arg_extremum(tr: &mut Trace, x_id: NodeId, axes: &[usize], keepdim: bool, is_max: bool) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let axes = x.reduce_axes(axes);
+    let y = if is_max {x.argmax(&axes, keepdim)} else {x.argmin(&axes, keepdim)};
+    let op = if is_max {OpKind::ArgMax { axes, keepdim }} else {OpKind::ArgMin { axes, keepdim }};
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(op, y, smallvec![x_id]);
+    }
+
+    let shape = y.shape.clone();
+    let jvp = move |_: &[Tensor]| -> Tensor{
+        Tensor::zeros(&shape)
+    };
+    tr.push(Node { op, value: y, parents_id: smallvec![x_id], vjp: None, vjp_traced: None, jvp: Some(Box::new(jvp)), is_param: false })
+}
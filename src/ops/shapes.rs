@@ -2,22 +2,278 @@ use smallvec::{smallvec, SmallVec};
 
 use crate::tensor::Tensor; 
 use crate::tensor::Numel;
-use crate::trace::{Trace, NodeId};
+use crate::trace::{Trace, NodeId, Node, OpKind};
+use crate::ops::elementwise::scale;
 
 pub fn mean_all(tr: &mut Trace, x_id: NodeId) -> NodeId{
     let x = tr.get_tensor(x_id).clone(); 
     let n = x.shape.numel();
-    let y = x.sum_all(); 
+    let y = x.sum_all().apply(|v| v/(n as f32)); 
+    let x_shape = x.shape.clone();
 
 
     /*
     d/dx mean x = 1/n. 
      */
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::MeanAll, y, smallvec![x_id]);
+    }
+
  //   println!("X SHAPE :  {:?}", x.shape);
     let vjp = move |g_out: &Tensor|  -> SmallVec<[(NodeId, Tensor); 2]>{
         let gx = g_out.apply(|x| x/(n as f32)).broadcast_view(&x.shape).unwrap();
    //     println!("G X, G OUT: : {} {}" ,gx, g_out);
         smallvec![(x_id, gx)]
     };
-    tr.push(crate::trace::Node { value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), is_param: false })
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        let g = scale(tr, g_out, 1f32/(n as f32));
+        smallvec![(x_id, broadcast_to(tr, g, &x_shape))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].sum_all().apply(|x| x/(n as f32))
+    };
+    tr.push(Node { op: OpKind::MeanAll, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// () = somme de tous les éléments
+pub fn sum_all(tr: &mut Trace, x_id: NodeId) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let y = x.sum_all();
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::SumAll, y, smallvec![x_id]);
+    }
+
+    let x_shape = x.shape.clone(); 
+    let x_shape_t = x.shape.clone(); 
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.broadcast_view(&x_shape).unwrap())]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, broadcast_to(tr, g_out, &x_shape_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].sum_all()
+    };
+    tr.push(Node { op: OpKind::SumAll, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// (..., n) => (..., 1)
+pub fn sum_last(tr: &mut Trace, x_id: NodeId) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    let y = x.sum_last();
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::SumLast, y, smallvec![x_id]);
+    }
+
+    let x_shape = x.shape.clone(); 
+    let x_shape_t = x.shape.clone(); 
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.broadcast_view(&x_shape).unwrap())]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, broadcast_to(tr, g_out, &x_shape_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].sum_last()
+    };
+    tr.push(Node { op: OpKind::SumLast, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+/*
+sum_to_shape et broadcast_to sont adjointes l'une de l'autre: 
+c'est ce qui permet d'écrire les vjp des ops broadcastées (add, hadamard_mul...) avec des noeuds.
+si la shape est déjà la bonne, on ne crée pas de noeud.
+*/
+pub fn sum_to_shape(tr: &mut Trace, x_id: NodeId, shape: &[usize]) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    if x.shape == shape{
+        return x_id;
+    }
+    let y = x.sum_over_broadcasted_batches(shape);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::SumToShape(shape.to_vec()), y, smallvec![x_id]);
+    }
+
+    let x_shape = x.shape.clone(); 
+    let x_shape_t = x.shape.clone(); 
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.broadcast_view(&x_shape).unwrap())]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, broadcast_to(tr, g_out, &x_shape_t))]
+    };
+    let out_shape = shape.to_vec();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].sum_over_broadcasted_batches(&out_shape)
+    };
+    tr.push(Node { op: OpKind::SumToShape(shape.to_vec()), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn broadcast_to(tr: &mut Trace, x_id: NodeId, shape: &[usize]) -> NodeId{
+    let x = tr.get_tensor(x_id);
+    if x.shape == shape{
+        return x_id;
+    }
+    let y = x.broadcast_view(shape).unwrap();
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::BroadcastTo(shape.to_vec()), y, smallvec![x_id]);
+    }
+
+    let x_shape = x.shape.clone(); 
+    let x_shape_t = x.shape.clone(); 
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.sum_over_broadcasted_batches(&x_shape))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, sum_to_shape(tr, g_out, &x_shape_t))]
+    };
+    let out_shape = shape.to_vec();
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].broadcast_view(&out_shape).unwrap()
+    };
+    tr.push(Node { op: OpKind::BroadcastTo(shape.to_vec()), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// échange les deux derniers axes (vue), comme Tensor::mat_transpose
+pub fn mat_transpose(tr: &mut Trace, x_id: NodeId) -> NodeId{
+    let y = tr.get_tensor(x_id).mat_transpose();
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::MatTranspose, y, smallvec![x_id]);
+    }
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.mat_transpose())]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, mat_transpose(tr, g_out))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].mat_transpose()
+    };
+    tr.push(Node { op: OpKind::MatTranspose, value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn unsqueeze(tr: &mut Trace, x_id: NodeId, axis: usize) -> NodeId{
+    let y = tr.get_tensor(x_id).unsqueeze_view(axis);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Unsqueeze(axis), y, smallvec![x_id]);
+    }
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.squeeze_view(axis))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, squeeze(tr, g_out, axis))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].unsqueeze_view(axis)
+    };
+    tr.push(Node { op: OpKind::Unsqueeze(axis), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn squeeze(tr: &mut Trace, x_id: NodeId, axis: usize) -> NodeId{
+    let y = tr.get_tensor(x_id).squeeze_view(axis);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Squeeze(axis), y, smallvec![x_id]);
+    }
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.unsqueeze_view(axis))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, unsqueeze(tr, g_out, axis))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].squeeze_view(axis)
+    };
+    tr.push(Node { op: OpKind::Squeeze(axis), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+// axes[i] = axe de x qui devient l'axe i. le gradient repasse par la permutation inverse
+pub fn permute(tr: &mut Trace, x_id: NodeId, axes: &[usize]) -> NodeId{
+    let y = tr.get_tensor(x_id).permute(axes);
+
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Permute(axes.to_vec()), y, smallvec![x_id]);
+    }
+
+    let mut inv = vec![0; axes.len()];
+    for (i, &a) in axes.iter().enumerate(){
+        inv[a] = i;
+    }
+    let inv_t = inv.clone();
+    let axes_j = axes.to_vec();
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.permute(&inv))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, permute(tr, g_out, &inv_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].permute(&axes_j)
+    };
+    tr.push(Node { op: OpKind::Permute(axes.to_vec()), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
+}
+
+pub fn transpose(tr: &mut Trace, x_id: NodeId, a: usize, b: usize) -> NodeId{
+    let mut axes: Vec<usize> = (0..tr.get_tensor(x_id).shape.len()).collect();
+    axes.swap(a, b);
+    permute(tr, x_id, &axes)
+}
+
+// copie seulement si les strides l'imposent (cf Tensor::reshape). le gradient est remis à la shape de x
+pub fn reshape(tr: &mut Trace, x_id: NodeId, shape: &[usize]) -> NodeId{
+    let y = tr.get_tensor(x_id).reshape(shape);
+    push_reshape(tr, x_id, y)
+}
+
+// comme reshape, mais panique si la vue demande une copie
+pub fn view(tr: &mut Trace, x_id: NodeId, shape: &[usize]) -> NodeId{
+    let y = tr.get_tensor(x_id).view(shape).unwrap();
+    push_reshape(tr, x_id, y)
+}
+
+pub fn flatten(tr: &mut Trace, x_id: NodeId, start: usize, end: usize) -> NodeId{
+    let shape = Tensor::flatten_shape(&tr.get_tensor(x_id).shape, start, end);
+    reshape(tr, x_id, &shape)
+}
+
+// comme torch.expand: c'est un broadcast_to, dont le gradient somme sur les axes étendus
+pub fn expand(tr: &mut Trace, x_id: NodeId, shape: &[usize]) -> NodeId{
+    broadcast_to(tr, x_id, shape)
+}
+
+fn push_reshape(tr: &mut Trace, x_id: NodeId, y: Tensor) -> NodeId{
+    let shape = y.shape.clone();
+    if !tr.grad_enabled(){
+        return tr.push_no_grad(OpKind::Reshape(shape), y, smallvec![x_id]);
+    }
+
+    let x_shape = tr.get_tensor(x_id).shape.clone();
+    let x_shape_t = x_shape.clone();
+    let shape_j = shape.clone();
+
+    let vjp = move |g_out: &Tensor| -> SmallVec<[(NodeId, Tensor); 2]>{
+        smallvec![(x_id, g_out.reshape(&x_shape))]
+    };
+    let vjp_traced = move |tr: &mut Trace, g_out: NodeId| -> SmallVec<[(NodeId, NodeId); 2]>{
+        smallvec![(x_id, reshape(tr, g_out, &x_shape_t))]
+    };
+    let jvp = move |t: &[Tensor]| -> Tensor{
+        t[0].reshape(&shape_j)
+    };
+    tr.push(Node { op: OpKind::Reshape(shape), value: y, parents_id: smallvec![x_id], vjp: Some(Box::new(vjp)), vjp_traced: Some(Box::new(vjp_traced)), jvp: Some(Box::new(jvp)), is_param: false })
 }
@@ -1 +1,123 @@
-pub mod sgd; 
\ No newline at end of file
+pub mod sgd; 
+pub mod adam;
+pub mod rmsprop;
+pub mod adagrad;
+pub mod lr_scheduler;
+pub mod clip;
+pub mod accumulate;
+
+use crate::tensor::Tensor;
+use crate::utils::params::{ParamTree, Pytree};
+
+/*
+interface commune des optimiseurs. params et grads sont dans le meme ordre (celui de l'init, ex: Linear::init_kaiming concaténés),
+et tout l'état interne (moments, vitesses...) est indexé de la meme facon: un tenseur par param.
+*/
+pub trait Optimizer{
+    // met à jour params en place: leurs buffers ne sont copiés que s'ils sont partagés (cf Tensor::data_mut)
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]);
+
+    // version qui renvoie les nouveaux params, ceux passés en argument ne changent pas
+    fn update(&mut self, params: &[Tensor], grads: &[Tensor]) -> Vec<Tensor>{
+        let mut res = params.to_vec();
+        self.update_(&mut res, grads);
+        res
+    }
+
+    // meme chose sur un arbre de params nommés (les grads de value_and_grad ont la meme structure)
+    fn update_tree(&mut self, params: &ParamTree, grads: &ParamTree) -> ParamTree{
+        assert!(params.same_structure(grads), "optim: params et grads n'ont pas la meme structure\n{:?}\n{:?}", params.paths(), grads.paths());
+        params.with_leaves(self.update(&params.leaves(), &grads.leaves()))
+    }
+
+    fn update_tree_(&mut self, params: &mut ParamTree, grads: &ParamTree){
+        assert!(params.same_structure(grads), "optim: params et grads n'ont pas la meme structure\n{:?}\n{:?}", params.paths(), grads.paths());
+        // on sort les feuilles de l'arbre (remplacées par des tenseurs vides): leurs buffers ne sont plus partagés
+        let mut leaves: Vec<Tensor> = params.leaves_mut().into_iter().map(|t| std::mem::replace(t, Tensor::zeros(&[0]))).collect();
+        self.update_(&mut leaves, &grads.leaves());
+        for (slot, t) in params.leaves_mut().into_iter().zip(leaves){
+            *slot = t;
+        }
+    }
+
+    // utilisés par les lr_scheduler
+    fn lr(&self) -> f32;
+    fn set_lr(&mut self, lr: f32);
+
+    // pour checkpoint: le nombre de pas et les buffers par param, nommés ("m", "v", "momentum"...)
+    fn state(&self) -> OptimState;
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>;
+
+    // load_state, en vérifiant d'abord chaque buffer contre le param qu'il suit (un buffer de mauvaise shape fausserait les updates)
+    fn load_state_checked(&mut self, state: OptimState, params: &[Tensor]) -> Result<(), String>{
+        state.check_shapes(params)?;
+        self.load_state(state)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OptimState{
+    pub step: usize, 
+    pub buffers: Vec<(String, Vec<Tensor>)>,
+}
+
+impl OptimState{
+    pub fn buffer(&self, name: &str) -> Result<&Vec<Tensor>, String>{
+        self.buffers.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, b)| b)
+            .ok_or_else(|| format!("optim state: buffer '{name}' manquant"))
+    }
+
+    // un tenseur par param, de la meme shape. un buffer vide (optimiseur jamais mis à jour) est accepté
+    pub fn check_shapes(&self, params: &[Tensor]) -> Result<(), String>{
+        for (name, buf) in &self.buffers{
+            if buf.is_empty(){
+                continue;
+            }
+            if buf.len() != params.len(){
+                return Err(format!("optim state: buffer '{name}' de {} tenseurs pour {} params", buf.len(), params.len()));
+            }
+            if let Some((i, (b, p))) = buf.iter().zip(params).enumerate().find(|(_, (b, p))| b.shape != p.shape){
+                return Err(format!("optim state: buffer '{name}'[{i}] de shape {:?}, le param est {:?}", b.shape, p.shape));
+            }
+        }
+        Ok(())
+    }
+}
+
+/*
+crée les buffers à zéro au premier update (on ne connait les shapes qu'à ce moment la).
+sinon (updates suivants, ou état chargé par load_state) vérifie qu'ils suivent toujours les params.
+*/
+pub(crate) fn zeros_like_if_empty(buf: &mut Vec<Tensor>, params: &[Tensor]){
+    if buf.is_empty(){
+        *buf = params.iter().map(|p| Tensor::zeros(&p.shape)).collect();
+    }
+    assert_eq!(buf.len(), params.len(), "optim: le nombre de params a changé depuis le premier update");
+    for (i, (b, p)) in buf.iter().zip(params).enumerate(){
+        assert_eq!(b.shape, p.shape, "optim: le buffer {i} n'a pas la shape de son param");
+    }
+}
+
+pub(crate) fn check_grad_shapes(name: &str, params: &[Tensor], grads: &[Tensor]){
+    assert_eq!(params.len(), grads.len(), "{name}: autant de gradients que de params attendus");
+    for (p, g) in params.iter().zip(grads){
+        assert_eq!(p.shape, g.shape, "{name}: shape du gradient differente de celle du param");
+    }
+}
+
+// vérifie que des buffers chargés vont ensemble: meme nombre de tenseurs, memes shapes (m et v d'adam par ex.)
+pub(crate) fn check_same_len(state: &OptimState, names: &[&str]) -> Result<(), String>{
+    let bufs: Vec<&Vec<Tensor>> = names.iter().map(|n| state.buffer(n)).collect::<Result<_, _>>()?;
+    let lens: Vec<usize> = bufs.iter().map(|b| b.len()).collect();
+    if lens.windows(2).any(|w| w[0] != w[1]){
+        return Err(format!("optim state: buffers {names:?} de tailles differentes {lens:?}"));
+    }
+    for w in bufs.windows(2){
+        if let Some(i) = (0..w[0].len()).find(|&i| w[0][i].shape != w[1][i].shape){
+            return Err(format!("optim state: buffers {names:?} de shapes differentes au tenseur {i}"));
+        }
+    }
+    Ok(())
+}
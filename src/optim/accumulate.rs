@@ -0,0 +1,60 @@
+use crate::tensor::Tensor; 
+
+/*
+accumule les gradients de plusieurs micro-batches avant un seul pas d'optimiseur:
+
+    let mut acc = GradAccumulator::new(4);
+    for (xb, yb) in &mut train {
+        let (loss, grads) = value_and_grad(&params, ...);
+        if let Some(g) = acc.push(&grads) {
+            params = opt.update(&params, &g);
+        }
+    }
+
+les gradients sont sommés puis divisés par le nombre de micro-batches: comme chaque loss est déjà une moyenne
+sur son micro-batch, on retombe sur le gradient d'un batch `every` fois plus gros.
+*/
+pub struct GradAccumulator{
+    every: usize, 
+    sum: Vec<Tensor>, 
+    count: usize,
+}
+
+impl GradAccumulator{
+    pub fn new(every: usize) -> GradAccumulator{
+        assert!(every > 0, "GradAccumulator: every doit etre > 0");
+        GradAccumulator { every, sum: Vec::new(), count: 0 }
+    }
+
+    pub fn count(&self) -> usize{
+        self.count
+    }
+
+    // ajoute un micro-batch; renvoie le gradient moyen quand `every` micro-batches ont été vus
+    pub fn push(&mut self, grads: &[Tensor]) -> Option<Vec<Tensor>>{
+        if self.sum.is_empty(){
+            self.sum = grads.to_vec();
+        }else{
+            assert_eq!(self.sum.len(), grads.len(), "GradAccumulator: nombre de gradients différent entre deux micro-batches");
+            for (s, g) in self.sum.iter_mut().zip(grads.iter()){
+                s.add_(g);
+            }
+        }
+        self.count += 1; 
+        if self.count == self.every {self.flush()} else {None}
+    }
+
+    // vide l'accumulateur (ex: fin d'epoch avec un reste de micro-batches), None s'il est vide
+    pub fn flush(&mut self) -> Option<Vec<Tensor>>{
+        if self.count == 0{
+            return None;
+        }
+        let n = self.count as f32; 
+        let mut res = std::mem::take(&mut self.sum); 
+        for s in res.iter_mut(){
+            s.apply_(|x| x/n);
+        }
+        self.count = 0; 
+        Some(res)
+    }
+}
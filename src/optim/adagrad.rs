@@ -0,0 +1,57 @@
+use crate::tensor::Tensor; 
+use crate::optim::{Optimizer, OptimState, zeros_like_if_empty, check_grad_shapes};
+
+/*
+adagrad (duchi et al.):
+    s <- s + g^2
+    p <- p - lr*g/(sqrt(s) + eps)
+*/
+pub struct Adagrad{
+    pub lr: f32, 
+    pub eps: f32, 
+    sum_sq: Vec<Tensor>,
+}
+
+impl Adagrad{
+    pub fn new(lr: f32) -> Adagrad{
+        Adagrad { lr, eps: 1e-10, sum_sq: Vec::new() }
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self{
+        self.eps = eps; 
+        self
+    }
+}
+
+impl Optimizer for Adagrad{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        check_grad_shapes("adagrad", params, grads);
+        zeros_like_if_empty(&mut self.sum_sq, params);
+
+        for (i, (param, grad)) in params.iter_mut().zip(grads.iter()).enumerate(){
+            let g = grad.contiguous(); 
+            let s = self.sum_sq[i].data_mut();
+            for ((pk, sk), &gk) in param.data_mut().iter_mut().zip(s.iter_mut()).zip(g.data.iter()){
+                *sk += gk*gk; 
+                *pk -= self.lr*gk/(sk.sqrt() + self.eps);
+            }
+        }
+    }
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        OptimState { step: 0, buffers: vec![("sum_sq".into(), self.sum_sq.clone())] }
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        self.sum_sq = state.buffer("sum_sq")?.clone();
+        Ok(())
+    }
+}
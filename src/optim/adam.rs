@@ -0,0 +1,170 @@
+use crate::tensor::Tensor; 
+use crate::optim::{Optimizer, OptimState, zeros_like_if_empty, check_same_len, check_grad_shapes};
+
+/*
+adam (kingma & ba):
+    m <- b1*m + (1-b1)*g
+    v <- b2*v + (1-b2)*g^2
+    p <- p - lr * m_hat/(sqrt(v_hat) + eps)     avec m_hat = m/(1-b1^t), v_hat = v/(1-b2^t)
+
+Adam: weight_decay est un l2 classique, ajouté au gradient (g <- g + wd*p) donc passe dans m et v.
+AdamW: weight_decay est découplé (loshchilov & hutter): p <- p - lr*wd*p, hors des moments.
+
+m et v sont indexés comme params (un buffer par tenseur), créés au premier update.
+*/
+
+struct AdamMoments{
+    m: Vec<Tensor>, 
+    v: Vec<Tensor>, 
+    t: usize,
+}
+
+impl AdamMoments{
+    fn new() -> Self{
+        AdamMoments { m: Vec::new(), v: Vec::new(), t: 0 }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn step(&mut self, params: &mut [Tensor], grads: &[Tensor], lr: f32, beta1: f32, beta2: f32, eps: f32, l2: f32, decoupled: f32){
+        check_grad_shapes("adam", params, grads);
+        zeros_like_if_empty(&mut self.m, params);
+        zeros_like_if_empty(&mut self.v, params);
+        self.t += 1; 
+        let bc1 = 1f32 - beta1.powi(self.t as i32);
+        let bc2 = 1f32 - beta2.powi(self.t as i32);
+
+        for (i, (param, grad)) in params.iter_mut().zip(grads.iter()).enumerate(){
+            // les gradients peuvent etre des vues (broadcast du mean_all par ex.) => contiguous
+            let g = grad.contiguous(); 
+            let m = self.m[i].data_mut(); 
+            let v = self.v[i].data_mut(); 
+
+            for (((pk, mk), vk), &gk) in param.data_mut().iter_mut().zip(m.iter_mut()).zip(v.iter_mut()).zip(g.data.iter()){
+                let gk = gk + l2 * *pk; 
+                *mk = beta1 * *mk + (1f32-beta1)*gk; 
+                *vk = beta2 * *vk + (1f32-beta2)*gk*gk; 
+                let m_hat = *mk/bc1; 
+                let v_hat = *vk/bc2; 
+                *pk -= lr*(m_hat/(v_hat.sqrt() + eps) + decoupled * *pk);
+            }
+        }
+    }
+
+    fn state(&self) -> OptimState{
+        OptimState { step: self.t, buffers: vec![("m".into(), self.m.clone()), ("v".into(), self.v.clone())] }
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        check_same_len(&state, &["m", "v"])?;
+        self.m = state.buffer("m")?.clone(); 
+        self.v = state.buffer("v")?.clone(); 
+        self.t = state.step; 
+        Ok(())
+    }
+}
+
+pub struct Adam{
+    pub lr: f32, 
+    pub beta1: f32, 
+    pub beta2: f32, 
+    pub eps: f32, 
+    pub weight_decay: f32, 
+    moments: AdamMoments,
+}
+
+impl Adam{
+    pub fn new(lr: f32) -> Adam{
+        Adam { lr, beta1: 0.9, beta2: 0.999, eps: 1e-8, weight_decay: 0f32, moments: AdamMoments::new() }
+    }
+
+    pub fn betas(mut self, beta1: f32, beta2: f32) -> Self{
+        self.beta1 = beta1; 
+        self.beta2 = beta2; 
+        self
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self{
+        self.eps = eps; 
+        self
+    }
+
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self{
+        self.weight_decay = weight_decay; 
+        self
+    }
+}
+
+impl Optimizer for Adam{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        self.moments.step(params, grads, self.lr, self.beta1, self.beta2, self.eps, self.weight_decay, 0f32)
+    }
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        self.moments.state()
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        self.moments.load_state(state)
+    }
+}
+
+pub struct AdamW{
+    pub lr: f32, 
+    pub beta1: f32, 
+    pub beta2: f32, 
+    pub eps: f32, 
+    pub weight_decay: f32, 
+    moments: AdamMoments,
+}
+
+impl AdamW{
+    pub fn new(lr: f32) -> AdamW{
+        AdamW { lr, beta1: 0.9, beta2: 0.999, eps: 1e-8, weight_decay: 1e-2, moments: AdamMoments::new() }
+    }
+
+    pub fn betas(mut self, beta1: f32, beta2: f32) -> Self{
+        self.beta1 = beta1; 
+        self.beta2 = beta2; 
+        self
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self{
+        self.eps = eps; 
+        self
+    }
+
+    pub fn weight_decay(mut self, weight_decay: f32) -> Self{
+        self.weight_decay = weight_decay; 
+        self
+    }
+}
+
+impl Optimizer for AdamW{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        self.moments.step(params, grads, self.lr, self.beta1, self.beta2, self.eps, 0f32, self.weight_decay)
+    }
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        self.moments.state()
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        self.moments.load_state(state)
+    }
+}
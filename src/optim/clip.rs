@@ -0,0 +1,28 @@
+use crate::tensor::Tensor; 
+
+// norme l2 de tous les gradients mis bout à bout
+pub fn global_norm(grads: &[Tensor]) -> f32{
+    grads.iter()
+        .map(|g| g.contiguous().data.iter().map(|&x| x*x).sum::<f32>())
+        .sum::<f32>()
+        .sqrt()
+}
+
+/*
+si la norme globale dépasse max_norm, tous les gradients sont multipliés par max_norm/norme (la direction est gardée).
+renvoie aussi la norme avant clipping, pratique pour la logger.
+*/
+pub fn clip_grad_norm(grads: &[Tensor], max_norm: f32) -> (Vec<Tensor>, f32){
+    let norm = global_norm(grads);
+    if norm <= max_norm || norm == 0f32{
+        return (grads.to_vec(), norm);
+    }
+    let scale = max_norm/norm; 
+    (grads.iter().map(|g| g.apply(|x| x*scale)).collect(), norm)
+}
+
+// chaque composante est ramenée dans [-clip, clip]
+pub fn clip_grad_value(grads: &[Tensor], clip: f32) -> Vec<Tensor>{
+    assert!(clip >= 0f32, "clip_grad_value: clip doit etre >= 0");
+    grads.iter().map(|g| g.apply(|x| x.clamp(-clip, clip))).collect()
+}
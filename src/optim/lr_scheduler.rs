@@ -0,0 +1,383 @@
+use std::f32::consts::PI;
+
+use crate::optim::Optimizer;
+
+/*
+les schedulers modifient le lr de n'importe quel Optimizer. 
+ils lisent le lr de base dans l'optimiseur à la construction, puis step() avance d'un cran et écrit le nouveau lr.
+c'est à l'appelant de choisir si un cran = un batch ou une epoch (appeler step après opt.update, comme pytorch).
+
+    let mut opt = Adam::new(1e-3);
+    let mut sched = CosineAnnealingWarmRestarts::new(&mut opt, 10, 2, 1e-5);
+    for epoch in 0..n { ...; sched.step(&mut opt); }
+*/
+pub trait LrScheduler{
+    fn step(&mut self, opt: &mut dyn Optimizer);
+    fn last_lr(&self) -> f32;
+
+    // position du scheduler, pour les checkpoints. les hyperparamètres viennent de la construction, pas de l'état
+    fn state(&self) -> SchedulerState;
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SchedulerState{
+    pub t: usize, 
+    pub last_lr: f32, 
+    pub values: Vec<(String, f32)>, // état propre à certains schedulers ("best", "num_bad" pour ReduceLrOnPlateau)
+    pub after: Option<Box<SchedulerState>>, // scheduler chainé (LinearWarmup::then)
+}
+
+impl SchedulerState{
+    pub fn value(&self, name: &str) -> Result<f32, String>{
+        self.values.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| *v)
+            .ok_or_else(|| format!("scheduler state: valeur '{name}' manquante"))
+    }
+}
+
+// lr = base * gamma^(t / step_size)
+pub struct StepLr{
+    base_lr: f32, 
+    step_size: usize, 
+    gamma: f32, 
+    t: usize, 
+    last_lr: f32,
+}
+
+impl StepLr{
+    pub fn new(opt: &mut dyn Optimizer, step_size: usize, gamma: f32) -> StepLr{
+        assert!(step_size > 0, "StepLr: step_size doit etre > 0");
+        let base_lr = opt.lr();
+        StepLr { base_lr, step_size, gamma, t: 0, last_lr: base_lr }
+    }
+}
+
+impl LrScheduler for StepLr{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        self.t += 1; 
+        self.last_lr = self.base_lr*self.gamma.powi((self.t/self.step_size) as i32);
+        opt.set_lr(self.last_lr);
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: self.t, last_lr: self.last_lr, ..Default::default() }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        self.t = state.t; 
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
+
+// lr = base * gamma^t
+pub struct ExponentialLr{
+    base_lr: f32, 
+    gamma: f32, 
+    t: usize, 
+    last_lr: f32,
+}
+
+impl ExponentialLr{
+    pub fn new(opt: &mut dyn Optimizer, gamma: f32) -> ExponentialLr{
+        let base_lr = opt.lr();
+        ExponentialLr { base_lr, gamma, t: 0, last_lr: base_lr }
+    }
+}
+
+impl LrScheduler for ExponentialLr{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        self.t += 1; 
+        self.last_lr = self.base_lr*self.gamma.powi(self.t as i32);
+        opt.set_lr(self.last_lr);
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: self.t, last_lr: self.last_lr, ..Default::default() }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        self.t = state.t; 
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
+
+/*
+sgdr (loshchilov & hutter): cosinus de base à eta_min sur t_0 pas, puis redémarrage avec une période multipliée par t_mult
+    lr = eta_min + (base - eta_min) * (1 + cos(pi * t_cur/t_i))/2
+*/
+pub struct CosineAnnealingWarmRestarts{
+    base_lr: f32, 
+    t_0: usize, 
+    t_mult: usize, 
+    eta_min: f32, 
+    t: usize, 
+    last_lr: f32,
+}
+
+impl CosineAnnealingWarmRestarts{
+    pub fn new(opt: &mut dyn Optimizer, t_0: usize, t_mult: usize, eta_min: f32) -> CosineAnnealingWarmRestarts{
+        assert!(t_0 > 0 && t_mult > 0, "CosineAnnealingWarmRestarts: t_0 et t_mult doivent etre > 0");
+        let base_lr = opt.lr();
+        CosineAnnealingWarmRestarts { base_lr, t_0, t_mult, eta_min, t: 0, last_lr: base_lr }
+    }
+
+    // (position dans le cycle courant, longueur du cycle courant)
+    fn cycle(&self) -> (usize, usize){
+        let mut t_cur = self.t; 
+        let mut t_i = self.t_0; 
+        while t_cur >= t_i{
+            t_cur -= t_i; 
+            t_i *= self.t_mult;
+        }
+        (t_cur, t_i)
+    }
+}
+
+impl LrScheduler for CosineAnnealingWarmRestarts{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        self.t += 1; 
+        let (t_cur, t_i) = self.cycle();
+        self.last_lr = self.eta_min + (self.base_lr - self.eta_min)*(1f32 + (PI*t_cur as f32/t_i as f32).cos())/2f32;
+        opt.set_lr(self.last_lr);
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: self.t, last_lr: self.last_lr, ..Default::default() }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        self.t = state.t; 
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
+
+/*
+montée linéaire de start_factor*base à base sur warmup_steps pas, 
+puis on passe la main à `after` s'il y en a un (ex: cosinus), sinon lr constant.
+*/
+pub struct LinearWarmup{
+    base_lr: f32, 
+    warmup_steps: usize, 
+    start_factor: f32, 
+    after: Option<Box<dyn LrScheduler>>, 
+    t: usize, 
+    last_lr: f32,
+}
+
+impl LinearWarmup{
+    pub fn new(opt: &mut dyn Optimizer, warmup_steps: usize, start_factor: f32) -> LinearWarmup{
+        assert!(warmup_steps > 0, "LinearWarmup: warmup_steps doit etre > 0");
+        let base_lr = opt.lr();
+        let last_lr = base_lr*start_factor;
+        opt.set_lr(last_lr);
+        LinearWarmup { base_lr, warmup_steps, start_factor, after: None, t: 0, last_lr }
+    }
+
+    /*
+    build construit le scheduler suivant: l'optimiseur qu'il recoit est remis au lr de base le temps de l'appel,
+    puis le lr du warmup est rétabli.
+        let sched = LinearWarmup::new(&mut opt, 5, 0.1).then(&mut opt, |o| StepLr::new(o, 10, 0.5));
+    */
+    pub fn then<S: LrScheduler + 'static>(mut self, opt: &mut dyn Optimizer, build: impl FnOnce(&mut dyn Optimizer) -> S) -> Self{
+        opt.set_lr(self.base_lr);
+        self.after = Some(Box::new(build(opt)));
+        opt.set_lr(self.last_lr);
+        self
+    }
+}
+
+impl LrScheduler for LinearWarmup{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        self.t += 1; 
+        if self.t < self.warmup_steps{
+            let frac = self.t as f32/self.warmup_steps as f32;
+            self.last_lr = self.base_lr*(self.start_factor + (1f32-self.start_factor)*frac);
+            opt.set_lr(self.last_lr);
+        }else if self.t == self.warmup_steps{
+            self.last_lr = self.base_lr;
+            opt.set_lr(self.last_lr);
+        }else if let Some(after) = self.after.as_mut(){
+            after.step(opt);
+            self.last_lr = after.last_lr();
+        }
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: self.t, last_lr: self.last_lr, values: Vec::new(), after: self.after.as_ref().map(|a| Box::new(a.state())) }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        match (self.after.as_mut(), state.after){
+            (Some(after), Some(s)) => after.load_state(*s)?,
+            (None, None) => {}
+            _ => return Err("LinearWarmup: l'état ne correspond pas (scheduler chainé avec then ou non)".into()),
+        }
+        self.t = state.t; 
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
+
+/*
+one-cycle (smith), version pytorch avec cosinus: 
+    max_lr/div_factor --(pct_start*total pas)--> max_lr --(le reste)--> max_lr/(div_factor*final_div_factor)
+le max_lr est donné explicitement, le lr actuel de l'optimiseur est écrasé.
+*/
+pub struct OneCycleLr{
+    max_lr: f32, 
+    initial_lr: f32, 
+    min_lr: f32, 
+    total_steps: usize, 
+    pct_start: f32, 
+    t: usize, 
+    last_lr: f32,
+}
+
+impl OneCycleLr{
+    pub fn new(opt: &mut dyn Optimizer, max_lr: f32, total_steps: usize) -> OneCycleLr{
+        assert!(total_steps > 1, "OneCycleLr: total_steps doit etre > 1");
+        let initial_lr = max_lr/25f32;
+        opt.set_lr(initial_lr);
+        OneCycleLr { max_lr, initial_lr, min_lr: initial_lr/1e4, total_steps, pct_start: 0.3, t: 0, last_lr: initial_lr }
+    }
+
+    pub fn pct_start(mut self, pct_start: f32) -> Self{
+        assert!(pct_start > 0f32 && pct_start < 1f32, "OneCycleLr: pct_start doit etre dans ]0, 1[");
+        self.pct_start = pct_start; 
+        self
+    }
+
+    pub fn div_factors(mut self, div_factor: f32, final_div_factor: f32) -> Self{
+        self.initial_lr = self.max_lr/div_factor; 
+        self.min_lr = self.initial_lr/final_div_factor; 
+        self.last_lr = self.initial_lr;
+        self
+    }
+
+    fn cos_anneal(start: f32, end: f32, pct: f32) -> f32{
+        end + (start - end)/2f32*(1f32 + (PI*pct).cos())
+    }
+}
+
+impl LrScheduler for OneCycleLr{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        self.t = (self.t + 1).min(self.total_steps - 1); 
+        let up_end = (self.pct_start*self.total_steps as f32 - 1f32).max(1f32);
+        let t = self.t as f32; 
+        self.last_lr = if t <= up_end{
+            OneCycleLr::cos_anneal(self.initial_lr, self.max_lr, t/up_end)
+        }else{
+            let down = (self.total_steps as f32 - 1f32 - up_end).max(1f32);
+            OneCycleLr::cos_anneal(self.max_lr, self.min_lr, (t - up_end)/down)
+        };
+        opt.set_lr(self.last_lr);
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: self.t, last_lr: self.last_lr, ..Default::default() }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        self.t = state.t; 
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
+
+/*
+multiplie le lr par factor quand la métrique (à minimiser, ex: loss de validation) ne s'améliore plus 
+pendant plus de `patience` appels. la métrique est donnée par observe() avant chaque step() (sinon step ne fait rien):
+    plateau.observe(val_loss);
+    plateau.step(&mut opt);
+*/
+pub struct ReduceLrOnPlateau{
+    factor: f32, 
+    patience: usize, 
+    threshold: f32, 
+    min_lr: f32, 
+    best: f32, 
+    num_bad: usize, 
+    metric: Option<f32>, 
+    last_lr: f32,
+}
+
+impl ReduceLrOnPlateau{
+    pub fn new(opt: &mut dyn Optimizer, factor: f32, patience: usize) -> ReduceLrOnPlateau{
+        assert!(factor > 0f32 && factor < 1f32, "ReduceLrOnPlateau: factor doit etre dans ]0, 1[");
+        ReduceLrOnPlateau { factor, patience, threshold: 1e-4, min_lr: 0f32, best: f32::INFINITY, num_bad: 0, metric: None, last_lr: opt.lr() }
+    }
+
+    // amélioration relative minimale pour remettre la patience à zéro
+    pub fn threshold(mut self, threshold: f32) -> Self{
+        self.threshold = threshold; 
+        self
+    }
+
+    pub fn min_lr(mut self, min_lr: f32) -> Self{
+        self.min_lr = min_lr; 
+        self
+    }
+
+    pub fn observe(&mut self, metric: f32){
+        self.metric = Some(metric);
+    }
+}
+
+impl LrScheduler for ReduceLrOnPlateau{
+    fn step(&mut self, opt: &mut dyn Optimizer){
+        // pas de nouvelle métrique depuis le dernier step: rien à comparer, le lr ne bouge pas
+        let Some(metric) = self.metric.take() else { return };
+        if metric < self.best*(1f32 - self.threshold){
+            self.best = metric; 
+            self.num_bad = 0;
+        }else{
+            self.num_bad += 1;
+        }
+        if self.num_bad > self.patience{
+            self.last_lr = (opt.lr()*self.factor).max(self.min_lr);
+            opt.set_lr(self.last_lr);
+            self.num_bad = 0;
+        }
+    }
+
+    fn last_lr(&self) -> f32{
+        self.last_lr
+    }
+
+    // la métrique en attente (observe sans step) ne fait pas partie de l'état
+    fn state(&self) -> SchedulerState{
+        SchedulerState { t: 0, last_lr: self.last_lr, values: vec![("best".into(), self.best), ("num_bad".into(), self.num_bad as f32)], after: None }
+    }
+
+    fn load_state(&mut self, state: SchedulerState) -> Result<(), String>{
+        self.best = state.value("best")?;
+        self.num_bad = state.value("num_bad")? as usize;
+        self.last_lr = state.last_lr;
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use crate::tensor::Tensor; 
+use crate::optim::{Optimizer, OptimState, zeros_like_if_empty, check_grad_shapes};
+
+/*
+rmsprop (hinton):
+    v <- a*v + (1-a)*g^2
+    p <- p - lr*g/(sqrt(v) + eps)
+*/
+pub struct RmsProp{
+    pub lr: f32, 
+    pub alpha: f32, 
+    pub eps: f32, 
+    sq_avg: Vec<Tensor>,
+}
+
+impl RmsProp{
+    pub fn new(lr: f32) -> RmsProp{
+        RmsProp { lr, alpha: 0.99, eps: 1e-8, sq_avg: Vec::new() }
+    }
+
+    pub fn alpha(mut self, alpha: f32) -> Self{
+        self.alpha = alpha; 
+        self
+    }
+
+    pub fn eps(mut self, eps: f32) -> Self{
+        self.eps = eps; 
+        self
+    }
+}
+
+impl Optimizer for RmsProp{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        check_grad_shapes("rmsprop", params, grads);
+        zeros_like_if_empty(&mut self.sq_avg, params);
+
+        for (i, (param, grad)) in params.iter_mut().zip(grads.iter()).enumerate(){
+            let g = grad.contiguous(); 
+            let v = self.sq_avg[i].data_mut();
+            for ((pk, vk), &gk) in param.data_mut().iter_mut().zip(v.iter_mut()).zip(g.data.iter()){
+                *vk = self.alpha * *vk + (1f32-self.alpha)*gk*gk; 
+                *pk -= self.lr*gk/(vk.sqrt() + self.eps);
+            }
+        }
+    }
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        OptimState { step: 0, buffers: vec![("sq_avg".into(), self.sq_avg.clone())] }
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        self.sq_avg = state.buffer("sq_avg")?.clone();
+        Ok(())
+    }
+}
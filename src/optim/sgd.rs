@@ -1,14 +1,89 @@
 use crate::tensor::Tensor; 
+use crate::optim::{Optimizer, OptimState, zeros_like_if_empty, check_grad_shapes};
 
 pub struct Sgd{
     pub lr: f32
 }
 
-impl Sgd{
-    pub fn update(&self, params: &[Tensor], grads: &[Tensor]) -> Vec<Tensor>{
-        params.iter().zip(grads.iter())
-            .map(|(param, grad)|
-                param + &grad.apply(|x| x*(-self.lr))
-        ).collect()
+impl Optimizer for Sgd{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        check_grad_shapes("sgd", params, grads);
+        for (param, grad) in params.iter_mut().zip(grads.iter()){
+            param.axpy_(-self.lr, grad);
+        }
     }
-}
\ No newline at end of file
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        OptimState::default()
+    }
+
+    fn load_state(&mut self, _state: OptimState) -> Result<(), String>{
+        Ok(())
+    }
+}
+
+/*
+sgd avec momentum (convention pytorch):
+    b <- mu*b + g
+    p <- p - lr*b                 (classique)
+    p <- p - lr*(g + mu*b)        (nesterov)
+*/
+pub struct SgdMomentum{
+    pub lr: f32, 
+    pub momentum: f32, 
+    pub nesterov: bool, 
+    velocity: Vec<Tensor>,
+}
+
+impl SgdMomentum{
+    pub fn new(lr: f32, momentum: f32) -> SgdMomentum{
+        SgdMomentum { lr, momentum, nesterov: false, velocity: Vec::new() }
+    }
+
+    pub fn nesterov(mut self, nesterov: bool) -> Self{
+        self.nesterov = nesterov; 
+        self
+    }
+}
+
+impl Optimizer for SgdMomentum{
+    fn update_(&mut self, params: &mut [Tensor], grads: &[Tensor]){
+        check_grad_shapes("sgd", params, grads);
+        zeros_like_if_empty(&mut self.velocity, params);
+
+        for (i, (param, grad)) in params.iter_mut().zip(grads.iter()).enumerate(){
+            let g = grad.contiguous(); 
+            let b = self.velocity[i].data_mut();
+            for ((pk, bk), &gk) in param.data_mut().iter_mut().zip(b.iter_mut()).zip(g.data.iter()){
+                *bk = self.momentum * *bk + gk; 
+                let step = if self.nesterov {gk + self.momentum * *bk} else {*bk};
+                *pk -= self.lr*step;
+            }
+        }
+    }
+
+    fn lr(&self) -> f32{
+        self.lr
+    }
+
+    fn set_lr(&mut self, lr: f32){
+        self.lr = lr;
+    }
+
+    fn state(&self) -> OptimState{
+        OptimState { step: 0, buffers: vec![("velocity".into(), self.velocity.clone())] }
+    }
+
+    fn load_state(&mut self, state: OptimState) -> Result<(), String>{
+        self.velocity = state.buffer("velocity")?.clone();
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+#[allow(clippy::module_inception)]
 mod tensor; 
+pub(crate) mod strided;
+mod reduce;
 pub use tensor::Tensor;
 pub use tensor::Numel;
\ No newline at end of file
@@ -0,0 +1,114 @@
+use crate::tensor::Tensor;
+use super::strided;
+
+/*
+réductions sur un ensemble d'axes. axes vide = tous les axes.
+keepdim: les axes réduits restent, avec une taille 1 (la sortie se broadcaste contre l'entrée); sinon ils disparaissent.
+la sortie est toujours contiguë.
+*/
+impl Tensor{
+    // axes triés; [] => tous les axes
+    pub(crate) fn reduce_axes(&self, axes: &[usize]) -> Vec<usize>{
+        let r = self.shape.len();
+        if axes.is_empty(){
+            return (0..r).collect();
+        }
+        let mut v = axes.to_vec();
+        v.sort_unstable();
+        assert!(v.windows(2).all(|w| w[0] != w[1]), "reduce: axe en double dans {axes:?}");
+        assert!(v[v.len()-1] < r, "reduce: axes {axes:?} hors d'un tenseur de rang {r}");
+        v
+    }
+
+    // axes déjà passés par reduce_axes
+    pub(crate) fn reduced_shape(&self, axes: &[usize], keepdim: bool) -> Vec<usize>{
+        self.shape.iter().enumerate()
+            .filter_map(|(i, &d)| match (axes.contains(&i), keepdim){
+                (false, _) => Some(d),
+                (true, true) => Some(1),
+                (true, false) => None,
+            })
+            .collect()
+    }
+
+    // nombre d'éléments de chaque bloc réduit
+    pub(crate) fn reduced_numel(&self, axes: &[usize]) -> usize{
+        self.reduce_axes(axes).iter().map(|&i| self.shape[i]).product()
+    }
+
+    pub fn reduce_with(&self, axes: &[usize], keepdim: bool, init: f32, f: impl Fn(f32, f32) -> f32 + Sync) -> Tensor{
+        let axes = self.reduce_axes(axes);
+        let data = strided::reduce(self, &axes, init, |acc, x, _| f(acc, x));
+        Tensor::from_owned(data, &self.reduced_shape(&axes, keepdim)).unwrap()
+    }
+
+    // t calculé avec keepdim = true => shape demandée (meme buffer)
+    fn drop_axes(&self, t: Tensor, axes: &[usize], keepdim: bool) -> Tensor{
+        if keepdim{
+            return t;
+        }
+        Tensor::new(t.data, &self.reduced_shape(&self.reduce_axes(axes), false), 0)
+    }
+
+    pub fn sum(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.reduce_with(axes, keepdim, 0f32, |acc, x| acc + x)
+    }
+
+    pub fn mean(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        let n = self.reduced_numel(axes) as f32;
+        self.sum(axes, keepdim).apply(|x| x/n)
+    }
+
+    pub fn max(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.reduce_with(axes, keepdim, f32::NEG_INFINITY, f32::max)
+    }
+
+    pub fn min(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.reduce_with(axes, keepdim, f32::INFINITY, f32::min)
+    }
+
+    pub fn prod(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.reduce_with(axes, keepdim, 1f32, |acc, x| acc * x)
+    }
+
+    // variance biaisée (divisée par n, pas n-1): celle des batchnorm / layernorm
+    pub fn var(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        let n = self.reduced_numel(axes) as f32;
+        let m = self.mean(axes, true);
+        strided::zip_map(self, &m, |x, m| (x-m)*(x-m)).sum(axes, keepdim).apply(|x| x/n)
+    }
+
+    pub fn std(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.var(axes, keepdim).apply(f32::sqrt)
+    }
+
+    // log(sum(exp(x))) sans overflow: on retire le max de chaque bloc avant l'exp
+    pub fn logsumexp(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        let m = self.max(axes, true).apply(|x| if x.is_finite() {x} else {0f32});
+        let s = strided::zip_map(self, &m, |x, m| (x-m).exp()).sum(axes, true);
+        self.drop_axes(strided::zip_map(&m, &s, |m, s| m + s.ln()), axes, keepdim)
+    }
+
+    // norme l2
+    pub fn norm(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.reduce_with(axes, keepdim, 0f32, |acc, x| acc + x*x).apply(f32::sqrt)
+    }
+
+    /*
+    indice (en f32) du max dans chaque bloc réduit, le premier en cas d'égalité.
+    sur plusieurs axes, c'est l'indice linéaire dans le bloc (ordre row major des axes réduits).
+    */
+    pub fn argmax(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.arg_reduce(axes, keepdim, f32::NEG_INFINITY, |x, best| x > best)
+    }
+
+    pub fn argmin(&self, axes: &[usize], keepdim: bool) -> Tensor{
+        self.arg_reduce(axes, keepdim, f32::INFINITY, |x, best| x < best)
+    }
+
+    fn arg_reduce(&self, axes: &[usize], keepdim: bool, init: f32, better: impl Fn(f32, f32) -> bool + Sync) -> Tensor{
+        let axes = self.reduce_axes(axes);
+        let best = strided::reduce(self, &axes, (init, 0usize), |acc, x, i| if better(x, acc.0) {(x, i)} else {acc});
+        Tensor::from_owned(best.iter().map(|&(_, i)| i as f32).collect(), &self.reduced_shape(&axes, keepdim)).unwrap()
+    }
+}
@@ -0,0 +1,363 @@
+use std::sync::Arc;
+
+use crate::tensor::{Tensor, Numel};
+use crate::utils::parallel;
+
+/*
+moteur des ops élément par élément (apply, +, -, *, /, contiguous, et leurs versions en place apply_, add_...).
+    - opérandes contigus de meme shape: boucle serrée sur les slices, vectorisable.
+    - sinon (broadcast, transposée, sous vue): on parcourt la sortie ligne par ligne, les offsets de chaque
+      opérande avancent de leur stride sur le dernier axe, et on ne refait l'arithmétique d'indices
+      qu'au changement de ligne. les axes consécutifs contigus pour tous sont fusionnés avant (lignes plus longues).
+au dela de PAR_MIN éléments, la sortie est découpée en morceaux de PAR_CHUNK répartis sur les threads.
+*/
+
+const PAR_MIN: usize = 1 << 17;
+const PAR_CHUNK: usize = 1 << 15;
+
+// remplit out morceau par morceau: fill(indice linéaire du début du morceau, morceau)
+fn fill_chunks(out: &mut [f32], fill: impl Fn(usize, &mut [f32]) + Sync){
+    if out.len() >= PAR_MIN && parallel::num_threads() > 1{
+        parallel::for_each_chunk_mut(out, PAR_CHUNK, |i, chunk| fill(i*PAR_CHUNK, chunk));
+    }else{
+        fill(0, out);
+    }
+}
+
+fn fill_out(numel: usize, fill: impl Fn(usize, &mut [f32]) + Sync) -> Vec<f32>{
+    let mut out = vec![0f32; numel];
+    fill_chunks(&mut out, fill);
+    out
+}
+
+/*
+fusionne les axes consécutifs que toutes les vues parcourent de facon contiguë: (4, 5) contigu => (20).
+les axes de taille 1 disparaissent (leur stride ne sert jamais). renvoie au moins un axe.
+*/
+fn coalesce<const N: usize>(shape: &[usize], strides: [&[usize]; N]) -> (Vec<usize>, [Vec<usize>; N]){
+    let mut s: Vec<usize> = Vec::with_capacity(shape.len());
+    let mut st: [Vec<usize>; N] = std::array::from_fn(|_| Vec::with_capacity(shape.len()));
+    for (i, &d) in shape.iter().enumerate(){
+        if d == 1{
+            continue;
+        }
+        let merge = !s.is_empty() && (0..N).all(|k| *st[k].last().unwrap() == strides[k][i]*d);
+        if merge{
+            *s.last_mut().unwrap() *= d;
+            for k in 0..N{
+                *st[k].last_mut().unwrap() = strides[k][i];
+            }
+        }else{
+            s.push(d);
+            for k in 0..N{
+                st[k].push(strides[k][i]);
+            }
+        }
+    }
+    if s.is_empty(){
+        s.push(1);
+        for v in st.iter_mut(){
+            v.push(0);
+        }
+    }
+    (s, st)
+}
+
+/*
+parcourt les éléments start..start+len (ordre logique de shape) par morceaux de ligne:
+row(position dans le morceau, nombre d'éléments, offset de chaque vue au début, stride de chaque vue sur la ligne)
+*/
+fn walk<const N: usize>(shape: &[usize], strides: &[Vec<usize>; N], base: [usize; N], start: usize, len: usize, mut row: impl FnMut(usize, usize, [usize; N], [usize; N])){
+    let r = shape.len();
+    let mut idx = Tensor::idx_from_lin(shape, start);
+    let mut off: [usize; N] = std::array::from_fn(|k| base[k] + idx.iter().zip(&strides[k]).map(|(i, s)| i*s).sum::<usize>());
+    let inner: [usize; N] = std::array::from_fn(|k| strides[k][r-1]);
+
+    let mut done = 0;
+    while done < len{
+        let cnt = (shape[r-1] - idx[r-1]).min(len - done);
+        row(done, cnt, off, inner);
+        done += cnt;
+
+        idx[r-1] += cnt;
+        for k in 0..N{
+            off[k] += cnt*inner[k];
+        }
+        // retenue: fin d'un axe => retour à 0 et +1 sur l'axe précédent
+        let mut ax = r-1;
+        while ax > 0 && idx[ax] == shape[ax]{
+            for k in 0..N{
+                off[k] = off[k] - shape[ax]*strides[k][ax] + strides[k][ax-1];
+            }
+            idx[ax] = 0;
+            idx[ax-1] += 1;
+            ax -= 1;
+        }
+    }
+}
+
+fn map_slice(x: &[f32], f: &(impl Fn(f32) -> f32 + Sync)) -> Vec<f32>{
+    fill_out(x.len(), |start, out| {
+        let end = start + out.len();
+        for (o, &v) in out.iter_mut().zip(&x[start..end]){
+            *o = f(v);
+        }
+    })
+}
+
+/*
+f sur chaque élément. si le buffer n'est pas plus grand que la vue (contigu, broadcast, transposée...),
+on applique f à chaque valeur stockée en gardant la disposition; sinon (sous vue d'un buffer plus grand)
+on ne parcourt que les éléments de la vue, et la sortie est contiguë.
+*/
+pub(crate) fn map(a: &Tensor, f: impl Fn(f32) -> f32 + Sync) -> Tensor{
+    let n = a.shape.numel();
+    if a.data.len() <= n{
+        return Tensor { data: Arc::new(map_slice(&a.data, &f)), shape: a.shape.clone(), strides: a.strides.clone(), offset: a.offset };
+    }
+    let (shape, st) = coalesce(&a.shape, [&a.strides]);
+    let out = fill_out(n, |start, out| {
+        walk(&shape, &st, [a.offset], start, out.len(), |k, cnt, [oa], [sa]| {
+            for (j, o) in out[k..k+cnt].iter_mut().enumerate(){
+                *o = f(a.data[oa + j*sa]);
+            }
+        });
+    });
+    Tensor::from_owned(out, &a.shape).unwrap()
+}
+
+// map en place: meme cas que map, le buffer n'est copié que s'il est partagé (Arc::make_mut)
+pub(crate) fn map_(a: &mut Tensor, f: impl Fn(f32) -> f32 + Sync){
+    if a.data.len() > a.shape.numel(){
+        *a = map(a, f);
+        return;
+    }
+    fill_chunks(Arc::make_mut(&mut a.data).as_mut_slice(), |_, chunk| {
+        for x in chunk.iter_mut(){
+            *x = f(*x);
+        }
+    });
+}
+
+// copie dans l'ordre logique (row major)
+pub(crate) fn gather(a: &Tensor) -> Tensor{
+    let n = a.shape.numel();
+    let (shape, st) = coalesce(&a.shape, [&a.strides]);
+    let out = fill_out(n, |start, out| {
+        walk(&shape, &st, [a.offset], start, out.len(), |k, cnt, [oa], [sa]| {
+            if sa == 1{
+                out[k..k+cnt].copy_from_slice(&a.data[oa..oa+cnt]);
+            }else{
+                for (j, o) in out[k..k+cnt].iter_mut().enumerate(){
+                    *o = a.data[oa + j*sa];
+                }
+            }
+        });
+    });
+    Tensor::from_owned(out, &a.shape).unwrap()
+}
+
+// f(a, b) avec broadcast numpy, sortie contiguë
+pub(crate) fn zip_map(a: &Tensor, b: &Tensor, f: impl Fn(f32, f32) -> f32 + Sync) -> Tensor{
+    let out_shape = Tensor::broadcast_shape(&a.shape, &b.shape).unwrap();
+    let n = out_shape.numel();
+
+    if a.shape == b.shape && a.is_contiguous() && b.is_contiguous(){
+        let out = fill_out(n, |start, out| {
+            let end = start + out.len();
+            for ((o, &x), &y) in out.iter_mut().zip(&a.data[start..end]).zip(&b.data[start..end]){
+                *o = f(x, y);
+            }
+        });
+        return Tensor::from_owned(out, &out_shape).unwrap();
+    }
+
+    let a_b = a.broadcast_view(&out_shape).unwrap();
+    let b_b = b.broadcast_view(&out_shape).unwrap();
+    let (shape, st) = coalesce(&out_shape, [&a_b.strides, &b_b.strides]);
+    let (da, db) = (&a_b.data, &b_b.data);
+    let out = fill_out(n, |start, out| {
+        walk(&shape, &st, [a_b.offset, b_b.offset], start, out.len(), |k, cnt, [oa, ob], [sa, sb]| {
+            let out = &mut out[k..k+cnt];
+            match (sa, sb){
+                (1, 1) => for ((o, &x), &y) in out.iter_mut().zip(&da[oa..oa+cnt]).zip(&db[ob..ob+cnt]){
+                    *o = f(x, y);
+                },
+                // un des deux est constant le long de la ligne (broadcast d'un biais, d'un scalaire...)
+                (1, 0) => {
+                    let y = db[ob];
+                    for (o, &x) in out.iter_mut().zip(&da[oa..oa+cnt]){
+                        *o = f(x, y);
+                    }
+                },
+                (0, 1) => {
+                    let x = da[oa];
+                    for (o, &y) in out.iter_mut().zip(&db[ob..ob+cnt]){
+                        *o = f(x, y);
+                    }
+                },
+                _ => for (j, o) in out.iter_mut().enumerate(){
+                    *o = f(da[oa + j*sa], db[ob + j*sb]);
+                },
+            }
+        });
+    });
+    Tensor::from_owned(out, &out_shape).unwrap()
+}
+
+// a <- f(a, b) en place, b broadcasté vers la shape de a (qui ne change pas)
+pub(crate) fn zip_map_(a: &mut Tensor, b: &Tensor, f: impl Fn(f32, f32) -> f32 + Sync){
+    let b_b = b.broadcast_view(&a.shape)
+        .unwrap_or_else(|_| panic!("op en place: {:?} ne se broadcast pas vers {:?}", b.shape, a.shape));
+    let (shape, st) = coalesce(&a.shape, [&b_b.strides]);
+    let db = &b_b.data;
+    fill_chunks(a.data_mut(), |start, out| {
+        walk(&shape, &st, [b_b.offset], start, out.len(), |k, cnt, [ob], [sb]| {
+            let out = &mut out[k..k+cnt];
+            match sb{
+                1 => for (o, &y) in out.iter_mut().zip(&db[ob..ob+cnt]){
+                    *o = f(*o, y);
+                },
+                0 => {
+                    let y = db[ob];
+                    for o in out.iter_mut(){
+                        *o = f(*o, y);
+                    }
+                },
+                _ => for (j, o) in out.iter_mut().enumerate(){
+                    *o = f(*o, db[ob + j*sb]);
+                },
+            }
+        });
+    });
+}
+
+/*
+réduction sur axes (triés, sans doublon): un accumulateur par élément de sortie (ordre logique des axes gardés),
+acc = f(acc, x, position de x dans son bloc réduit) en partant de init.
+on parcourt la vue permutée (axes gardés, puis axes réduits): les éléments d'un meme bloc se suivent.
+*/
+pub(crate) fn reduce<A: Copy + Send>(a: &Tensor, axes: &[usize], init: A, f: impl Fn(A, f32, usize) -> A + Sync) -> Vec<A>{
+    let order: Vec<usize> = (0..a.shape.len()).filter(|i| !axes.contains(i)).chain(axes.iter().copied()).collect();
+    let shape: Vec<usize> = order.iter().map(|&i| a.shape[i]).collect();
+    let strides: Vec<usize> = order.iter().map(|&i| a.strides[i]).collect();
+    let r: usize = axes.iter().map(|&i| a.shape[i]).product();
+    let n_out: usize = shape[..shape.len()-axes.len()].iter().product();
+
+    let mut out = vec![init; n_out];
+    if r == 0 || n_out == 0{
+        return out;
+    }
+    let (shape, st) = coalesce(&shape, [&strides]);
+    let run = |first: usize, out: &mut [A]| {
+        walk(&shape, &st, [a.offset], first*r, out.len()*r, |k, cnt, [oa], [sa]| {
+            let mut j = 0;
+            while j < cnt{
+                let (o, pos) = ((k+j) / r, (k+j) % r);
+                let take = (r - pos).min(cnt - j);
+                let mut acc = out[o];
+                for t in 0..take{
+                    acc = f(acc, a.data[oa + (j+t)*sa], pos + t);
+                }
+                out[o] = acc;
+                j += take;
+            }
+        });
+    };
+    if a.shape.numel() >= PAR_MIN && parallel::num_threads() > 1{
+        let per_chunk = (PAR_CHUNK / r).max(1);
+        parallel::for_each_chunk_mut(&mut out, per_chunk, |i, chunk| run(i*per_chunk, chunk));
+    }else{
+        run(0, &mut out);
+    }
+    out
+}
+
+// coalesce + walk doivent visiter les memes offsets que la définition (get_from_lin), quel que soit le découpage
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn arange(n: usize) -> Arc<Vec<f32>>{
+        Arc::new((0..n).map(|i| i as f32).collect())
+    }
+
+    // transposée, broadcast (stride 0), sous vue décalée d'un buffer plus grand, et leurs combinaisons
+    fn views() -> Vec<Tensor>{
+        let t = Tensor::new(arange(60), &[3, 4, 5], 0);
+        let big = arange(200);
+        vec![
+            t.clone(),
+            t.permute(&[2, 0, 1]),
+            t.mat_transpose(),
+            Tensor::new(arange(5), &[1, 5], 0).broadcast_view(&[3, 4, 5]).unwrap(),
+            Tensor::new(arange(4), &[4, 1], 0).broadcast_view(&[3, 4, 5]).unwrap(),
+            // lignes de 5 prises dans des lignes de 8, à partir de l'offset 17
+            Tensor { data: big.clone(), shape: vec![3, 4, 5], strides: vec![40, 8, 1], offset: 17 },
+            Tensor { data: big.clone(), shape: vec![4, 3, 5], strides: vec![8, 40, 1], offset: 3 }.permute(&[2, 1, 0]),
+            Tensor { data: big, shape: vec![3, 1, 5], strides: vec![40, 0, 2], offset: 9 }.broadcast_view(&[2, 3, 4, 5]).unwrap(),
+            Tensor::new(arange(1), &[], 0).broadcast_view(&[3, 1, 4]).unwrap(),
+        ]
+    }
+
+    // les éléments start..start+len vus par walk, dans l'ordre
+    fn walked(v: &Tensor, start: usize, len: usize) -> Vec<f32>{
+        let (shape, st) = coalesce(&v.shape, [&v.strides]);
+        assert_eq!(shape.iter().product::<usize>(), v.shape.numel());
+        let mut out = vec![f32::NAN; len];
+        walk(&shape, &st, [v.offset], start, len, |k, cnt, [o], [s]| {
+            for j in 0..cnt{
+                out[k+j] = v.data[o + j*s];
+            }
+        });
+        out
+    }
+
+    #[test]
+    fn walk_matches_get_from_lin(){
+        for v in views(){
+            let n = v.shape.numel();
+            let expected: Vec<f32> = (0..n).map(|i| v.get_from_lin(i)).collect();
+            assert_eq!(walked(&v, 0, n), expected, "{:?} {:?} +{}", v.shape, v.strides, v.offset);
+            // morceaux qui commencent et finissent au milieu des lignes
+            for chunk in [1, 3, 7, 13]{
+                for start in (0..n).step_by(chunk){
+                    let len = chunk.min(n - start);
+                    assert_eq!(walked(&v, start, len), expected[start..start+len], "{:?} {:?} {start}+{len}", v.shape, v.strides);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn coalesce_merges_only_common_contiguous_axes(){
+        let (s, [a, b]) = coalesce(&[3, 4, 5], [&[20, 5, 1], &[20, 5, 1]]);
+        assert_eq!((s, a, b), (vec![60], vec![1], vec![1]));
+        // b broadcasté sur le premier axe: seuls les deux derniers fusionnent
+        let (s, [a, b]) = coalesce(&[3, 4, 5], [&[20, 5, 1], &[0, 5, 1]]);
+        assert_eq!((s, a, b), (vec![3, 20], vec![20, 1], vec![0, 1]));
+        // axes de taille 1 ignorés, et au moins un axe
+        let (s, [a]) = coalesce(&[1, 4, 1, 5], [&[99, 5, 7, 1]]);
+        assert_eq!((s, a), (vec![20], vec![1]));
+        let (s, [a]) = coalesce(&[1, 1], [&[3, 1]]);
+        assert_eq!((s, a), (vec![1], vec![0]));
+    }
+
+    #[test]
+    fn two_views_walk_together(){
+        let vs = views();
+        // (4, 1) broadcasté et sous vue décalée, de meme shape
+        let (a, b) = (&vs[4], &vs[5]);
+        let (shape, st) = coalesce(&a.shape, [&a.strides, &b.strides]);
+        let n = a.shape.numel();
+        let mut got = vec![(0.0, 0.0); n];
+        walk(&shape, &st, [a.offset, b.offset], 7, n - 7, |k, cnt, [oa, ob], [sa, sb]| {
+            for j in 0..cnt{
+                got[7+k+j] = (a.data[oa + j*sa], b.data[ob + j*sb]);
+            }
+        });
+        for (i, &(x, y)) in got.iter().enumerate().skip(7){
+            assert_eq!((x, y), (a.get_from_lin(i), b.get_from_lin(i)), "{i}");
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::sync::Arc;
 use std::iter; 
 use rand::Rng; 
 
+use super::strided;
+
 #[derive(Debug, Clone)]
 pub struct Tensor {
     pub data: Arc<Vec<f32>>,
@@ -66,13 +68,14 @@ impl Tensor{
             Tensor { data: Arc::new(data.to_vec()), shape: shape.to_vec(), strides: Tensor::compute_strides(shape), offset:0
         })
     }
-    /*
+    // fusionne les `last` derniers axes en un seul: (a, b, c, d).flatten_last_nb(2) => (a, b, c*d)
     pub fn flatten_last_nb(&self, last: usize) -> Tensor{
-        unimplemented!()
+        let r = self.shape.len();
+        assert!(last >= 1 && last <= r, "flatten_last_nb: {last} axes demandés sur un tenseur de rang {r}");
+        self.flatten(r-last, r-1)
     }
-        */
     pub fn flatten_all(&self) -> Tensor{
-        Tensor { data: self.data.clone(), shape:vec![self.shape.numel()],  strides: vec![1], offset: self.offset }
+        self.reshape(&[self.shape.numel()])
     }
     pub fn from_owned(data: Vec<f32>, shape: &[usize]) -> Result<Self, String> {
         if data.len() != shape.iter().product(){
@@ -86,9 +89,21 @@ impl Tensor{
         })
     }
 
+    #[inline(always)]
+    pub fn is_contiguous(&self) -> bool {
+        self.offset == 0 && self.data.len() == self.shape.numel() && self.strides == Tensor::compute_strides(&self.shape)
+    }
+
+    // copie les données dans l'ordre logique (row major) si la vue ne l'est pas déjà
+    pub fn contiguous(&self) -> Tensor{
+        if self.is_contiguous(){
+            return self.clone();
+        }
+        strided::gather(self)
+    }
+
     pub fn sum_all(&self) -> Tensor{
-        let new_data: f32 = self.data.iter().sum();
-        Tensor { data: Arc::new(vec![new_data]), shape: Vec::new(), strides: Vec::new(), offset: 0 }
+        self.sum(&[], false)
     }
 
 
@@ -96,11 +111,10 @@ impl Tensor{
 
     #[inline(always)]
     pub fn is_broadcasted(&self) -> bool {
-        self.strides.iter().any(|&s| s == 0)
+        self.strides.contains(&0)
     }
 
     //TODO: check cette implémentation de squeeze;
-    //TODO: check l'implem matmul vjp
     pub fn unsqueeze_view(&self, axis: usize) -> Tensor {
         let r = self.shape.len();
         assert!(axis <= r, "unsqueeze axis hors limites");
@@ -160,12 +174,11 @@ impl Tensor{
         let mut res: Tensor = self.unsqueeze_first(a.len()-self.shape.len()); // assumes that la shape quon veut a une taille >= celle quon aura
         assert_eq!(res.shape.len(), a.len(), "enorme bug broadcast_view");
         //recalculer les strides
-        let n  = a.len();
-        for i in 0..n{
-            if res.shape[i] < a[i] && res.shape[i] ==1 {
-                res.shape[i] =  a[i];
+        for (i, &ai) in a.iter().enumerate(){
+            if res.shape[i] < ai && res.shape[i] ==1 {
+                res.shape[i] =  ai;
                 res.strides[i] = 0;
-            }else if res.shape[i] != a[i] && res.shape[i] != 1 { // pas la meme shape mais pas broadcastable...
+            }else if res.shape[i] != ai && res.shape[i] != 1 { // pas la meme shape mais pas broadcastable...
                 return Err("broadcast_view : non broadcastable..".into());
             }
         }
@@ -198,7 +211,7 @@ impl Tensor{
     }
     pub fn vue2d(&self, offset: usize) -> Tensor{
         let dim = self.shape.len();
-        Tensor { data: self.data.clone(), shape: self.shape[(dim-2)..dim].to_vec(), strides: self.strides[(dim-2)..dim].to_vec(), offset: offset }
+        Tensor { data: self.data.clone(), shape: self.shape[(dim-2)..dim].to_vec(), strides: self.strides[(dim-2)..dim].to_vec(), offset }
     }
     pub fn mat_transpose(&self) -> Tensor{
         let dim = self.shape.len();
@@ -217,6 +230,94 @@ impl Tensor{
 
 
 
+    // axes[i] = axe de self qui devient l'axe i (vue, pas de copie)
+    pub fn permute(&self, axes: &[usize]) -> Tensor{
+        let r = self.shape.len();
+        let mut seen = vec![false; r];
+        assert!(axes.len() == r && axes.iter().all(|&a| a < r && !std::mem::replace(&mut seen[a], true)),
+            "permute: {axes:?} n'est pas une permutation des {r} axes");
+        Tensor {
+            data: self.data.clone(),
+            shape: axes.iter().map(|&a| self.shape[a]).collect(),
+            strides: axes.iter().map(|&a| self.strides[a]).collect(),
+            offset: self.offset,
+        }
+    }
+
+    // échange deux axes quelconques (vue)
+    pub fn transpose(&self, a: usize, b: usize) -> Tensor{
+        let mut axes: Vec<usize> = (0..self.shape.len()).collect();
+        axes.swap(a, b);
+        self.permute(&axes)
+    }
+
+    /*
+    memes éléments, meme ordre logique, autre shape, sans copie. possible tant que chaque groupe d'axes
+    fusionnés/découpés est contigu dans le buffer (c'est le calcul de strides de pytorch);
+    sinon (transposée, broadcast...) Err, et il faut passer par reshape.
+    */
+    pub fn view(&self, shape: &[usize]) -> Result<Tensor, String>{
+        if shape.numel() != self.shape.numel(){
+            return Err(format!("view: {:?} n'a pas le meme nombre d'éléments que {:?}", shape, self.shape));
+        }
+        let Some(strides) = Tensor::view_strides(&self.shape, &self.strides, shape) else {
+            return Err(format!("view: {:?} (strides {:?}) ne peut pas etre vu en {:?} sans copie", self.shape, self.strides, shape));
+        };
+        Ok(Tensor { data: self.data.clone(), shape: shape.to_vec(), strides, offset: self.offset })
+    }
+
+    fn view_strides(old_shape: &[usize], old_strides: &[usize], shape: &[usize]) -> Option<Vec<usize>>{
+        if old_shape.is_empty() || old_shape.numel() == 0{
+            return Some(Tensor::compute_strides(shape));
+        }
+        let mut strides = vec![0; shape.len()];
+        let mut view_d = shape.len();
+        // on remonte les axes de self par groupes contigus; chaque groupe doit correspondre à un groupe d'axes de shape
+        let mut chunk_stride = old_strides[old_shape.len()-1];
+        let (mut old_numel, mut new_numel) = (1, 1);
+        for d in (0..old_shape.len()).rev(){
+            old_numel *= old_shape[d];
+            let chunk_ends = d == 0 || (old_shape[d-1] != 1 && old_strides[d-1] != old_numel*chunk_stride);
+            if !chunk_ends{
+                continue;
+            }
+            while view_d > 0 && (new_numel < old_numel || shape[view_d-1] == 1){
+                strides[view_d-1] = new_numel*chunk_stride;
+                new_numel *= shape[view_d-1];
+                view_d -= 1;
+            }
+            if new_numel != old_numel{
+                return None;
+            }
+            if d > 0{
+                chunk_stride = old_strides[d-1];
+                old_numel = 1;
+                new_numel = 1;
+            }
+        }
+        if view_d != 0 {None} else {Some(strides)}
+    }
+
+    // comme view, mais copie (contiguous) quand les strides ne le permettent pas
+    pub fn reshape(&self, shape: &[usize]) -> Tensor{
+        self.view(shape).unwrap_or_else(|_| self.contiguous().view(shape).unwrap())
+    }
+
+    // fusionne les axes start..=end en un seul
+    pub fn flatten(&self, start: usize, end: usize) -> Tensor{
+        self.reshape(&Tensor::flatten_shape(&self.shape, start, end))
+    }
+
+    pub fn flatten_shape(shape: &[usize], start: usize, end: usize) -> Vec<usize>{
+        assert!(start <= end && end < shape.len(), "flatten: axes {start}..={end} hors d'un tenseur de rang {}", shape.len());
+        [&shape[..start], &[shape[start..=end].numel()], &shape[end+1..]].concat()
+    }
+
+    // vue broadcastée (stride 0 sur les axes étendus), comme torch.expand
+    pub fn expand(&self, shape: &[usize]) -> Tensor{
+        self.broadcast_view(shape).unwrap_or_else(|e| panic!("expand: {:?} => {:?}: {e}", self.shape, shape))
+    }
+
     // conversion index, prise d'éléments, ... /!\ remove set2, inutile je pense..
     #[inline(always)] // pour la rapitidité
     pub fn get2(&self, i: usize, j: usize) -> f32 {
@@ -325,7 +426,7 @@ impl Tensor{
             let old_idx = Tensor::idx_from_lin(&self.shape, lin);
             let mut new_idx = Vec::new();
             for i in 0..n{
-                if to_sum[i] == true{
+                if to_sum[i]{
                     new_idx.push(0);
                 }else{
                     new_idx.push(old_idx[i]);
@@ -347,36 +448,52 @@ impl Tensor{
 
     }
 
-    /*
     pub fn expand_to_shape(&self, shape: &[usize]) -> Tensor{
-        unimplemented!()
+        self.expand(shape)
     }
-    */
     // fonction generique
     pub fn apply<F>(&self, f : F)-> Tensor
-    where F : Fn(f32) -> f32
+    where F : Fn(f32) -> f32 + Sync
     {
-        
-        let new_values = Arc::new(self.data.iter().map(|&x| f(x)).collect());
-        Tensor { data: new_values, shape: self.shape.clone(), strides: self.strides.clone(), offset: self.offset }
-        
+        strided::map(self, f)
     }
-    // suppose que keepdim = true;
-    pub fn apply_and_reduce_last(&self, f: fn(f32, f32) -> f32, neutral_el: f32) -> Tensor{
-        assert!(self.shape.len() >= 1);
-        
-        let n = self.shape.len();
-        let batches_shape= &self.shape[0..(n-1)];
-        let batches_number = batches_shape.numel();
-        let mut batches = vec![neutral_el; batches_number];
-
-        assert!(self.shape[n-1] > 0);
-        for lin in 0..self.shape.numel(){
-            let x = self.get_from_lin(lin);
-            batches[lin/self.shape[n-1]] = f(batches[lin/self.shape[n-1]], x);
+
+    /*
+    versions en place (suffixe _, comme pytorch). le buffer est modifié directement s'il n'est partagé
+    avec aucun autre tenseur, sinon il est d'abord copié (copy on write): les autres tenseurs ne voient
+    jamais le changement. l'autre opérande est broadcasté vers la shape de self, qui ne change pas.
+    */
+
+    // buffer en écriture dans l'ordre logique: rend la vue contiguë, et copie le buffer s'il est partagé
+    pub fn data_mut(&mut self) -> &mut [f32]{
+        if !self.is_contiguous(){
+            *self = self.contiguous();
         }
-        Tensor::from_vec(&batches, batches_shape).unwrap().unsqueeze_view(n-1)
+        Arc::make_mut(&mut self.data).as_mut_slice()
+    }
 
+    pub fn apply_<F>(&mut self, f: F)
+    where F: Fn(f32) -> f32 + Sync
+    {
+        strided::map_(self, f)
+    }
+
+    pub fn add_(&mut self, b: &Tensor){
+        strided::zip_map_(self, b, |x, y| x+y)
+    }
+
+    pub fn mul_(&mut self, b: &Tensor){
+        strided::zip_map_(self, b, |x, y| x*y)
+    }
+
+    // self <- self + alpha*x
+    pub fn axpy_(&mut self, alpha: f32, x: &Tensor){
+        strided::zip_map_(self, x, |s, v| s + alpha*v)
+    }
+    // suppose que keepdim = true;
+    pub fn apply_and_reduce_last(&self, f: fn(f32, f32) -> f32, neutral_el: f32) -> Tensor{
+        assert!(!self.shape.is_empty());
+        self.reduce_with(&[self.shape.len()-1], true, neutral_el, f)
     }
     // suppose que keepdim = true;
     pub fn sum_last(&self) -> Tensor{
@@ -387,28 +504,9 @@ impl Tensor{
         self.apply_and_reduce_last(|x, y| x.max(y), f32::NEG_INFINITY)
     }
 
-    //suppose que keepdim = true; 
     pub fn argmax_last(&self)-> Vec<usize>{
-        assert!(self.shape.len() >= 1);
-        
-        let n = self.shape.len();
-        let batches_shape= &self.shape[0..(n-1)];
-        let batches_number = batches_shape.numel();
-        let mut batches = vec![f32::NEG_INFINITY; batches_number];
-        let mut args = vec![0; batches_number];
-
-        for lin in 0..self.shape.numel(){
-            let x = self.get_from_lin(lin);
-            let new_idx = lin/self.shape[n-1];
-            if batches[new_idx] < x{
-                batches[new_idx] = x; 
-                args[new_idx] = lin%self.shape[n-1];
-            }
-        }
-
-        args
-
-
+        assert!(!self.shape.is_empty());
+        self.argmax(&[self.shape.len()-1], false).data.iter().map(|&i| i as usize).collect()
     }
 
 }
@@ -442,7 +540,7 @@ impl fmt::Display for Tensor {
         writeln!(f, "  offset: {},", self.offset)?;
 
         // Gestion des tenseurs vides (au moins une dim = 0)
-        if self.shape.iter().any(|&d| d == 0) {
+        if self.shape.contains(&0) {
             writeln!(f, "  data: []")?;
             return write!(f, ")");
         }
@@ -1,4 +1,8 @@
 mod dynamic;
+mod op;
+mod debug;
 
 pub use dynamic::{Trace, NodeId};
-pub use dynamic::Node;
\ No newline at end of file
+pub use dynamic::Node;
+pub use dynamic::{VjpFn, TracedVjpFn, JvpFn};
+pub use op::OpKind;
@@ -0,0 +1,102 @@
+use std::fmt::Write;
+
+use crate::trace::{Trace, NodeId, OpKind};
+
+/*
+outils de debug: la trace en graphviz (dot -Tsvg trace.dot > trace.svg) ou en tableau texte.
+*/
+
+// nom de l'op + ses attributs en version courte
+fn label(op: &OpKind) -> String{
+    match op{
+        OpKind::Scale(c) => format!("Scale({c})"),
+        OpKind::L2Reg(l) => format!("L2Reg({l})"),
+        OpKind::SumToShape(s) | OpKind::BroadcastTo(s) | OpKind::Permute(s) | OpKind::Reshape(s) => format!("{}({s:?})", op.name()),
+        OpKind::Unsqueeze(a) | OpKind::Squeeze(a) => format!("{}({a})", op.name()),
+        OpKind::Conv2d(o) | OpKind::Conv2dInputGrad(o) | OpKind::Conv2dKernelGrad(o) =>
+            format!("{}(s={:?} p={:?} d={:?})", op.name(), o.stride, o.padding, o.dilation),
+        OpKind::MaxPool2d{kernel, stride, padding} | OpKind::AvgPool2d{kernel, stride, padding} =>
+            format!("{}(k={kernel:?} s={stride:?} p={padding:?})", op.name()),
+        OpKind::AdaptiveAvgPool2d(s) => format!("AdaptiveAvgPool2d({s:?})"),
+        OpKind::Sum{axes, keepdim} | OpKind::Mean{axes, keepdim} | OpKind::Max{axes, keepdim} | OpKind::Min{axes, keepdim}
+        | OpKind::Prod{axes, keepdim} | OpKind::Var{axes, keepdim} | OpKind::Std{axes, keepdim} | OpKind::ArgMax{axes, keepdim}
+        | OpKind::ArgMin{axes, keepdim} | OpKind::LogSumExp{axes, keepdim} | OpKind::Norm{axes, keepdim} =>
+            format!("{}(axes={axes:?}{})", op.name(), if *keepdim {", keepdim"} else {""}),
+        _ => op.name().to_string(),
+    }
+}
+
+impl Trace{
+    /*
+    graphe au format dot: un noeud par op (id, op, shape), params en bleu, entrées en gris.
+    avec root, les noeuds de order(root) (ceux que le backward parcourt) et leurs arêtes sont en gras,
+    le reste est grisé.
+    */
+    pub fn to_dot(&self, root: Option<NodeId>) -> String{
+        let mut on_path = vec![root.is_none(); self.len()];
+        if let Some(r) = root{
+            for id in self.order(r){
+                on_path[id] = true;
+            }
+        }
+
+        let mut out = String::from("digraph trace {\n    rankdir=TB;\n    node [fontname=\"monospace\", fontsize=10];\n");
+        for (id, &path) in on_path.iter().enumerate(){
+            let node = self.node(id);
+            let (shape, fill) = match node.op{
+                OpKind::Param => ("ellipse", "lightblue"),
+                OpKind::Input => ("ellipse", "lightgrey"),
+                _ => ("box", "white"),
+            };
+            let style = match (path, Some(id) == root){
+                (_, true) => "filled,bold\", penwidth=\"2.5\", color=\"red",
+                (true, _) => "filled,bold",
+                (false, _) => "filled,dashed\", fontcolor=\"grey50\", color=\"grey70",
+            };
+            let _ = writeln!(out, "    n{id} [label=\"#{id} {}\\n{:?}\", shape={shape}, fillcolor={fill}, style=\"{style}\"];",
+                label(&node.op), self.get_tensor(id).shape);
+        }
+        for id in 0..self.len(){
+            for &p in &self.node(id).parents_id{
+                let style = if on_path[id] && on_path[p] {""} else {" [color=\"grey70\", style=dashed]"};
+                let _ = writeln!(out, "    n{p} -> n{id}{style};");
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /*
+    un noeud par ligne: id, op, shape, parents, et ce qui est disponible pour la dérivation (vjp, vjp tracée, jvp).
+    termine par le nombre de noeuds, de params et de scalaires stockés.
+    */
+    pub fn summary(&self) -> String{
+        let mut rows: Vec<[String; 5]> = vec![["id".into(), "op".into(), "shape".into(), "parents".into(), "dérivées".into()]];
+        for id in 0..self.len(){
+            let node = self.node(id);
+            let parents = if node.parents_id.is_empty() {"-".to_string()} else {format!("{:?}", node.parents_id.as_slice())};
+            let derivs: Vec<&str> = [(node.vjp.is_some(), "vjp"), (node.vjp_traced.is_some(), "traced"), (node.jvp.is_some(), "jvp")]
+                .iter().filter(|(has, _)| *has).map(|(_, n)| *n).collect();
+            let derivs = match node.op{
+                OpKind::Param | OpKind::Input => String::new(),
+                _ if derivs.is_empty() => "aucune".to_string(),
+                _ => derivs.join(" "),
+            };
+            rows.push([id.to_string(), label(&node.op), format!("{:?}", self.get_tensor(id).shape), parents, derivs]);
+        }
+
+        let widths: Vec<usize> = (0..5).map(|c| rows.iter().map(|r| r[c].chars().count()).max().unwrap()).collect();
+        let mut out = String::new();
+        for r in &rows{
+            let line: Vec<String> = r.iter().zip(&widths).map(|(s, w)| format!("{s:<w$}")).collect();
+            let _ = writeln!(out, "{}", line.join("  ").trim_end());
+        }
+
+        let numel = |id: NodeId| self.get_tensor(id).shape.iter().product::<usize>();
+        let n_inputs = (0..self.len()).filter(|&id| self.node(id).op == OpKind::Input).count();
+        let _ = writeln!(out, "{} noeuds, {} params ({} scalaires), {} entrées, {} scalaires au total, grad {}",
+            self.len(), self.params_id().len(), self.params_id().iter().map(|&id| numel(id)).sum::<usize>(),
+            n_inputs, (0..self.len()).map(numel).sum::<usize>(), if self.grad_enabled() {"activé"} else {"désactivé"});
+        out
+    }
+}
@@ -1,4 +1,6 @@
 use crate::tensor::Tensor;
+use crate::ops::add;
+use crate::trace::OpKind;
 
 use smallvec::SmallVec;
 
@@ -6,17 +8,31 @@ use smallvec::SmallVec;
 
 pub type NodeId = usize; 
 
-type VjpFn = 
+pub type VjpFn = 
     Box<dyn Fn(&Tensor) -> SmallVec<[(NodeId, Tensor); 2]> +Send + Sync>;
 
+// tangentes des parents (dans l'ordre de parents_id) => tangente de la sortie
+pub type JvpFn = 
+    Box<dyn Fn(&[Tensor]) -> Tensor +Send + Sync>;
+
+// meme chose que VjpFn, mais le gradient est construit avec des ops sur la trace (donc lui meme dérivable)
+pub type TracedVjpFn = 
+    Box<dyn Fn(&mut Trace, NodeId) -> SmallVec<[(NodeId, NodeId); 2]> +Send + Sync>;
+
 pub struct Node{ // TODO: remplacer value par rien, pour du 100% JAX. la entre pytorch et jax..  
 
+    pub op: OpKind, 
+
     pub value: Tensor, 
 
     pub parents_id: SmallVec<[NodeId; 2]>, 
 
     pub vjp: Option<VjpFn>,
 
+    pub vjp_traced: Option<TracedVjpFn>,
+
+    pub jvp: Option<JvpFn>,
+
     pub is_param: bool 
 }
 
@@ -24,12 +40,37 @@ pub struct Trace{
     nodes: Vec<Node>, 
 
     params_id: Vec<NodeId>,
+
+    // false => les ops ne construisent aucune closure de dérivation (inférence, réseaux cibles...)
+    grad_enabled: bool,
+}
+
+impl Default for Trace {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Trace{
     pub fn new() -> Trace 
     {
-        Trace { nodes: Vec::new(), params_id: Vec::new() }
+        Trace { nodes: Vec::new(), params_id: Vec::new(), grad_enabled: true }
+    }
+
+    // trace d'inférence: pas de vjp/jvp, donc pas de copies des opérandes gardées pour le backward 
+    pub fn no_grad() -> Trace 
+    {
+        Trace { nodes: Vec::new(), params_id: Vec::new(), grad_enabled: false }
+    }
+
+    pub fn grad_enabled(&self) -> bool
+    {
+        self.grad_enabled
+    }
+
+    pub fn set_grad_enabled(&mut self, enabled: bool)
+    {
+        self.grad_enabled = enabled;
     }
     
     pub fn len(&self) -> usize
@@ -37,13 +78,35 @@ impl Trace{
         self.nodes.len()
     }    
 
+    pub fn is_empty(&self) -> bool
+    {
+        self.nodes.is_empty()
+    }
+
     pub fn get_tensor(&self, id: NodeId)-> &Tensor
     {
         &self.nodes[id].value
     }
 
-    pub fn push(&mut self, node: Node) -> NodeId
+    pub fn node(&self, id: NodeId) -> &Node
+    {
+        &self.nodes[id]
+    }
+
+    // ids des params, dans l'ordre des appels à param()
+    pub fn params_id(&self) -> &[NodeId]
     {
+        &self.params_id
+    }
+
+    pub fn push(&mut self, mut node: Node) -> NodeId
+    {
+        // filet de sécurité: une op qui n'a pas testé grad_enabled avant de construire ses règles les perd ici
+        if !self.grad_enabled{
+            node.vjp = None;
+            node.vjp_traced = None;
+            node.jvp = None;
+        }
         let id = self.len();
         self.nodes.push(node); 
         //if node.is_param{
@@ -52,12 +115,29 @@ impl Trace{
         id
     }
 
+    // noeud sans règle de dérivation, utilisé par les ops en mode no_grad (avant tout calcul de dérivée)
+    pub fn push_no_grad(&mut self, op: OpKind, value: Tensor, parents_id: SmallVec<[NodeId; 2]>) -> NodeId
+    {
+        self.push(Node{
+            op, 
+            value, 
+            parents_id, 
+            vjp: None, 
+            vjp_traced: None, 
+            jvp: None, 
+            is_param: false
+        })
+    }
+
     pub fn input(&mut self, t: Tensor) -> NodeId
     {
         self.push(Node{
+            op: OpKind::Input, 
             value: t, 
             parents_id: SmallVec::new(), 
             vjp: None, 
+            vjp_traced: None, 
+            jvp: None, 
             is_param: false
         })
     }
@@ -65,9 +145,12 @@ impl Trace{
     pub fn param(&mut self, t: Tensor) -> NodeId
     {
         let id = self.push(Node{
+            op: OpKind::Param, 
             value: t, 
             parents_id: SmallVec::new(), 
             vjp: None, 
+            vjp_traced: None, 
+            jvp: None, 
             is_param: false
         }); 
         self.params_id.push(id); 
@@ -90,45 +173,129 @@ impl Trace{
             }
             order.push(u);
         }
-        dfs(&self, &mut visited, &mut order, root);
+        dfs(self, &mut visited, &mut order, root);
         order.reverse();
         order
 
     }
     
+    // en place: le buffer du gradient déjà accumulé n'est copié que s'il est partagé (ex: g_out renvoyé tel quel par un vjp)
     pub fn accum(slot: &mut Option<Tensor>, delta: Tensor)
     {
-        *slot =Some(
-            match slot.take(){
-                Some(g) => &g+&delta,
-                None =>delta
-            }
-        )
+        match slot{
+            Some(g) => g.add_(&delta),
+            None => *slot = Some(delta),
+        }
     }
 
-    pub fn backward_param_grads(&self, root: NodeId) -> Vec<Tensor>
+    /*
+    seed = gradient de départ sur root (Tensor::ones pour une loss scalaire). 
+    pour une sortie non scalaire, un seed one-hot donne une ligne de la jacobienne.
+    renvoie le gradient de chaque noeud, None si non atteint depuis root.
+    */
+    fn backward_all(&self, root: NodeId, seed: Tensor) -> Vec<Option<Tensor>>
     {
+        assert!(self.grad_enabled, "dynamic: backward sur une trace no_grad");
+        assert_eq!(self.get_tensor(root).shape, seed.shape, "dynamic: le seed n'a pas la shape de la sortie");
         let order = self.order(root);
 
         let mut grads: Vec<Option<Tensor>> = vec![None; self.len()];
-        grads[root] = Some(Tensor::ones(&self.get_tensor(root).shape));
+        grads[root] = Some(seed);
 
         for &node_id in order.iter(){
-            let Some(ref g_out) = grads[node_id] else {println!("gradient non trouvé"); continue}; // permet de chopper le g_out dans le Some directement. Normalement déjà dedans car gradient déjà calculé par l'ordre topologique 
+            let Some(ref g_out) = grads[node_id] else {continue}; // permet de chopper le g_out dans le Some directement. Normalement déjà dedans car gradient déjà calculé par l'ordre topologique 
             if let Some(ref vjp) = self.nodes[node_id].vjp{ // si il a une vector jacobian product
                 for (parent_id, tensor) in vjp(g_out){
                     Trace::accum(&mut grads[parent_id], tensor);
                 }
             }
         }
+        grads
+    }
 
-        //TODO : faire map + iter pcq la c pas fou fou 
-        let mut res = Vec::with_capacity(self.params_id.len());
-        for &id in &self.params_id{
-            res.push(grads[id].as_ref().unwrap_or_else(|| panic!("dynamic: le gradient d'un param n'est pas trouvable")).clone());
-        }
-        res
+    // gradient de root par rapport à n'importe quels noeuds (inputs compris), nul si non atteint
+    pub fn backward(&self, root: NodeId, wrt: &[NodeId]) -> Vec<Tensor>
+    {
+        let grads = self.backward_all(root, Tensor::ones(&self.get_tensor(root).shape));
+        wrt.iter().map(|&id| grads[id].clone().unwrap_or_else(|| Tensor::zeros(&self.get_tensor(id).shape))).collect()
+    }
 
+    pub fn backward_param_grads(&self, root: NodeId, seed: Tensor) -> Vec<Tensor>
+    {
+        let grads = self.backward_all(root, seed);
+        self.params_id.iter().map(|&id| grads[id].clone().unwrap_or_else(|| Tensor::zeros(&self.get_tensor(id).shape))).collect()
     }
     
+
+    /*
+    meme parcours que backward_param_grads, mais chaque vjp est enregistrée comme de nouveaux noeuds sur la trace.
+    les gradients renvoyés sont donc des NodeId qu'on peut réutiliser dans le calcul (pénalité de gradient, ...) 
+    et redériver: grad(grad(f)), produits hessienne-vecteur, ...
+    les noeuds sans gradient (non atteints depuis root) recoivent une constante nulle.
+    */
+    pub fn backward_traced(&mut self, root: NodeId, wrt: &[NodeId]) -> Vec<NodeId>
+    {
+        assert!(self.grad_enabled, "dynamic: backward sur une trace no_grad");
+        let order = self.order(root);
+        let n = self.len(); // les noeuds ajoutés pendant le backward ne sont jamais des parents des anciens
+
+        let mut grads: Vec<Option<NodeId>> = vec![None; n];
+        grads[root] = Some(self.input(Tensor::ones(&self.get_tensor(root).shape)));
+
+        for &node_id in order.iter(){
+            let Some(g_out) = grads[node_id] else {continue};
+            if self.nodes[node_id].vjp.is_none(){
+                continue;
+            }
+            // on sort la closure le temps de l'appel, pour pouvoir passer &mut self
+            let Some(vjp) = self.nodes[node_id].vjp_traced.take() else {
+                panic!("dynamic: le noeud {node_id} n'a pas de vjp tracée, impossible de le dériver plusieurs fois")
+            };
+            let contribs = vjp(self, g_out);
+            self.nodes[node_id].vjp_traced = Some(vjp);
+
+            for (parent_id, g) in contribs{
+                grads[parent_id] = Some(match grads[parent_id]{
+                    Some(prev) => add(self, prev, g),
+                    None => g,
+                });
+            }
+        }
+
+        wrt.iter().map(|&id| match grads[id]{
+            Some(g) => g,
+            None => self.input(Tensor::zeros(&self.get_tensor(id).shape)),
+        }).collect()
+    }
+
+    /*
+    mode direct (forward): propage des tangentes depuis les seeds. les noeuds sont poussés dans un ordre topologique,
+    donc un simple parcours par id suffit. res[id] vaut None si le noeud ne dépend d'aucune seed;
+    un parent sans tangente compte comme une tangente nulle.
+    Err si un noeud qui dépend des seeds n'a pas de règle jvp (noeud créé en no_grad par ex.).
+    */
+    pub fn forward_tangents(&self, seeds: &[(NodeId, Tensor)]) -> Result<Vec<Option<Tensor>>, String>
+    {
+        let mut tangents: Vec<Option<Tensor>> = vec![None; self.len()];
+        for (id, t) in seeds{
+            assert_eq!(self.get_tensor(*id).shape, t.shape, "dynamic: la tangente du noeud {id} n'a pas la shape du noeud");
+            tangents[*id] = Some(t.clone());
+        }
+        let Some(start) = seeds.iter().map(|(id, _)| *id).min() else {return Ok(tangents)};
+
+        for node_id in start..self.len(){
+            let node = &self.nodes[node_id];
+            if tangents[node_id].is_some() || node.parents_id.iter().all(|&p| tangents[p].is_none()){
+                continue;
+            }
+            let Some(ref jvp) = node.jvp else {
+                return Err(format!("dynamic: le noeud {node_id} ({}) dépend des tangentes mais n'a pas de règle jvp", node.op.name()));
+            };
+            let parent_tangents: Vec<Tensor> = node.parents_id.iter()
+                .map(|&p| tangents[p].clone().unwrap_or_else(|| Tensor::zeros(&self.get_tensor(p).shape)))
+                .collect();
+            tangents[node_id] = Some(jvp(&parent_tangents));
+        }
+        Ok(tangents)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use crate::ops::conv::Conv2dOpts;
+
+/*
+nature de l'op qui a produit un noeud, avec ses attributs. 
+les closures vjp/jvp restent la source de vérité pour la dérivation: OpKind sert à inspecter la trace (export onnx, affichage...).
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpKind{
+    Input, 
+    Param, 
+
+    Add, 
+    Sub, 
+    Mul, // hadamard
+    Scale(f32), 
+    MatMul, 
+    StopGradient, 
+
+    Relu, 
+    Tanh, 
+    Map, // apply() avec une fonction quelconque, opaque
+    Softmax, // dernier axe
+
+    SoftmaxCrossEntropy, // par ligne, avant la moyenne
+    L2Reg(f32), 
+
+    MeanAll, 
+    SumAll, 
+    SumLast, // keepdim
+    SumToShape(Vec<usize>), 
+    BroadcastTo(Vec<usize>), 
+    MatTranspose, 
+    Unsqueeze(usize), 
+    Squeeze(usize), 
+    Permute(Vec<usize>), 
+    Reshape(Vec<usize>), // aussi view et flatten
+
+    // réductions: axes triés (tous si [] à la création), keepdim garde les axes réduits en taille 1
+    Sum{axes: Vec<usize>, keepdim: bool}, 
+    Mean{axes: Vec<usize>, keepdim: bool}, 
+    Max{axes: Vec<usize>, keepdim: bool}, 
+    Min{axes: Vec<usize>, keepdim: bool}, 
+    Prod{axes: Vec<usize>, keepdim: bool}, 
+    Var{axes: Vec<usize>, keepdim: bool}, // biaisée
+    Std{axes: Vec<usize>, keepdim: bool}, 
+    ArgMax{axes: Vec<usize>, keepdim: bool}, 
+    ArgMin{axes: Vec<usize>, keepdim: bool}, 
+    LogSumExp{axes: Vec<usize>, keepdim: bool}, 
+    Norm{axes: Vec<usize>, keepdim: bool}, // l2
+
+    Conv2d(Conv2dOpts), 
+    Conv2dInputGrad(Conv2dOpts), 
+    Conv2dKernelGrad(Conv2dOpts), 
+
+    MaxPool2d{kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize)}, 
+    AvgPool2d{kernel: (usize, usize), stride: (usize, usize), padding: (usize, usize)}, // count_include_pad
+    AdaptiveAvgPool2d((usize, usize)), 
+    GlobalAvgPool2d, 
+    // noeuds internes des gradients tracés des poolings
+    PoolScatter, 
+    PoolGather, 
+    AvgPoolGrad, 
+}
+
+impl OpKind{
+    // nom court, pour l'affichage et les messages d'erreur
+    pub fn name(&self) -> &'static str{
+        match self{
+            OpKind::Input => "Input",
+            OpKind::Param => "Param",
+            OpKind::Add => "Add",
+            OpKind::Sub => "Sub",
+            OpKind::Mul => "Mul",
+            OpKind::Scale(_) => "Scale",
+            OpKind::MatMul => "MatMul",
+            OpKind::StopGradient => "StopGradient",
+            OpKind::Relu => "Relu",
+            OpKind::Tanh => "Tanh",
+            OpKind::Map => "Map",
+            OpKind::Softmax => "Softmax",
+            OpKind::SoftmaxCrossEntropy => "SoftmaxCrossEntropy",
+            OpKind::L2Reg(_) => "L2Reg",
+            OpKind::MeanAll => "MeanAll",
+            OpKind::SumAll => "SumAll",
+            OpKind::SumLast => "SumLast",
+            OpKind::SumToShape(_) => "SumToShape",
+            OpKind::BroadcastTo(_) => "BroadcastTo",
+            OpKind::MatTranspose => "MatTranspose",
+            OpKind::Unsqueeze(_) => "Unsqueeze",
+            OpKind::Squeeze(_) => "Squeeze",
+            OpKind::Permute(_) => "Permute",
+            OpKind::Reshape(_) => "Reshape",
+            OpKind::Sum{..} => "Sum",
+            OpKind::Mean{..} => "Mean",
+            OpKind::Max{..} => "Max",
+            OpKind::Min{..} => "Min",
+            OpKind::Prod{..} => "Prod",
+            OpKind::Var{..} => "Var",
+            OpKind::Std{..} => "Std",
+            OpKind::ArgMax{..} => "ArgMax",
+            OpKind::ArgMin{..} => "ArgMin",
+            OpKind::LogSumExp{..} => "LogSumExp",
+            OpKind::Norm{..} => "Norm",
+            OpKind::Conv2d(_) => "Conv2d",
+            OpKind::Conv2dInputGrad(_) => "Conv2dInputGrad",
+            OpKind::Conv2dKernelGrad(_) => "Conv2dKernelGrad",
+            OpKind::MaxPool2d{..} => "MaxPool2d",
+            OpKind::AvgPool2d{..} => "AvgPool2d",
+            OpKind::AdaptiveAvgPool2d(_) => "AdaptiveAvgPool2d",
+            OpKind::GlobalAvgPool2d => "GlobalAvgPool2d",
+            OpKind::PoolScatter => "PoolScatter",
+            OpKind::PoolGather => "PoolGather",
+            OpKind::AvgPoolGrad => "AvgPoolGrad",
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod inits; 
-pub mod params; 
\ No newline at end of file
+pub mod params; 
+pub mod rng;
+pub mod parallel;
@@ -19,4 +19,16 @@ pub fn kaiming(in_dim: usize, out_dim: usize) -> Tensor{
         vec.push(rng.gen_range((-limit)..(limit)));
     }
     Tensor::from_vec(&vec, &[in_dim, out_dim]).unwrap()
-}
\ No newline at end of file
+}
+
+// w: (out_ch, in_ch, kh, kw), fan_in = in_ch*kh*kw
+pub fn kaiming_conv2d(in_ch: usize, out_ch: usize, kh: usize, kw: usize) -> Tensor{
+    let fan_in = in_ch*kh*kw;
+    let limit = f32::sqrt(6f32/(fan_in as f32));
+    let mut rng = rand::thread_rng();
+    let mut vec = Vec::with_capacity(out_ch*fan_in);
+    for _ in 0..out_ch*fan_in{
+        vec.push(rng.gen_range((-limit)..(limit)));
+    }
+    Tensor::from_vec(&vec, &[out_ch, in_ch, kh, kw]).unwrap()
+}
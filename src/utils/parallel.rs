@@ -0,0 +1,55 @@
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+// 0: pas d'override, on suit LAMP_NUM_THREADS / les coeurs
+static OVERRIDE: AtomicUsize = AtomicUsize::new(0);
+
+/*
+parallélisme sans dépendance externe: std::thread::scope sur des morceaux de travail indépendants
+(typiquement des tranches disjointes du buffer de sortie, donc pas de synchronisation).
+nombre de threads: set_num_threads si appelé, sinon variable d'environnement LAMP_NUM_THREADS si définie, sinon le nombre de coeurs.
+*/
+pub fn num_threads() -> usize{
+    static N: OnceLock<usize> = OnceLock::new();
+    match OVERRIDE.load(Ordering::Relaxed){
+        0 => *N.get_or_init(|| {
+            std::env::var("LAMP_NUM_THREADS").ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n| n > 0)
+                .unwrap_or_else(|| thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+        }),
+        n => n,
+    }
+}
+
+// fixe le nombre de threads pour tout le process (tests, benchs), quel que soit le moment de l'appel. 0 revient au défaut
+pub fn set_num_threads(n: usize){
+    OVERRIDE.store(n, Ordering::Relaxed);
+}
+
+// répartit items en num_threads() groupes contigus, un thread par groupe. séquentiel s'il n'y a qu'un groupe
+pub fn for_each<T: Send>(items: Vec<T>, f: impl Fn(T) + Sync){
+    let threads = num_threads().min(items.len());
+    if threads <= 1{
+        items.into_iter().for_each(f);
+        return;
+    }
+    let per_thread = items.len().div_ceil(threads);
+    let mut items = items.into_iter();
+    let f = &f;
+    thread::scope(|s| {
+        loop{
+            let group: Vec<T> = items.by_ref().take(per_thread).collect();
+            if group.is_empty(){
+                break;
+            }
+            s.spawn(move || group.into_iter().for_each(f));
+        }
+    });
+}
+
+// f(indice du morceau, morceau) sur des morceaux de `chunk` éléments de out (le dernier peut etre plus court)
+pub fn for_each_chunk_mut<T: Send>(out: &mut [T], chunk: usize, f: impl Fn(usize, &mut [T]) + Sync){
+    for_each(out.chunks_mut(chunk.max(1)).enumerate().collect(), |(i, c)| f(i, c));
+}
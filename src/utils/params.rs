@@ -10,4 +10,195 @@ pub fn get_params_id(tr: &mut Trace, params: &[Tensor])-> Vec<usize>{
         param_ids.push(tr.param(p.clone()));
     }
     param_ids
-}
\ No newline at end of file
+}
+
+/*
+tout ce qui s'aplatit en une liste de tenseurs et se reconstruit avec la meme structure (pytree à la JAX).
+value_and_grad pousse les feuilles dans l'ordre de leaves(), et renvoie les gradients via with_leaves: 
+meme structure, memes noms que les params.
+*/
+pub trait Pytree{
+    type Tree;
+
+    fn leaves(&self) -> Vec<Tensor>;
+
+    fn with_leaves(&self, leaves: Vec<Tensor>) -> Self::Tree;
+
+    // un nom par feuille, pour les messages (gradcheck...): l'indice par défaut, le chemin pour un ParamTree
+    fn leaf_names(&self) -> Vec<String>{
+        (0..self.leaves().len()).map(|i| i.to_string()).collect()
+    }
+}
+
+impl Pytree for [Tensor]{
+    type Tree = Vec<Tensor>;
+
+    fn leaves(&self) -> Vec<Tensor>{
+        self.to_vec()
+    }
+
+    fn with_leaves(&self, leaves: Vec<Tensor>) -> Vec<Tensor>{
+        assert_eq!(self.len(), leaves.len(), "pytree: nombre de feuilles différent");
+        leaves
+    }
+}
+
+impl Pytree for Vec<Tensor>{
+    type Tree = Vec<Tensor>;
+
+    fn leaves(&self) -> Vec<Tensor>{
+        self.clone()
+    }
+
+    fn with_leaves(&self, leaves: Vec<Tensor>) -> Vec<Tensor>{
+        self.as_slice().with_leaves(leaves)
+    }
+}
+
+impl<const N: usize> Pytree for [Tensor; N]{
+    type Tree = Vec<Tensor>;
+
+    fn leaves(&self) -> Vec<Tensor>{
+        self.to_vec()
+    }
+
+    fn with_leaves(&self, leaves: Vec<Tensor>) -> Vec<Tensor>{
+        self.as_slice().with_leaves(leaves)
+    }
+}
+
+/*
+arbre de params nommés: chaque feuille a un chemin du type "layer0.w".
+l'ordre des enfants est conservé, c'est l'ordre des feuilles (donc des NodeId dans build).
+
+    let params = ParamTree::from_named(vec![("layer0.w".into(), w), ("layer0.b".into(), b)])?;
+    params.get("layer0.w")
+*/
+#[derive(Debug, Clone)]
+pub enum ParamTree{
+    Leaf(Tensor),
+    Node(Vec<(String, ParamTree)>),
+}
+
+impl Default for ParamTree{
+    fn default() -> Self{
+        ParamTree::Node(Vec::new())
+    }
+}
+
+impl ParamTree{
+    pub fn from_named(named: Vec<(String, Tensor)>) -> Result<ParamTree, String>{
+        let mut tree = ParamTree::default();
+        for (path, t) in named{
+            tree.insert(&path, t)?;
+        }
+        Ok(tree)
+    }
+
+    pub fn insert(&mut self, path: &str, t: Tensor) -> Result<(), String>{
+        let ParamTree::Node(children) = self else {
+            return Err(format!("param tree: impossible d'insérer '{path}' sous une feuille"));
+        };
+        match path.split_once('.'){
+            None => {
+                if children.iter().any(|(n, _)| n == path){
+                    return Err(format!("param tree: '{path}' existe déjà"));
+                }
+                children.push((path.to_string(), ParamTree::Leaf(t)));
+                Ok(())
+            }
+            Some((head, rest)) => {
+                let pos = match children.iter().position(|(n, _)| n == head){
+                    Some(pos) => pos,
+                    None => {
+                        children.push((head.to_string(), ParamTree::default()));
+                        children.len() - 1
+                    }
+                };
+                children[pos].1.insert(rest, t).map_err(|e| format!("{e} (dans '{head}')"))
+            }
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<&Tensor>{
+        match (self, path.split_once('.')){
+            (ParamTree::Leaf(t), _) if path.is_empty() => Some(t),
+            (ParamTree::Node(children), None) => children.iter().find(|(n, _)| n == path).and_then(|(_, c)| c.get("")),
+            (ParamTree::Node(children), Some((head, rest))) => children.iter().find(|(n, _)| n == head).and_then(|(_, c)| c.get(rest)),
+            _ => None,
+        }
+    }
+
+    // feuilles avec leur chemin complet, dans l'ordre des leaves
+    pub fn named_leaves(&self) -> Vec<(String, Tensor)>{
+        fn walk(tree: &ParamTree, prefix: &str, out: &mut Vec<(String, Tensor)>){
+            match tree{
+                ParamTree::Leaf(t) => out.push((prefix.to_string(), t.clone())),
+                ParamTree::Node(children) => for (name, c) in children{
+                    let path = if prefix.is_empty() {name.clone()} else {format!("{prefix}.{name}")};
+                    walk(c, &path, out);
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(self, "", &mut out);
+        out
+    }
+
+    // feuilles modifiables, dans l'ordre des leaves
+    pub fn leaves_mut(&mut self) -> Vec<&mut Tensor>{
+        match self{
+            ParamTree::Leaf(t) => vec![t],
+            ParamTree::Node(children) => children.iter_mut().flat_map(|(_, c)| c.leaves_mut()).collect(),
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String>{
+        self.named_leaves().into_iter().map(|(p, _)| p).collect()
+    }
+
+    pub fn len(&self) -> usize{
+        match self{
+            ParamTree::Leaf(_) => 1,
+            ParamTree::Node(children) => children.iter().map(|(_, c)| c.len()).sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool{
+        self.len() == 0
+    }
+
+    pub fn map(&self, f: impl Fn(&Tensor) -> Tensor) -> ParamTree{
+        self.with_leaves(self.leaves().iter().map(f).collect())
+    }
+
+    // meme chemins, dans le meme ordre
+    pub fn same_structure(&self, other: &ParamTree) -> bool{
+        self.paths() == other.paths()
+    }
+}
+
+impl Pytree for ParamTree{
+    type Tree = ParamTree;
+
+    fn leaves(&self) -> Vec<Tensor>{
+        self.named_leaves().into_iter().map(|(_, t)| t).collect()
+    }
+
+    fn leaf_names(&self) -> Vec<String>{
+        self.paths()
+    }
+
+    fn with_leaves(&self, leaves: Vec<Tensor>) -> ParamTree{
+        assert_eq!(self.len(), leaves.len(), "param tree: nombre de feuilles différent");
+        fn rebuild(tree: &ParamTree, it: &mut std::vec::IntoIter<Tensor>) -> ParamTree{
+            match tree{
+                ParamTree::Leaf(_) => ParamTree::Leaf(it.next().unwrap()),
+                ParamTree::Node(children) => ParamTree::Node(
+                    children.iter().map(|(n, c)| (n.clone(), rebuild(c, it))).collect()
+                ),
+            }
+        }
+        rebuild(self, &mut leaves.into_iter())
+    }
+}
@@ -0,0 +1,56 @@
+use rand::{RngCore, Rng};
+
+/*
+générateur splitmix64: tout l'état tient dans un u64, donc on peut le sauvegarder dans un checkpoint 
+et reprendre exactement la meme suite de tirages (contrairement à thread_rng).
+implémente RngCore, donc utilisable partout ou rand attend un Rng (shuffle, gen_range...).
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitMix64{
+    state: u64,
+}
+
+impl SplitMix64{
+    pub fn seed(seed: u64) -> SplitMix64{
+        SplitMix64 { state: seed }
+    }
+
+    // graine tirée au hasard, pour les cas sans seed explicite
+    pub fn from_entropy() -> SplitMix64{
+        SplitMix64 { state: rand::thread_rng().gen() }
+    }
+
+    pub fn state(&self) -> u64{
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: u64){
+        self.state = state;
+    }
+}
+
+impl RngCore for SplitMix64{
+    fn next_u64(&mut self) -> u64{
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn next_u32(&mut self) -> u32{
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]){
+        for chunk in dest.chunks_mut(8){
+            let v = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&v[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error>{
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+// un run repris depuis un checkpoint (meme en milieu d'epoch) doit donner exactement le meme résultat qu'un run d'une traite
+use lamp::tensor::Tensor;
+use lamp::nn::losses;
+use lamp::nn::module::Module;
+use lamp::nn::layers::sequential::Sequential;
+use lamp::nn::layers::linear::Linear;
+use lamp::nn::layers::activations::Tanh;
+use lamp::optim::Optimizer;
+use lamp::optim::adam::Adam;
+use lamp::optim::lr_scheduler::{LrScheduler, LinearWarmup, StepLr};
+use lamp::dataloader::dataloader::{Dataset, DataLoader};
+use lamp::autodiff::value_and_grad::value_and_grad;
+use lamp::utils::params::{ParamTree, Pytree};
+use lamp::io::checkpoint::Checkpoint;
+
+struct Toy;
+
+impl Dataset for Toy{
+    type Item = (f32, f32);
+    fn len(&self) -> usize {10}
+    fn get(&mut self, idx: usize) -> (f32, f32){
+        let x = idx as f32 / 10.0;
+        (x, (3.0*x).sin())
+    }
+}
+
+type Collate = fn(Vec<(f32, f32)>) -> (Tensor, Tensor);
+
+fn collate(batch: Vec<(f32, f32)>) -> (Tensor, Tensor){
+    let n = batch.len();
+    let xs: Vec<f32> = batch.iter().flat_map(|&(x, _)| [x, x*x]).collect();
+    let ys: Vec<f32> = batch.iter().map(|&(_, y)| y).collect();
+    (Tensor::from_owned(xs, &[n, 2]).unwrap(), Tensor::from_owned(ys, &[n, 1]).unwrap())
+}
+
+struct Run{
+    model: Sequential,
+    params: ParamTree,
+    opt: Adam,
+    sched: LinearWarmup,
+    loader: DataLoader<Toy, Collate, (Tensor, Tensor)>,
+    epoch: usize,
+    step: usize,
+}
+
+impl Run{
+    fn new(params: &ParamTree, loader_seed: u64) -> Run{
+        let model = Sequential::new().layer(Linear::new(2, 4)).layer(Tanh).layer(Linear::new(4, 1));
+        let mut opt = Adam::new(0.05);
+        let sched = LinearWarmup::new(&mut opt, 3, 0.2).then(&mut opt, |o| StepLr::new(o, 2, 0.7));
+        // batchs de 3 sur 10 éléments: 4 batchs par epoch, le dernier incomplet
+        let loader = DataLoader::new(Toy, 3, true, collate as Collate).seed(loader_seed);
+        Run { model, params: params.clone(), opt, sched, loader, epoch: 0, step: 0 }
+    }
+
+    fn train(&mut self, steps: usize){
+        let end = self.step + steps;
+        while self.step < end{
+            let Some((xb, yb)) = self.loader.next() else {
+                self.loader.reset_epoch();
+                self.epoch += 1;
+                continue;
+            };
+            let model = &mut self.model;
+            let (_, grads) = value_and_grad(&self.params, |tr, pids| {
+                let (x, y) = (tr.input(xb.clone()), tr.input(yb.clone()));
+                model.bind_params(pids);
+                let out = model.forward(tr, x);
+                losses::mse(tr, out, y)
+            });
+            self.opt.update_tree_(&mut self.params, &grads);
+            self.sched.step(&mut self.opt);
+            self.step += 1;
+        }
+    }
+
+    fn checkpoint(&self) -> Checkpoint{
+        Checkpoint::new(self.epoch, self.step, self.params.clone(), &self.opt).scheduler(&self.sched).loader(self.loader.state())
+    }
+
+    fn restore(&mut self, ck: Checkpoint){
+        ck.restore_optim(&mut self.opt).unwrap();
+        ck.restore_scheduler(&mut self.sched).unwrap();
+        self.loader.load_state(ck.loader.clone().unwrap()).unwrap();
+        (self.epoch, self.step) = (ck.epoch, ck.step);
+        self.params = ck.params;
+    }
+}
+
+fn bits(ts: &[Tensor]) -> Vec<Vec<u32>>{
+    ts.iter().map(|t| t.contiguous().data.iter().map(|x| x.to_bits()).collect()).collect()
+}
+
+fn assert_same_run(a: &Run, b: &Run){
+    assert_eq!((a.epoch, a.step), (b.epoch, b.step));
+    assert_eq!(bits(&a.params.leaves()), bits(&b.params.leaves()));
+    let (sa, sb) = (a.opt.state(), b.opt.state());
+    assert_eq!(sa.step, sb.step);
+    for ((na, ta), (nb, tb)) in sa.buffers.iter().zip(&sb.buffers){
+        assert_eq!(na, nb);
+        assert_eq!(bits(ta), bits(tb), "buffer {na}");
+    }
+    assert_eq!(a.opt.lr().to_bits(), b.opt.lr().to_bits());
+    assert_eq!(a.sched.state(), b.sched.state());
+    assert_eq!(a.loader.state(), b.loader.state());
+}
+
+fn resume_matches(total: usize, split: usize){
+    let init = Sequential::new().layer(Linear::new(2, 4)).layer(Tanh).layer(Linear::new(4, 1)).init_tree();
+
+    let mut straight = Run::new(&init, 7);
+    straight.train(total);
+
+    let mut first = Run::new(&init, 7);
+    first.train(split);
+    let path = std::env::temp_dir().join(format!("lamp_test_{}_{split}.ckpt", std::process::id()));
+    first.checkpoint().save(&path).unwrap();
+
+    // autres params et autre ordre au départ: tout doit venir du checkpoint
+    let mut resumed = Run::new(&init.map(|t| t.apply(|x| x + 1.0)), 99);
+    resumed.restore(Checkpoint::load(&path, &init).unwrap());
+    std::fs::remove_file(path).unwrap();
+    resumed.train(total - split);
+
+    assert_same_run(&straight, &resumed);
+}
+
+#[test]
+fn resume_mid_epoch(){
+    // 6 pas = 1 epoch + 2 batchs: la reprise doit garder la position dans l'epoch
+    resume_matches(13, 6);
+}
+
+#[test]
+fn resume_at_epoch_boundary(){
+    resume_matches(13, 4);
+}
+
+#[test]
+fn resume_during_warmup(){
+    resume_matches(9, 1);
+}
@@ -0,0 +1,97 @@
+// clipping des gradients et accumulation sur plusieurs micro-batches
+use lamp::tensor::Tensor;
+use lamp::optim::clip::{global_norm, clip_grad_norm, clip_grad_value};
+use lamp::optim::accumulate::GradAccumulator;
+
+fn t(v: &[f32], shape: &[usize]) -> Tensor{
+    Tensor::from_vec(v, shape).unwrap()
+}
+
+fn data(ts: &[Tensor]) -> Vec<Vec<f32>>{
+    ts.iter().map(|t| t.contiguous().data.to_vec()).collect()
+}
+
+// norme globale 13: (3, 4) puis (12), comme un seul vecteur (3, 4, 12)
+fn grads() -> Vec<Tensor>{
+    vec![t(&[3.0, 4.0], &[2]), t(&[12.0], &[1, 1])]
+}
+
+#[test]
+fn global_norm_concatenates(){
+    assert_eq!(global_norm(&grads()), 13.0);
+    assert_eq!(global_norm(&[t(&[1.0, 2.0, 3.0, 4.0], &[2, 2]).mat_transpose()]), 30f32.sqrt());
+    assert_eq!(global_norm(&[]), 0.0);
+}
+
+#[test]
+fn clip_grad_norm_scales_above_max(){
+    let (clipped, norm) = clip_grad_norm(&grads(), 6.5);
+    // norme d'avant le clipping, puis tout est multiplié par 6.5/13
+    assert_eq!(norm, 13.0);
+    assert_eq!(data(&clipped), vec![vec![1.5, 2.0], vec![6.0]]);
+    assert_eq!(clipped[1].shape, vec![1, 1]);
+    assert!((global_norm(&clipped) - 6.5).abs() < 1e-6);
+}
+
+#[test]
+fn clip_grad_norm_keeps_below_max(){
+    for max in [13.0, 20.0]{
+        let (clipped, norm) = clip_grad_norm(&grads(), max);
+        assert_eq!(norm, 13.0);
+        assert_eq!(data(&clipped), data(&grads()));
+    }
+    let zeros = vec![Tensor::zeros(&[3])];
+    let (clipped, norm) = clip_grad_norm(&zeros, 1.0);
+    assert_eq!((data(&clipped), norm), (vec![vec![0.0; 3]], 0.0));
+}
+
+#[test]
+fn clip_grad_value_clamps_each_element(){
+    let g = vec![t(&[-3.0, -0.5, 0.0, 0.7, 2.0, -1.0], &[2, 3]), t(&[10.0], &[1])];
+    let clipped = clip_grad_value(&g, 1.0);
+    assert_eq!(data(&clipped), vec![vec![-1.0, -0.5, 0.0, 0.7, 1.0, -1.0], vec![1.0]]);
+    assert_eq!(clipped[0].shape, vec![2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "clip doit etre >= 0")]
+fn clip_grad_value_rejects_negative(){
+    clip_grad_value(&grads(), -1.0);
+}
+
+#[test]
+fn accumulator_averages_every_n(){
+    let mut acc = GradAccumulator::new(3);
+    let micro = |k: f32| vec![t(&[k, 2.0*k], &[2]), t(&[-k], &[1])];
+
+    assert!(acc.push(&micro(1.0)).is_none());
+    assert!(acc.push(&micro(2.0)).is_none());
+    assert_eq!(acc.count(), 2);
+    let g = acc.push(&micro(6.0)).unwrap();
+    // (1 + 2 + 6)/3
+    assert_eq!(data(&g), vec![vec![3.0, 6.0], vec![-3.0]]);
+
+    // remis à zéro: le cycle suivant ne voit pas les anciens micro-batches
+    assert_eq!(acc.count(), 0);
+    assert!(acc.flush().is_none());
+    assert!(acc.push(&micro(4.0)).is_none());
+    assert!(acc.push(&micro(2.0)).is_none());
+    // reste de fin d'epoch: moyenne sur ce qui a été vu
+    let g = acc.flush().unwrap();
+    assert_eq!(data(&g), vec![vec![3.0, 6.0], vec![-3.0]]);
+    assert_eq!(acc.count(), 0);
+    assert!(acc.flush().is_none());
+
+    let g = [acc.push(&micro(5.0)), acc.push(&micro(5.0)), acc.push(&micro(5.0))];
+    assert!(g[0].is_none() && g[1].is_none());
+    assert_eq!(data(g[2].as_ref().unwrap()), vec![vec![5.0, 10.0], vec![-5.0]]);
+}
+
+#[test]
+fn accumulator_does_not_touch_callers_grads(){
+    let mut acc = GradAccumulator::new(2);
+    let first = grads();
+    acc.push(&first);
+    acc.push(&grads());
+    assert_eq!(data(&first), data(&grads()));
+}
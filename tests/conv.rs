@@ -0,0 +1,39 @@
+// valeurs de conv2d calculées à la main (les gradients sont dans gradcheck.rs)
+use lamp::tensor::Tensor;
+use lamp::trace::Trace;
+use lamp::ops::{self, Conv2dOpts};
+
+fn conv(x: Tensor, w: Tensor, opts: Conv2dOpts) -> Tensor{
+    let mut tr = Trace::new();
+    let (x, w) = (tr.input(x), tr.input(w));
+    let y = ops::conv2d(&mut tr, x, w, opts);
+    tr.get_tensor(y).contiguous()
+}
+
+fn x_3x3() -> Tensor{
+    Tensor::from_owned((1..=9).map(|i| i as f32).collect(), &[1, 1, 3, 3]).unwrap()
+}
+
+#[test]
+fn conv2d_stride_padding(){
+    // x = [[1,2,3],[4,5,6],[7,8,9]] paddé de 1, noyau [[1,2],[3,4]], stride 2
+    let w = Tensor::from_vec(&[1., 2., 3., 4.], &[1, 1, 2, 2]).unwrap();
+    let y = conv(x_3x3(), w, Conv2dOpts::default().stride(2, 2).padding(1, 1));
+    assert_eq!(y.shape, vec![1, 1, 2, 2]);
+    assert_eq!(y.data.to_vec(), vec![4., 18., 36., 77.]);
+}
+
+#[test]
+fn conv2d_dilation_channels(){
+    // dilation 2: le noyau 2x2 prend les 4 coins; 2 canaux de sortie, le second de signe opposé
+    let w = Tensor::from_vec(&[1., 2., 3., 4., -1., -2., -3., -4.], &[2, 1, 2, 2]).unwrap();
+    let y = conv(x_3x3(), w, Conv2dOpts::default().dilation(2, 2));
+    assert_eq!(y.shape, vec![1, 2, 1, 1]);
+    assert_eq!(y.data.to_vec(), vec![64., -64.]);
+}
+
+#[test]
+#[should_panic(expected = "noyau de taille 0")]
+fn conv_out_dim_empty_kernel(){
+    ops::conv_out_dim(5, 0, 1, 0, 1);
+}
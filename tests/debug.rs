@@ -0,0 +1,68 @@
+// to_dot et summary: le chemin de order(root) est mis en avant, le reste grisé
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::ops;
+use lamp::nn::functions;
+
+// x, w -> matmul -> relu -> sum_all (root), plus une branche scale(x) hors du chemin
+fn build(tr: &mut Trace) -> (NodeId, NodeId){
+    let x = tr.input(Tensor::from_owned(vec![1.0, -2.0, 0.5, 3.0], &[2, 2]).unwrap());
+    let w = tr.param(Tensor::from_owned(vec![0.5, 1.0, -1.0, 2.0], &[2, 2]).unwrap());
+    let y = ops::matmul(tr, x, w);
+    let y = functions::relu(tr, y);
+    let side = ops::scale(tr, x, 3.0);
+    let root = ops::sum_all(tr, y);
+    (root, side)
+}
+
+fn dot_line(dot: &str, id: NodeId) -> &str{
+    dot.lines().find(|l| l.trim_start().starts_with(&format!("n{id} ["))).unwrap()
+}
+
+#[test]
+fn to_dot_styles_root_and_path(){
+    let mut tr = Trace::new();
+    let (root, side) = build(&mut tr);
+    let dot = tr.to_dot(Some(root));
+    assert!(dot.starts_with("digraph trace {") && dot.trim_end().ends_with('}'));
+
+    let root_line = dot_line(&dot, root);
+    assert!(root_line.contains("SumAll") && root_line.contains("color=\"red\""), "{root_line}");
+    for id in tr.order(root){
+        if id != root{
+            let l = dot_line(&dot, id);
+            assert!(l.contains("style=\"filled,bold\"") && !l.contains("dashed"), "{l}");
+        }
+    }
+    let side_line = dot_line(&dot, side);
+    assert!(side_line.contains("Scale(3)") && side_line.contains("dashed"), "{side_line}");
+    assert!(dot_line(&dot, 1).contains("lightblue"));
+    assert!(dot_line(&dot, 0).contains("lightgrey"));
+
+    // arêtes: celles du chemin pleines, celle vers la branche grisée
+    assert!(dot.contains("    n2 -> n3;\n"));
+    assert!(dot.contains(&format!("    n0 -> n{side} [color=\"grey70\", style=dashed];\n")));
+
+    // sans root, rien n'est grisé
+    assert!(!tr.to_dot(None).contains("dashed"));
+}
+
+#[test]
+fn summary_rows_and_totals(){
+    let mut tr = Trace::new();
+    build(&mut tr);
+    let s = tr.summary();
+    let lines: Vec<&str> = s.lines().collect();
+    assert_eq!(lines.len(), tr.len() + 2);
+    assert!(lines[0].starts_with("id") && lines[0].ends_with("dérivées"));
+    let matmul: Vec<&str> = lines[3].split_whitespace().collect();
+    assert_eq!(matmul, ["2", "MatMul", "[2,", "2]", "[0,", "1]", "vjp", "traced", "jvp"]);
+    assert_eq!(lines.last().unwrap(), &"6 noeuds, 1 params (4 scalaires), 1 entrées, 21 scalaires au total, grad activé");
+
+    // en no_grad les noeuds n'ont plus de règles
+    let mut ng = Trace::no_grad();
+    build(&mut ng);
+    let s = ng.summary();
+    assert!(s.lines().nth(3).unwrap().ends_with("aucune"));
+    assert!(s.trim_end().ends_with("grad désactivé"));
+}
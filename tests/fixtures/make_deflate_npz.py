@@ -0,0 +1,33 @@
+# génère deflate_blocks.npz (lu par tests/npy.rs), sans numpy:
+# trois .npy compressés en deflate, chacun avec un type de bloc différent (stocké, huffman fixe, huffman dynamique)
+import struct, zlib
+
+def npy(descr, fortran, shape, payload):
+    shape_s = "()" if not shape else "(%d,)" % shape[0] if len(shape) == 1 else "(" + ", ".join(map(str, shape)) + ")"
+    h = "{'descr': '%s', 'fortran_order': %s, 'shape': %s, }" % (descr, fortran, shape_s)
+    h += " " * ((64 - (10 + len(h) + 1) % 64) % 64) + "\n"
+    return b"\x93NUMPY\x01\x00" + struct.pack("<H", len(h)) + h.encode() + payload
+
+def deflate(data, level, strategy, btype):
+    c = zlib.compressobj(level, zlib.DEFLATED, -15, 9, strategy)
+    raw = c.compress(data) + c.flush()
+    assert (raw[0] >> 1) & 3 == btype, (raw[0] >> 1) & 3
+    return raw
+
+r, c = 16, 24
+members = [
+    ("stored.npy", npy("<f8", False, (2, 3), struct.pack("<6d", 0.5, -1.0, 2.0, 3.25, 1e10, -0.0)), 0, zlib.Z_DEFAULT_STRATEGY, 0),
+    ("fixed.npy", npy("<i8", False, (4,), struct.pack("<4q", -3, 0, 7, 2**40)), 6, zlib.Z_FIXED, 1),
+    # ordre fortran: (i, j) vaut i*c + j, écrit colonne par colonne
+    ("dynamic.npy", npy("<f4", True, (r, c), b"".join(struct.pack("<f", i*c + j) for j in range(c) for i in range(r))), 9, zlib.Z_DEFAULT_STRATEGY, 2),
+]
+
+out, central = b"", b""
+for name, data, level, strategy, btype in members:
+    comp = deflate(data, level, strategy, btype)
+    crc, name_b = zlib.crc32(data), name.encode()
+    fields = struct.pack("<HHHHHIII", 20, 0, 8, 0, 33, crc, len(comp), len(data))
+    central += b"PK\x01\x02" + struct.pack("<H", 20) + fields + struct.pack("<HHHHHII", len(name_b), 0, 0, 0, 0, 0, len(out)) + name_b
+    out += b"PK\x03\x04" + fields + struct.pack("<HH", len(name_b), 0) + name_b + comp
+eocd = b"PK\x05\x06" + struct.pack("<HHHHIIH", 0, 0, len(members), len(members), len(central), len(out), 0)
+open(__file__.replace("make_deflate_npz.py", "deflate_blocks.npz"), "wb").write(out + central + eocd)
@@ -0,0 +1,71 @@
+// tensor_mul (gemm par blocs) contre la triple boucle, sur des shapes plus grandes que les blocs KC/MC/NC.
+// entrées entières petites: produits et sommes sont exacts en f32, on peut comparer bit à bit
+use lamp::tensor::Tensor;
+use lamp::utils::parallel::set_num_threads;
+use lamp::ops::tensor_mul;
+
+fn int_t(shape: &[usize], seed: usize) -> Tensor{
+    let n = shape.iter().product();
+    Tensor::from_owned((0..n).map(|i| ((i * 7919 + seed * 104729) % 9) as f32 - 4.0).collect(), shape).unwrap()
+}
+
+// a: (.., m, p), b: (.., p, n), batch broadcasté
+fn naive(a: &Tensor, b: &Tensor) -> Tensor{
+    let (ra, rb) = (a.shape.len(), b.shape.len());
+    let (m, p, n) = (a.shape[ra-2], a.shape[ra-1], b.shape[rb-1]);
+    let batch = Tensor::broadcast_shape(&a.shape[..ra-2], &b.shape[..rb-2]).unwrap();
+    let a = a.broadcast_view(&[batch.clone(), vec![m, p]].concat()).unwrap().contiguous();
+    let b = b.broadcast_view(&[batch.clone(), vec![p, n]].concat()).unwrap().contiguous();
+    let nb: usize = batch.iter().product();
+    let mut c = Vec::with_capacity(nb*m*n);
+    for l in 0..nb{
+        for i in 0..m{
+            for j in 0..n{
+                let mut sum = 0.0;
+                for k in 0..p{
+                    sum += a.data[l*m*p + i*p + k] * b.data[l*p*n + k*n + j];
+                }
+                c.push(sum);
+            }
+        }
+    }
+    Tensor::from_owned(c, &[batch, vec![m, n]].concat()).unwrap()
+}
+
+fn check(a: &Tensor, b: &Tensor){
+    let got = tensor_mul(a, b);
+    let expected = naive(a, b);
+    assert_eq!(got.shape, expected.shape);
+    assert!(got.data == expected.data, "{:?} @ {:?}: différent de la triple boucle", a.shape, b.shape);
+}
+
+#[test]
+fn larger_than_blocks_threaded_row_split(){
+    // 4 threads, meme sur une machine à 1 coeur
+    set_num_threads(4);
+    // m > MC, p > KC, n > NC, aucun multiple de MR/NR; un seul batch < threads => découpage en lignes
+    check(&int_t(&[150, 270], 0), &int_t(&[270, 1030], 1));
+    // blocs de lignes plus grands que MC
+    check(&int_t(&[300, 260], 2), &int_t(&[260, 40], 3));
+}
+
+#[test]
+fn transposed_views(){
+    set_num_threads(4);
+    // x^T g et g w^T comme dans les vjp: pas de copie, seulement des strides échangés
+    check(&int_t(&[270, 130], 4).mat_transpose(), &int_t(&[270, 70], 5));
+    check(&int_t(&[90, 300], 6), &int_t(&[1030, 300], 7).mat_transpose());
+    check(&int_t(&[300, 70], 8).mat_transpose(), &int_t(&[90, 300], 9).mat_transpose());
+}
+
+#[test]
+fn batched_and_broadcast(){
+    set_num_threads(4);
+    // nb = 2 < threads: chaque matrice est coupée en blocs de lignes
+    check(&int_t(&[2, 100, 300], 10), &int_t(&[300, 80], 11));
+    // nb >= threads: une matrice par morceau
+    check(&int_t(&[5, 70, 64], 12), &int_t(&[1, 64, 90], 13));
+    check(&int_t(&[3, 1, 9, 20], 14), &int_t(&[4, 20, 7], 15).mat_transpose().mat_transpose());
+    // sous PAR_MIN_FLOPS: séquentiel
+    check(&int_t(&[3, 5], 16), &int_t(&[5, 7], 17));
+}
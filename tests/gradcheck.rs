@@ -0,0 +1,389 @@
+// chaque op dérivable de ops, nn::functions et nn::losses passe par gradcheck.
+// pour une nouvelle op: ajouter un test ici avec des entrées loin de ses points non dérivables.
+use std::sync::Arc;
+
+use rand::Rng;
+
+use lamp::autodiff::gradcheck;
+use lamp::autodiff::value_and_grad::value_and_grad;
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::utils::rng::SplitMix64;
+use lamp::ops::{self, Conv2dOpts};
+use lamp::nn::{functions, losses};
+use lamp::nn::module::Module;
+use lamp::nn::layers::sequential::Sequential;
+use lamp::nn::layers::linear::Linear;
+use lamp::nn::layers::activations::Tanh;
+
+const EPS: f32 = 1e-2;
+const TOL: f32 = 1e-2;
+
+fn rand_t(shape: &[usize], seed: u64) -> Tensor{
+    let mut rng = SplitMix64::seed(seed);
+    let n = shape.iter().product();
+    Tensor::from_owned((0..n).map(|_| rng.gen_range(-1.0..1.0)).collect(), shape).unwrap()
+}
+
+// valeurs écartées de 0.1 deux à deux, mélangées: pas d'égalité dans les max pooling, pas de 0 pour relu
+fn distinct_t(shape: &[usize], seed: u64) -> Tensor{
+    let mut rng = SplitMix64::seed(seed);
+    let n: usize = shape.iter().product();
+    let mut v: Vec<f32> = (0..n).map(|i| (i as f32 - n as f32 / 2.0) * 0.1 + 0.05).collect();
+    for i in (1..n).rev(){
+        v.swap(i, rng.gen_range(0..=i));
+    }
+    Tensor::from_owned(v, shape).unwrap()
+}
+
+fn check(params: Vec<Tensor>, build: impl FnMut(&mut Trace, &[NodeId]) -> NodeId){
+    let report = gradcheck(&params, build, EPS, TOL);
+    assert!(report.ok(), "gradcheck:\n{report}");
+}
+
+// ---------- ops::elementwise
+
+#[test]
+fn hadamard_mul_broadcast(){
+    check(vec![rand_t(&[2, 3], 1), rand_t(&[3], 2)], |tr, p| ops::hadamard_mul(tr, p[0], p[1]));
+}
+
+#[test]
+fn add_broadcast(){
+    check(vec![rand_t(&[2, 3, 4], 1), rand_t(&[3, 1], 2)], |tr, p| ops::add(tr, p[0], p[1]));
+}
+
+#[test]
+fn sub_broadcast(){
+    check(vec![rand_t(&[4], 1), rand_t(&[2, 3, 4], 2)], |tr, p| ops::sub(tr, p[0], p[1]));
+}
+
+#[test]
+fn scale(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| ops::scale(tr, p[0], -1.7));
+}
+
+// pas de différences finies ici: justement, le gradient doit ignorer la dépendance qu'elles verraient
+#[test]
+fn stop_gradient(){
+    let x = rand_t(&[5], 1);
+    let (_, grads) = value_and_grad(std::slice::from_ref(&x), |tr, p| {
+        let s = ops::stop_gradient(tr, p[0]);
+        let y = ops::hadamard_mul(tr, p[0], s);
+        ops::sum_all(tr, y)
+    });
+    // d/dx sum(x * sg(x)) = sg(x) = x
+    assert_eq!(grads[0].contiguous().data.to_vec(), x.data.to_vec());
+}
+
+// ---------- ops::linalg
+
+#[test]
+fn matmul_2d(){
+    check(vec![rand_t(&[3, 4], 1), rand_t(&[4, 2], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+}
+
+#[test]
+fn matmul_batched(){
+    check(vec![rand_t(&[2, 3, 4], 1), rand_t(&[4, 2], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+    check(vec![rand_t(&[3, 4], 1), rand_t(&[2, 4, 2], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+}
+
+#[test]
+fn matmul_vectors(){
+    check(vec![rand_t(&[4], 1), rand_t(&[4, 2], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+    check(vec![rand_t(&[3, 4], 1), rand_t(&[4], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+    check(vec![rand_t(&[4], 1), rand_t(&[4], 2)], |tr, p| ops::matmul(tr, p[0], p[1]));
+}
+
+// ---------- ops::shapes
+
+#[test]
+fn mean_all(){
+    let x = rand_t(&[2, 3], 1);
+    let mut tr = Trace::new();
+    let id = tr.input(x.clone());
+    let m = ops::mean_all(&mut tr, id);
+    assert!((tr.get_tensor(m).data[0] - x.sum_all().data[0] / 6.0).abs() < 1e-6, "mean_all doit renvoyer la moyenne");
+
+    check(vec![x], |tr, p| ops::mean_all(tr, p[0]));
+}
+
+#[test]
+fn sum_all(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| ops::sum_all(tr, p[0]));
+}
+
+#[test]
+fn sum_last(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::sum_last(tr, p[0]));
+}
+
+#[test]
+fn sum_to_shape(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::sum_to_shape(tr, p[0], &[3, 1]));
+}
+
+#[test]
+fn broadcast_to(){
+    check(vec![rand_t(&[3, 1], 1)], |tr, p| ops::broadcast_to(tr, p[0], &[2, 3, 4]));
+}
+
+#[test]
+fn mat_transpose(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::mat_transpose(tr, p[0]));
+}
+
+#[test]
+fn unsqueeze_squeeze(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| ops::unsqueeze(tr, p[0], 1));
+    check(vec![rand_t(&[2, 1, 3], 1)], |tr, p| ops::squeeze(tr, p[0], 1));
+}
+
+#[test]
+fn permute_transpose(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::permute(tr, p[0], &[2, 0, 1]));
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::transpose(tr, p[0], 0, 2));
+}
+
+#[test]
+fn reshape_view_flatten(){
+    // reshape d'une vue transposée: copie obligatoire
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| {
+        let t = ops::mat_transpose(tr, p[0]);
+        ops::reshape(tr, t, &[4, 6])
+    });
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::view(tr, p[0], &[6, 2, 2]));
+    check(vec![rand_t(&[2, 3, 4, 2], 1)], |tr, p| ops::flatten(tr, p[0], 1, 2));
+}
+
+#[test]
+fn expand(){
+    check(vec![rand_t(&[3, 1], 1)], |tr, p| ops::expand(tr, p[0], &[2, 3, 4]));
+}
+
+#[test]
+fn view_strides(){
+    let x = rand_t(&[2, 3, 4], 1);
+    // contigu, ou découpage d'un axe d'une vue permutée: pas de copie
+    assert!(Arc::ptr_eq(&x.view(&[6, 4]).unwrap().data, &x.data));
+    let t = x.permute(&[2, 0, 1]);
+    let v = t.view(&[2, 2, 6]).unwrap();
+    assert!(Arc::ptr_eq(&v.data, &x.data));
+    assert_eq!(v.contiguous().data.to_vec(), t.contiguous().data.to_vec());
+    // fusionner des axes non contigus: view refuse, reshape copie
+    assert!(t.view(&[24]).is_err());
+    assert_eq!(t.reshape(&[24]).data.to_vec(), t.contiguous().data.to_vec());
+    assert_eq!(x.flatten_last_nb(2).shape, vec![2, 12]);
+}
+
+// ---------- ops::reduce
+
+#[test]
+fn sum_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::sum(tr, p[0], &[0, 2], false));
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::sum(tr, p[0], &[1], true));
+}
+
+#[test]
+fn mean_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::mean(tr, p[0], &[1, 2], false));
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::mean(tr, p[0], &[], true));
+}
+
+#[test]
+fn max_min_axes(){
+    check(vec![distinct_t(&[2, 3, 4], 1)], |tr, p| ops::max(tr, p[0], &[2], false));
+    check(vec![distinct_t(&[2, 3, 4], 1)], |tr, p| ops::min(tr, p[0], &[0, 1], true));
+}
+
+#[test]
+fn prod_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::prod(tr, p[0], &[1], false));
+    // un zéro dans le bloc: le gradient ne passe pas par prod/x
+    let x = Tensor::from_vec(&[0.5, 0.0, -1.5, 2.0, 0.7, -0.3], &[2, 3]).unwrap();
+    check(vec![x], |tr, p| ops::prod(tr, p[0], &[1], false));
+}
+
+#[test]
+fn var_std_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::var(tr, p[0], &[2], false));
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::std(tr, p[0], &[0, 2], true));
+}
+
+#[test]
+fn logsumexp_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::logsumexp(tr, p[0], &[1], true));
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::logsumexp(tr, p[0], &[], false));
+}
+
+#[test]
+fn norm_axes(){
+    check(vec![rand_t(&[2, 3, 4], 1)], |tr, p| ops::norm(tr, p[0], &[0, 1], false));
+}
+
+// indices: pas de gradient, le backward s'arrete sur argmax
+#[test]
+fn argmax_argmin(){
+    let x = Tensor::from_vec(&[0.1, 0.9, 0.3, 0.7, 0.2, 0.5], &[2, 3]).unwrap();
+    let mut tr = Trace::new();
+    let id = tr.param(x);
+    let am = ops::argmax(&mut tr, id, &[1], false);
+    let an = ops::argmin(&mut tr, id, &[], false);
+    assert_eq!(tr.get_tensor(am).data.to_vec(), vec![1.0, 0.0]);
+    assert_eq!(tr.get_tensor(an).data.to_vec(), vec![0.0]);
+    let s = ops::sum_all(&mut tr, am);
+    assert_eq!(tr.backward(s, &[id])[0].data.to_vec(), vec![0.0; 6]);
+}
+
+// ---------- ops::conv
+
+#[test]
+fn conv2d(){
+    let opts = Conv2dOpts::default().stride(2, 1).padding(1, 2).dilation(1, 2);
+    check(vec![rand_t(&[2, 2, 5, 6], 1), rand_t(&[3, 2, 3, 2], 2)], move |tr, p| ops::conv2d(tr, p[0], p[1], opts));
+}
+
+#[test]
+fn conv2d_input_grad(){
+    let opts = Conv2dOpts::default().stride(2, 2).padding(1, 1);
+    let x_shape = [1, 2, 5, 5];
+    let out_shape = ops::conv2d_out_shape(&x_shape, &[3, 2, 3, 3], &opts);
+    check(vec![rand_t(&out_shape, 1), rand_t(&[3, 2, 3, 3], 2)], move |tr, p| ops::conv2d_input_grad(tr, p[0], p[1], &x_shape, opts));
+}
+
+#[test]
+fn conv2d_kernel_grad(){
+    let opts = Conv2dOpts::default().padding(1, 0);
+    let w_shape = [3, 2, 2, 3];
+    let out_shape = ops::conv2d_out_shape(&[1, 2, 4, 5], &w_shape, &opts);
+    check(vec![rand_t(&[1, 2, 4, 5], 1), rand_t(&out_shape, 2)], move |tr, p| ops::conv2d_kernel_grad(tr, p[0], p[1], &w_shape, opts));
+}
+
+// ---------- ops::pool
+
+#[test]
+fn max_pool2d(){
+    check(vec![distinct_t(&[2, 2, 5, 5], 1)], |tr, p| ops::max_pool2d(tr, p[0], (3, 3), (2, 2), (1, 1)));
+}
+
+#[test]
+fn avg_pool2d(){
+    check(vec![rand_t(&[2, 2, 5, 5], 1)], |tr, p| ops::avg_pool2d(tr, p[0], (3, 2), (2, 1), (1, 1)));
+}
+
+#[test]
+fn adaptive_avg_pool2d(){
+    check(vec![rand_t(&[1, 2, 5, 7], 1)], |tr, p| ops::adaptive_avg_pool2d(tr, p[0], (3, 2)));
+}
+
+#[test]
+fn global_avg_pool2d(){
+    check(vec![rand_t(&[2, 3, 4, 5], 1)], |tr, p| ops::global_avg_pool2d(tr, p[0]));
+}
+
+// ---------- nn::functions
+
+#[test]
+fn apply(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| functions::apply(tr, p[0], |x| x.sin(), |x| x.cos()));
+}
+
+#[test]
+fn apply_twice(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| functions::apply_twice(tr, p[0], |x| x*x*x, |x| 3.0*x*x, |x| 6.0*x));
+}
+
+#[test]
+fn tanh(){
+    check(vec![rand_t(&[2, 3], 1)], |tr, p| functions::tanh(tr, p[0]));
+}
+
+#[test]
+fn relu(){
+    check(vec![distinct_t(&[4, 5], 1)], |tr, p| functions::relu(tr, p[0]));
+}
+
+#[test]
+fn softmax(){
+    check(vec![rand_t(&[3, 4], 1)], |tr, p| functions::softmax(tr, p[0]));
+}
+
+// ---------- nn::losses
+
+#[test]
+fn mse(){
+    check(vec![rand_t(&[3, 4], 1), rand_t(&[3, 4], 2)], |tr, p| losses::mse(tr, p[0], p[1]));
+}
+
+#[test]
+fn softmax_crossentropy(){
+    // la cible n'est pas dérivée: on la passe en entrée
+    let y = Tensor::from_vec(&[0., 1., 0., 0., 0., 0., 0., 1., 0.5, 0.5, 0., 0.], &[3, 4]).unwrap();
+    check(vec![rand_t(&[3, 4], 1)], move |tr, p| {
+        let y = tr.input(y.clone());
+        losses::softmax_crossentropy(tr, p[0], y)
+    });
+}
+
+#[test]
+fn l2_reg(){
+    check(vec![rand_t(&[3, 4], 1), rand_t(&[2], 2)], |tr, p| losses::l2_reg(tr, 0.3, p));
+}
+
+// ---------- modèle utilisateur, params nommés
+
+#[test]
+fn sequential_mlp(){
+    let mut model = Sequential::new().layer(Linear::new(4, 5)).layer(Tanh).layer(Linear::new(5, 3));
+    let params = model.init_tree();
+    let x = rand_t(&[6, 4], 1);
+    let y = Tensor::from_owned((0..18).map(|i| if i % 3 == i / 6 {1.0} else {0.0}).collect(), &[6, 3]).unwrap();
+    let report = gradcheck(&params, |tr, pids| {
+        let (x, y) = (tr.input(x.clone()), tr.input(y.clone()));
+        model.bind_params(pids);
+        let logits = model.forward(tr, x);
+        losses::softmax_crossentropy(tr, logits, y)
+    }, EPS, TOL);
+    assert_eq!(report.params.len(), params.len());
+    assert_eq!(report.params.iter().map(|p| p.name.clone()).collect::<Vec<_>>(), params.paths());
+    assert!(report.ok(), "gradcheck:\n{report}");
+}
+
+// ---------- gradcheck lui meme: une dérivée fausse doit etre vue
+
+#[test]
+fn wrong_derivative_fails(){
+    let params = vec![rand_t(&[3, 4], 1), rand_t(&[4], 2)];
+    let report = gradcheck(&params, |tr, p| {
+        // d/dx sin = cos, pas 2 cos: seul le premier param est faux
+        let s = functions::apply(tr, p[0], |v| v.sin(), |v| 2.0*v.cos());
+        ops::add(tr, s, p[1])
+    }, EPS, TOL);
+    assert!(!report.ok());
+    let (bad, good) = (&report.params[0], &report.params[1]);
+    assert!(!bad.ok && bad.max_abs > 0.5 && bad.max_rel > 0.4, "{report}");
+    assert!(good.ok, "{report}");
+    assert!(report.to_string().contains("param 0: ") && report.to_string().contains("ECHEC"));
+}
+
+#[test]
+fn wrong_derivative_names_the_leaf(){
+    let mut model = Sequential::new().layer(Linear::new(3, 2));
+    let params = model.init_tree();
+    let x = rand_t(&[4, 3], 3);
+    let report = gradcheck(&params, |tr, pids| {
+        let x = tr.input(x.clone());
+        model.bind_params(pids);
+        let y = model.forward(tr, x);
+        let y = functions::apply(tr, y, |v| v*v, |v| 3.0*v);
+        ops::sum_all(tr, y)
+    }, EPS, TOL);
+    assert!(!report.ok());
+    let paths = params.paths();
+    for (p, path) in report.params.iter().zip(&paths){
+        assert_eq!(&p.name, path);
+        assert!(!p.ok, "{report}");
+        assert!(report.to_string().contains(&format!("param {path}: ")));
+    }
+}
@@ -0,0 +1,88 @@
+// dérivées d'ordre 2 (backward_traced) contre des différences finies sur les gradients
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::ops;
+use lamp::nn::functions;
+use lamp::autodiff::value_and_grad::value_and_grad;
+use lamp::autodiff::higher_order::{grad, hvp};
+
+// sum(softmax(x) * w), w fixe
+fn softmax_loss(tr: &mut Trace, p: &[NodeId]) -> NodeId{
+    let w = tr.input(Tensor::from_vec(&[0.3, -1.2, 0.7, 2.0, 0.1, -0.4], &[2, 3]).unwrap());
+    let s = functions::softmax(tr, p[0]);
+    let sw = ops::hadamard_mul(tr, s, w);
+    ops::sum_all(tr, sw)
+}
+
+#[test]
+fn softmax_hvp(){
+    let x = Tensor::from_vec(&[0.5, -0.2, 1.0, 0.0, 0.3, -0.8], &[2, 3]).unwrap();
+    let v = Tensor::from_vec(&[1.0, 0.5, -1.0, 0.2, -0.3, 0.8], &[2, 3]).unwrap();
+    let (_, hv) = hvp(std::slice::from_ref(&x), std::slice::from_ref(&v), softmax_loss);
+
+    let eps = 1e-2f32;
+    let g_at = |sign: f32| {
+        let xs = Tensor::from_owned(x.data.iter().zip(v.data.iter()).map(|(a, b)| a + sign*eps*b).collect(), &[2, 3]).unwrap();
+        value_and_grad(&vec![xs], softmax_loss).1[0].contiguous()
+    };
+    let (gp, gm) = (g_at(1.0), g_at(-1.0));
+    let hv = hv[0].contiguous();
+    for k in 0..6{
+        let fd = (gp.data[k] - gm.data[k])/(2.0*eps);
+        assert!((hv.data[k] - fd).abs() < 1e-3, "H.v[{k}] = {} != {fd}", hv.data[k]);
+    }
+}
+
+#[test]
+#[should_panic(expected = "pas de vjp tracée")]
+fn apply_without_second_derivative(){
+    let mut tr = Trace::new();
+    let x = tr.param(Tensor::ones(&[3]));
+    let y = functions::apply(&mut tr, x, |x| x.sin(), |x| x.cos());
+    let s = ops::sum_all(&mut tr, y);
+    grad(&mut tr, s, &[x]);
+}
+
+// hessienne de prod, colonne par colonne avec hvp: H_ij = produit des x_k pour k != i, j
+fn prod_hessian(x: &Tensor) -> Vec<Vec<f32>>{
+    let n = x.data.len();
+    (0..n).map(|j| {
+        let e = Tensor::from_owned((0..n).map(|k| if k == j {1.0} else {0.0}).collect(), &x.shape).unwrap();
+        let (_, hv) = hvp(std::slice::from_ref(x), std::slice::from_ref(&e), |tr, p| ops::prod(tr, p[0], &[], false));
+        hv[0].contiguous().data.to_vec()
+    }).collect()
+}
+
+#[test]
+fn prod_second_order_with_zero(){
+    let x = Tensor::from_vec(&[2.0, 0.0, 3.0, -1.0], &[4]).unwrap();
+    let h = prod_hessian(&x);
+    for (j, col) in h.iter().enumerate(){
+        for (i, &hij) in col.iter().enumerate(){
+            let expected: f32 = if i == j {0.0} else {(0..4).filter(|&k| k != i && k != j).map(|k| x.data[k]).product()};
+            assert_eq!(hij, expected, "H[{i}][{j}]");
+        }
+    }
+}
+
+#[test]
+fn traced_grads_finite_at_zero(){
+    // prod: un bloc avec deux zéros, un avec un seul. norm: un bloc de norme nulle
+    let x = Tensor::from_vec(&[0.0, 2.0, 0.0, 1.5, 0.0, -2.0, 0.0, 0.0, 0.0, 3.0, 4.0, 0.0], &[4, 3]).unwrap();
+    let build = |tr: &mut Trace, p: &[NodeId]| {
+        let pr = ops::prod(tr, p[0], &[1], false);
+        let pr = ops::sum_all(tr, pr);
+        let n = ops::norm(tr, p[0], &[1], false);
+        let n = ops::sum_all(tr, n);
+        ops::add(tr, pr, n)
+    };
+    let (_, eager) = value_and_grad(&vec![x.clone()], build);
+
+    let mut tr = Trace::new();
+    let xid = tr.param(x);
+    let out = build(&mut tr, &[xid]);
+    let g = grad(&mut tr, out, &[xid])[0];
+    let traced = tr.get_tensor(g).contiguous();
+    assert!(traced.data.iter().all(|v| v.is_finite()), "{:?}", traced.data);
+    assert_eq!(traced.data, eager[0].contiguous().data);
+}
@@ -0,0 +1,44 @@
+// d(loss)/d(input) contre la forme fermée: loss = sum((W·x)²) -> d/dx = 2 Wᵀ W x
+use lamp::tensor::Tensor;
+use lamp::ops;
+use lamp::autodiff::input_grad::value_and_input_grad;
+
+#[test]
+fn input_grad_closed_form(){
+    let w = Tensor::from_vec(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]).unwrap();
+    let x = Tensor::from_vec(&[0.5, -1.0, 2.0], &[3, 1]).unwrap();
+    let (loss, gx) = value_and_input_grad(std::slice::from_ref(&w), std::slice::from_ref(&x), |tr, pids, xids| {
+        // W est bien un param, x un simple input
+        assert_eq!(tr.params_id(), pids);
+        let y = ops::matmul(tr, pids[0], xids[0]);
+        let y2 = ops::hadamard_mul(tr, y, y);
+        ops::sum_all(tr, y2)
+    });
+
+    // W·x = [4.5, 9.0]
+    assert_eq!(loss.data[0], 4.5*4.5 + 9.0*9.0);
+    // un gradient par input, aucun pour W
+    assert_eq!(gx.len(), 1);
+    assert_eq!(gx[0].shape, x.shape);
+    // 2 Wᵀ [4.5, 9.0]
+    let want = [2.0*(4.5 + 4.0*9.0), 2.0*(2.0*4.5 + 5.0*9.0), 2.0*(3.0*4.5 + 6.0*9.0)];
+    assert_eq!(*gx[0].contiguous().data, want.to_vec());
+}
+
+#[test]
+fn params_get_no_gradient(){
+    // la loss ne dépend que de W: le gradient de l'input est nul, et W n'a pas bougé
+    let w = Tensor::from_vec(&[1.0, -2.0], &[2]).unwrap();
+    let x = Tensor::from_vec(&[3.0, 4.0], &[2]).unwrap();
+    let (loss, gx) = value_and_input_grad(std::slice::from_ref(&w), &[x.clone(), x], |tr, pids, _| {
+        let w2 = ops::hadamard_mul(tr, pids[0], pids[0]);
+        ops::sum_all(tr, w2)
+    });
+    assert_eq!(loss.data[0], 5.0);
+    assert_eq!(gx.len(), 2);
+    for g in &gx{
+        assert_eq!(g.shape, vec![2]);
+        assert!(g.contiguous().data.iter().all(|&v| v == 0.0), "{:?}", g.data);
+    }
+    assert_eq!(*w.data, vec![1.0, -2.0]);
+}
@@ -0,0 +1,28 @@
+// jacobienne de x -> W·x: par rapport à x c'est W, par rapport à W c'est x placé sur la ligne de la sortie
+use lamp::tensor::Tensor;
+use lamp::ops;
+use lamp::autodiff::jacobian::jacobian;
+
+#[test]
+fn linear_map_jacobian(){
+    let w = Tensor::from_vec(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], &[2, 3]).unwrap();
+    let x = Tensor::from_vec(&[0.5, -1.0, 2.0], &[3, 1]).unwrap();
+    let (out, jac) = jacobian(&[w.clone(), x.clone()], |tr, p| ops::matmul(tr, p[0], p[1]));
+
+    assert_eq!(*out.contiguous().data, vec![4.5, 9.0]);
+    // [out.., param..]
+    assert_eq!(jac[0].shape, vec![2, 1, 2, 3]);
+    assert_eq!(jac[1].shape, vec![2, 1, 3, 1]);
+    assert_eq!(*jac[1].contiguous().data, *w.data);
+
+    // d out_i / d W_jk = [i == j] x_k
+    let jw = jac[0].contiguous();
+    for i in 0..2{
+        for j in 0..2{
+            for k in 0..3{
+                let want = if i == j { x.data[k] } else { 0.0 };
+                assert_eq!(jw.data[i*6 + j*3 + k], want, "i={i} j={j} k={k}");
+            }
+        }
+    }
+}
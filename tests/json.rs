@@ -0,0 +1,32 @@
+use lamp::io::json::Json;
+
+#[test]
+fn round_trip(){
+    let v = Json::Object(vec![
+        ("nom \"bizarre\"\\\n\t".into(), Json::Str("é ✓ 😀 \u{1}".into())),
+        ("nombres".into(), Json::Array(vec![Json::Number(0.0), Json::Number(-12.0), Json::Number(0.1), Json::Number(-3.5e-12), Json::Number(1e300)])),
+        ("vide".into(), Json::Object(vec![])),
+        ("b".into(), Json::Array(vec![Json::Bool(true), Json::Bool(false), Json::Null, Json::Array(vec![])])),
+    ]);
+    let s = v.dump();
+    assert_eq!(Json::parse(&s).unwrap(), v);
+    // les entiers s'écrivent sans ".0"
+    assert!(s.contains("[0,-12,"), "{s}");
+}
+
+#[test]
+fn parse_escapes(){
+    let v = Json::parse(r#"  {"a\/b": "é😀\n", "k": [1, 2.5e1 , -0] }  "#).unwrap();
+    assert_eq!(v.get("a/b").and_then(Json::as_str), Some("é😀\n"));
+    assert_eq!(v.get("k").and_then(Json::as_array).map(|a| a.len()), Some(3));
+    assert_eq!(v.get("k").unwrap().as_array().unwrap()[1].as_usize(), Some(25));
+    // surrogate haut seul: caractère de remplacement, pas d'erreur
+    assert_eq!(Json::parse(r#""\ud83dx""#).unwrap(), Json::Str("\u{fffd}x".into()));
+}
+
+#[test]
+fn parse_errors(){
+    for bad in ["", "{", "[1,]", r#"{"a" 1}"#, r#""abc"#, r#""\u12""#, r#""\q""#, "1 2", "nul", "--1"]{
+        assert!(Json::parse(bad).is_err(), "{bad:?} devrait etre refusé");
+    }
+}
@@ -0,0 +1,48 @@
+// mode direct: J.t contre le mode inverse, et erreur claire sur les noeuds sans règle jvp
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::ops;
+use lamp::nn::functions;
+use lamp::autodiff::jvp::jvp;
+use lamp::autodiff::value_and_grad::value_and_grad;
+
+fn f(tr: &mut Trace, p: &[NodeId]) -> NodeId{
+    let t = functions::tanh(tr, p[0]);
+    let m = ops::hadamard_mul(tr, t, p[1]);
+    ops::sum_all(tr, m)
+}
+
+#[test]
+fn jvp_matches_grad(){
+    let params = vec![Tensor::from_vec(&[0.1, -0.5, 0.9], &[3]).unwrap(), Tensor::from_vec(&[1.0, 2.0, -1.0], &[3]).unwrap()];
+    let tangents = vec![Tensor::from_vec(&[1.0, 0.0, -2.0], &[3]).unwrap(), Tensor::from_vec(&[0.5, 0.5, 0.5], &[3]).unwrap()];
+    let (y, jt) = jvp(&params, &tangents, f).unwrap();
+    let (y2, grads) = value_and_grad(&params, f);
+    assert_eq!(y.data.to_vec(), y2.data.to_vec());
+    let dot: f32 = grads.iter().zip(&tangents).map(|(g, t)| g.contiguous().data.iter().zip(t.data.iter()).map(|(a, b)| a*b).sum::<f32>()).sum();
+    assert!((jt.data[0] - dot).abs() < 1e-5, "{} != {dot}", jt.data[0]);
+}
+
+#[test]
+fn jvp_through_no_grad_node_is_an_error(){
+    let err = jvp(&[Tensor::ones(&[3])], &[Tensor::ones(&[3])], |tr, p| {
+        tr.set_grad_enabled(false);
+        let t = functions::tanh(tr, p[0]);
+        tr.set_grad_enabled(true);
+        ops::sum_all(tr, t)
+    }).unwrap_err();
+    assert!(err.contains("Tanh"), "{err}");
+}
+
+#[test]
+fn nodes_independent_of_seeds_have_no_tangent(){
+    let mut tr = Trace::no_grad();
+    let a = tr.input(Tensor::ones(&[2]));
+    let b = functions::tanh(&mut tr, a);
+    tr.set_grad_enabled(true);
+    let x = tr.param(Tensor::ones(&[2]));
+    let y = ops::add(&mut tr, x, b);
+    let t = tr.forward_tangents(&[(x, Tensor::ones(&[2]))]).unwrap();
+    assert!(t[b].is_none());
+    assert_eq!(t[y].as_ref().unwrap().contiguous().data.to_vec(), vec![1.0, 1.0]);
+}
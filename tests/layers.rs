@@ -0,0 +1,24 @@
+// l'api d'avant Module (Linear::bind / apply) donne le meme calcul que forward
+use lamp::tensor::Tensor;
+use lamp::trace::Trace;
+use lamp::nn::module::Module;
+use lamp::nn::layers::bind::ParamCursor;
+use lamp::nn::layers::linear::Linear;
+
+fn same_output(m: &mut dyn Module, legacy: impl Fn(&mut Trace, &[usize], usize) -> usize, x: Tensor){
+    let params = m.init();
+    let mut tr = Trace::new();
+    let pids: Vec<usize> = params.iter().map(|p| tr.param(p.clone())).collect();
+    let xid = tr.input(x);
+    m.bind_params(&pids);
+    let y = m.forward(&mut tr, xid);
+    let y_legacy = legacy(&mut tr, &pids, xid);
+    assert_eq!(tr.get_tensor(y).contiguous().data.to_vec(), tr.get_tensor(y_legacy).contiguous().data.to_vec());
+}
+
+#[test]
+fn linear_bind_apply(){
+    let x = Tensor::from_owned((0..12).map(|i| i as f32*0.1).collect(), &[3, 4]).unwrap();
+    same_output(&mut Linear::new(4, 2), |tr, pids, x| Linear::bind(&mut ParamCursor::new(pids)).apply(tr, x), x);
+}
+
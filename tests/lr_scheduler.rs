@@ -0,0 +1,113 @@
+// suites de lr attendues, pas après pas
+use lamp::optim::Optimizer;
+use lamp::optim::sgd::Sgd;
+use lamp::optim::lr_scheduler::{LrScheduler, StepLr, ExponentialLr, CosineAnnealingWarmRestarts, OneCycleLr, ReduceLrOnPlateau, LinearWarmup};
+
+fn run(sched: &mut dyn LrScheduler, opt: &mut Sgd, n: usize) -> Vec<f32>{
+    (0..n).map(|_| {
+        sched.step(opt);
+        assert_eq!(opt.lr(), sched.last_lr());
+        opt.lr()
+    }).collect()
+}
+
+fn assert_close(a: &[f32], b: &[f32]){
+    assert_eq!(a.len(), b.len());
+    for (x, y) in a.iter().zip(b){
+        assert!((x - y).abs() <= 1e-6*y.abs().max(1.0), "{a:?}\n{b:?}");
+    }
+}
+
+#[test]
+fn step_lr(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = StepLr::new(&mut opt, 2, 0.5);
+    assert_eq!(run(&mut sched, &mut opt, 6), vec![1.0, 0.5, 0.5, 0.25, 0.25, 0.125]);
+}
+
+#[test]
+fn exponential_lr(){
+    let mut opt = Sgd { lr: 2.0 };
+    let mut sched = ExponentialLr::new(&mut opt, 0.5);
+    assert_eq!(run(&mut sched, &mut opt, 4), vec![1.0, 0.5, 0.25, 0.125]);
+}
+
+#[test]
+fn cosine_warm_restarts(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = CosineAnnealingWarmRestarts::new(&mut opt, 2, 2, 0.1);
+    let cos = |k: f32, t_i: f32| 0.1 + 0.9*(1.0 + (std::f32::consts::PI*k/t_i).cos())/2.0;
+    // cycle de 2 pas, redémarrage au pas 2 sur un cycle de 4, puis de 8 au pas 6
+    let expected = vec![cos(1.0, 2.0), 1.0, cos(1.0, 4.0), 0.55, cos(3.0, 4.0), 1.0, cos(1.0, 8.0)];
+    assert_close(&expected[..2], &[0.55, 1.0]);
+    assert_close(&run(&mut sched, &mut opt, 7), &expected);
+}
+
+#[test]
+fn one_cycle(){
+    let mut opt = Sgd { lr: 123.0 };
+    let mut sched = OneCycleLr::new(&mut opt, 1.0, 10);
+    // le lr de départ est max_lr/25, celui de l'optimiseur est ignoré
+    assert_eq!(opt.lr(), 0.04);
+    let lrs = run(&mut sched, &mut opt, 11);
+    // montée sur 2 pas (0.3*10 - 1), puis descente en cosinus sur les 7 restants, jusqu'à 0.04/1e4
+    let (max, min) = (1.0f32, 0.04f32/1e4);
+    let down = |k: f32| min + (max - min)/2.0*(1.0 + (std::f32::consts::PI*k/7.0).cos());
+    let mut expected = vec![0.52, 1.0];
+    expected.extend((1..=7).map(|k| down(k as f32)));
+    expected.extend([min, min]); // bloqué à la fin du cycle
+    assert_close(&lrs, &expected);
+}
+
+#[test]
+fn reduce_on_plateau(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = ReduceLrOnPlateau::new(&mut opt, 0.5, 1).min_lr(0.2);
+    let metrics = [1.0, 0.9, 0.95, 0.95, 0.95, 0.8, 0.85, 0.85, 0.85, 0.85];
+    let lrs: Vec<f32> = metrics.iter().map(|&m| {
+        sched.observe(m);
+        sched.step(&mut opt);
+        opt.lr()
+    }).collect();
+    assert_eq!(lrs, vec![1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.5, 0.25, 0.25, 0.2]);
+}
+
+#[test]
+fn reduce_on_plateau_step_without_observe(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = ReduceLrOnPlateau::new(&mut opt, 0.5, 0);
+    sched.step(&mut opt);
+    assert_eq!(opt.lr(), 1.0);
+    sched.observe(1.0);
+    sched.step(&mut opt);
+    // la métrique est consommée: les steps suivants sans observe ne comptent pas comme des epochs sans progrès
+    sched.step(&mut opt);
+    sched.step(&mut opt);
+    assert_eq!(opt.lr(), 1.0);
+    sched.observe(1.0);
+    sched.step(&mut opt);
+    assert_eq!(opt.lr(), 0.5);
+}
+
+#[test]
+fn warmup_then(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = LinearWarmup::new(&mut opt, 2, 0.1).then(&mut opt, |o| StepLr::new(o, 1, 0.5));
+    // then ne change pas le lr du warmup, et StepLr part bien du lr de base
+    assert_eq!(opt.lr(), 0.1);
+    assert_eq!(run(&mut sched, &mut opt, 4), vec![0.55, 1.0, 0.5, 0.25]);
+}
+
+#[test]
+fn warmup_then_resume(){
+    let mut opt = Sgd { lr: 1.0 };
+    let mut sched = LinearWarmup::new(&mut opt, 2, 0.1).then(&mut opt, |o| StepLr::new(o, 1, 0.5));
+    run(&mut sched, &mut opt, 3);
+    let state = sched.state();
+    let expected = run(&mut sched, &mut opt, 3);
+
+    let mut opt2 = Sgd { lr: 1.0 };
+    let mut resumed = LinearWarmup::new(&mut opt2, 2, 0.1).then(&mut opt2, |o| StepLr::new(o, 1, 0.5));
+    resumed.load_state(state).unwrap();
+    assert_eq!(run(&mut resumed, &mut opt2, 3), expected);
+}
@@ -0,0 +1,115 @@
+// en no_grad, les ops ne construisent pas leurs règles de dérivation, les valeurs ne changent pas
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::ops::{self, Conv2dOpts};
+use lamp::nn::{functions, losses};
+
+fn build(tr: &mut Trace) -> NodeId{
+    let x = tr.input(Tensor::from_owned((0..32).map(|i| (i as f32*0.37).sin()).collect(), &[2, 1, 4, 4]).unwrap());
+    let w = tr.param(Tensor::from_owned((0..18).map(|i| (i as f32*0.11).cos()).collect(), &[2, 1, 3, 3]).unwrap());
+    let y = ops::conv2d(tr, x, w, Conv2dOpts::default().padding(1, 1));
+    let y = ops::max_pool2d(tr, y, (2, 2), (2, 2), (0, 0));
+    let y = functions::relu(tr, y);
+    let y = ops::flatten(tr, y, 1, 3);
+    let y = functions::softmax(tr, y);
+    let t = tr.input(Tensor::from_owned((0..16).map(|i| if i % 8 == 3 {1.0} else {0.0}).collect(), &[2, 8]).unwrap());
+    let l = losses::softmax_crossentropy(tr, y, t);
+    let m = ops::max(tr, y, &[1], false);
+    let m = ops::sum_all(tr, m);
+    ops::add(tr, l, m)
+}
+
+#[test]
+fn no_grad_drops_rules_keeps_values(){
+    let mut tr = Trace::new();
+    let out = build(&mut tr);
+    let mut ng = Trace::no_grad();
+    let out_ng = build(&mut ng);
+
+    assert_eq!(tr.len(), ng.len());
+    assert_eq!(tr.get_tensor(out).data.to_vec(), ng.get_tensor(out_ng).data.to_vec());
+    for id in 0..ng.len(){
+        let n = ng.node(id);
+        assert!(n.vjp.is_none() && n.vjp_traced.is_none() && n.jvp.is_none(), "noeud {id} ({})", n.op.name());
+    }
+    assert!(tr.node(out).vjp.is_some());
+}
+
+// compte les allocations du thread courant: les tests tournent en parallèle dans ce binaire
+struct Counting;
+
+thread_local!{
+    static ALLOCS: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+unsafe impl GlobalAlloc for Counting{
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8{
+        let _ = ALLOCS.try_with(|a| {
+            let (n, bytes) = a.get();
+            a.set((n + 1, bytes + layout.size()));
+        });
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout){
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+type Op = fn(&mut Trace, NodeId) -> NodeId;
+
+// (nombre d'allocations, octets) pendant l'appel de op, sur une trace avec ou sans gradient
+fn allocs(grad: bool, x: &Tensor, op: Op) -> (usize, usize){
+    let mut tr = if grad {Trace::new()} else {Trace::no_grad()};
+    let x = tr.param(x.clone());
+    ALLOCS.with(|a| a.set((0, 0)));
+    op(&mut tr, x);
+    ALLOCS.with(|a| a.get())
+}
+
+#[test]
+fn no_grad_skips_rule_building(){
+    // 64k éléments, sous PAR_MIN: pas de threads, tout est alloué sur ce thread
+    let x = Tensor::from_owned((0..1 << 16).map(|i| ((i * 37 % 101) as f32 - 50.0) * 0.01).collect(), &[4, 4, 64, 64]).unwrap();
+    let full = x.data.len() * 4;
+
+    // ops dont les règles gardent un intermédiaire de la taille de x (masques, prod des autres, x - mean, softmax...)
+    let heavy: [(&str, Op); 6] = [
+        ("max", |tr, x| ops::max(tr, x, &[3], false)),
+        ("prod", |tr, x| ops::prod(tr, x, &[1], false)),
+        ("var", |tr, x| ops::var(tr, x, &[2, 3], true)),
+        ("std", |tr, x| ops::std(tr, x, &[0], false)),
+        ("logsumexp", |tr, x| ops::logsumexp(tr, x, &[3], false)),
+        ("norm", |tr, x| ops::norm(tr, x, &[], false)),
+    ];
+    for (name, op) in heavy{
+        let (n_grad, b_grad) = allocs(true, &x, op);
+        let (n_ng, b_ng) = allocs(false, &x, op);
+        assert!(n_ng < n_grad, "{name}: {n_ng} allocations sans gradient, {n_grad} avec");
+        assert!(b_ng + full <= b_grad, "{name}: {b_ng} octets sans gradient, {b_grad} avec");
+    }
+
+    // le reste ne fait que cloner des Arc (ou garde ce que le forward calcule déjà): on ne voit que les closures en moins
+    let light: [(&str, Op); 6] = [
+        ("max_pool2d", |tr, x| ops::max_pool2d(tr, x, (2, 2), (2, 2), (0, 0))),
+        ("sum", |tr, x| ops::sum(tr, x, &[1], false)),
+        ("softmax", functions::softmax),
+        ("relu", functions::relu),
+        ("softmax_crossentropy", |tr, x| {
+            let t = tr.input(Tensor::ones(&[4, 4, 64, 64]));
+            losses::softmax_crossentropy(tr, x, t)
+        }),
+        ("l2_reg", |tr, x| losses::l2_reg(tr, 0.1, &[x])),
+    ];
+    for (name, op) in light{
+        let (n_grad, _) = allocs(true, &x, op);
+        let (n_ng, _) = allocs(false, &x, op);
+        assert!(n_ng + 3 <= n_grad, "{name}: {n_ng} allocations sans gradient, {n_grad} avec");
+    }
+}
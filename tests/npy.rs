@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use lamp::tensor::Tensor;
+use lamp::io::npy::{save_npz, load_npz};
+
+fn tmp(name: &str) -> PathBuf{
+    std::env::temp_dir().join(format!("lamp_test_{}_{name}", std::process::id()))
+}
+
+// .npy écrit à la main (v1.0), pour les dtypes et ordres que save_npy n'écrit pas
+fn npy(descr: &str, fortran: bool, shape: &str, payload: &[u8], version: u8) -> Vec<u8>{
+    let mut h = format!("{{'descr': '{descr}', 'fortran_order': {}, 'shape': {shape}, }}\n", if fortran {"True"} else {"False"});
+    let len_size = if version == 1 {2} else {4};
+    while !(8 + len_size + h.len()).is_multiple_of(64){
+        h.insert(h.len() - 1, ' ');
+    }
+    let mut out = b"\x93NUMPY".to_vec();
+    out.extend_from_slice(&[version, 0]);
+    if version == 1 {out.extend_from_slice(&(h.len() as u16).to_le_bytes())} else {out.extend_from_slice(&(h.len() as u32).to_le_bytes())}
+    out.extend_from_slice(h.as_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+fn assert_same(a: &Tensor, b: &Tensor){
+    assert_eq!(a.shape, b.shape);
+    assert_eq!(a.contiguous().data.to_vec(), b.contiguous().data.to_vec());
+}
+
+#[test]
+fn npy_round_trip(){
+    let a = Tensor::from_owned((0..24).map(|i| i as f32 * 0.5 - 3.0).collect(), &[2, 3, 4]).unwrap();
+    for t in [a.clone(), a.mat_transpose(), Tensor::from_vec(&[-7.25], &[]).unwrap(), Tensor::zeros(&[0, 3]), Tensor::ones(&[5])]{
+        let bytes = t.to_npy_bytes();
+        assert_eq!((bytes.len() - 4*t.shape.iter().product::<usize>()) % 64, 0, "header non aligné");
+        assert_same(&Tensor::from_npy_bytes(&bytes).unwrap(), &t);
+    }
+    // la vue transposée est écrite dans son ordre logique
+    let back = Tensor::from_npy_bytes(&a.mat_transpose().to_npy_bytes()).unwrap();
+    assert_eq!(back.shape, vec![2, 4, 3]);
+    assert_eq!(back.data[..4].to_vec(), vec![-3.0, -1.0, 1.0, -2.5]);
+}
+
+#[test]
+fn npy_file(){
+    let t = Tensor::from_vec(&[1.0, 2.0, 3.0, 4.0], &[2, 2]).unwrap();
+    let path = tmp("t.npy");
+    t.mat_transpose().save_npy(&path).unwrap();
+    assert_same(&Tensor::load_npy(&path).unwrap(), &t.mat_transpose());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn npy_dtypes_and_fortran(){
+    // f8 en ordre fortran: (2, 3) de valeurs i*3 + j, écrit colonne par colonne
+    let payload: Vec<u8> = [0.0f64, 3.0, 1.0, 4.0, 2.0, 5.0].iter().flat_map(|x| x.to_le_bytes()).collect();
+    let t = Tensor::from_npy_bytes(&npy("<f8", true, "(2, 3)", &payload, 1)).unwrap();
+    assert_eq!(t.shape, vec![2, 3]);
+    assert_eq!(t.data.to_vec(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+    let payload: Vec<u8> = [-5i64, 0, 1 << 40].iter().flat_map(|x| x.to_le_bytes()).collect();
+    let t = Tensor::from_npy_bytes(&npy("<i8", false, "(3,)", &payload, 1)).unwrap();
+    assert_eq!(t.data.to_vec(), vec![-5.0, 0.0, (1u64 << 40) as f32]);
+
+    let payload: Vec<u8> = [7i32, -1].iter().flat_map(|x| x.to_be_bytes()).collect();
+    assert_eq!(Tensor::from_npy_bytes(&npy(">i4", false, "(2,)", &payload, 2)).unwrap().data.to_vec(), vec![7.0, -1.0]);
+    assert_eq!(Tensor::from_npy_bytes(&npy("|b1", false, "(1, 2)", &[1, 0], 1)).unwrap().data.to_vec(), vec![1.0, 0.0]);
+    assert_eq!(Tensor::from_npy_bytes(&npy("<f2", false, "()", &0x3c00u16.to_le_bytes(), 1)).unwrap().data.to_vec(), vec![1.0]);
+}
+
+#[test]
+fn npy_errors(){
+    assert!(Tensor::from_npy_bytes(b"NOTNPY....").unwrap_err().contains("magic"));
+    assert!(Tensor::from_npy_bytes(&npy("<c8", false, "(1,)", &[0; 8], 1)).unwrap_err().contains("non supporté"));
+    assert!(Tensor::from_npy_bytes(&npy("<f4", false, "(3,)", &[0; 8], 1)).is_err());
+    let mut truncated = npy("<f4", false, "(1,)", &[0; 4], 1);
+    truncated.truncate(20);
+    assert!(Tensor::from_npy_bytes(&truncated).unwrap_err().contains("tronqué"));
+}
+
+#[test]
+fn npz_round_trip(){
+    let a = Tensor::from_owned((0..6).map(|i| i as f32).collect(), &[2, 3]).unwrap();
+    let tensors = vec![("w".to_string(), a.mat_transpose()), ("layer.b".to_string(), Tensor::from_vec(&[2.5], &[]).unwrap())];
+    let path = tmp("round_trip.npz");
+    save_npz(&path, &tensors).unwrap();
+    let back = load_npz(&path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(back.len(), 2);
+    for ((n, t), (n2, t2)) in tensors.iter().zip(&back){
+        assert_eq!(n, n2);
+        assert_same(t, t2);
+    }
+}
+
+// fixture générée par tests/fixtures/make_deflate_npz.py: un membre par type de bloc deflate
+#[test]
+fn npz_deflate_fixture(){
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deflate_blocks.npz");
+    let t = load_npz(path).unwrap();
+    let names: Vec<&str> = t.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(names, vec!["stored", "fixed", "dynamic"]);
+
+    assert_eq!(t[0].1.shape, vec![2, 3]);
+    assert_eq!(t[0].1.data.to_vec(), vec![0.5, -1.0, 2.0, 3.25, 1e10, -0.0]);
+    assert_eq!(t[1].1.data.to_vec(), vec![-3.0, 0.0, 7.0, (1u64 << 40) as f32]);
+    let (r, c) = (16, 24);
+    assert_eq!(t[2].1.shape, vec![r, c]);
+    assert_eq!(t[2].1.data.to_vec(), (0..r*c).map(|k| k as f32).collect::<Vec<_>>());
+}
+
+#[test]
+fn npz_corrupted(){
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/deflate_blocks.npz");
+    let mut bytes = std::fs::read(path).unwrap();
+    // un octet du flux deflate du membre dynamique: soit le flux devient invalide, soit le crc ne correspond plus
+    let i = bytes.windows(11).position(|w| w == b"dynamic.npy").unwrap() + 11 + 200;
+    bytes[i] ^= 0x55;
+    let out = tmp("corrupted.npz");
+    std::fs::write(&out, &bytes).unwrap();
+    let err = load_npz(&out).unwrap_err();
+    std::fs::remove_file(out).unwrap();
+    assert!(err.contains("dynamic.npy"), "{err}");
+}
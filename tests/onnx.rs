@@ -0,0 +1,134 @@
+// export onnx puis réimport: le graphe rejoué doit redonner exactement la meme sortie
+use lamp::tensor::Tensor;
+use lamp::trace::{Trace, NodeId};
+use lamp::ops;
+use lamp::nn::functions;
+use lamp::nn::losses::l2_reg;
+use lamp::nn::module::Module;
+use lamp::nn::layers::sequential::Sequential;
+use lamp::nn::layers::linear::Linear;
+use lamp::nn::layers::activations::Relu;
+use lamp::autodiff::inference::inference;
+use lamp::utils::params::ParamTree;
+use lamp::io::onnx::{export_onnx, import_onnx};
+
+fn bits(t: &Tensor) -> Vec<u32>{
+    t.contiguous().data.iter().map(|x| x.to_bits()).collect()
+}
+
+fn input(shape: &[usize]) -> Tensor{
+    let n: usize = shape.iter().product();
+    Tensor::from_owned((0..n).map(|i| ((i * 7 % 11) as f32 - 5.0) / 4.0).collect(), shape).unwrap()
+}
+
+// mlp, puis transpose/reshape/softmax sur les logits
+fn mlp_head(model: &mut Sequential, tr: &mut Trace, pids: &[NodeId], x: NodeId) -> NodeId{
+    model.bind_params(pids);
+    let logits = model.forward(tr, x);                  // [4, 6]
+    let t = ops::mat_transpose(tr, logits);             // [6, 4]
+    let r = ops::reshape(tr, t, &[3, 8]);
+    let p = ops::permute(tr, r, &[1, 0]);               // [8, 3]
+    functions::softmax(tr, p)
+}
+
+#[test]
+fn mlp_round_trip(){
+    let mut model = Sequential::new().layer(Linear::new(5, 7)).layer(Relu).layer(Linear::new(7, 6));
+    let params = model.init_tree();
+    let x = input(&[4, 5]);
+
+    let expected = inference(&params, |tr, pids| {
+        let xid = tr.input(x.clone());
+        mlp_head(&mut model, tr, pids, xid)
+    });
+
+    let bytes = export_onnx(&params, std::slice::from_ref(&x), |tr, pids, xs| mlp_head(&mut model, tr, pids, xs[0])).unwrap();
+    let onnx = import_onnx(&bytes).unwrap();
+    assert_eq!(onnx.inputs, vec!["input0"]);
+    assert_eq!(onnx.outputs, vec!["output0"]);
+
+    // memes params, sous les memes noms
+    let mut names = params.paths();
+    let mut imported = onnx.params.paths();
+    names.sort();
+    imported.sort();
+    assert_eq!(names, imported);
+    for (name, t) in params.named_leaves(){
+        assert_eq!(bits(onnx.params.get(&name).unwrap()), bits(&t), "{name}");
+    }
+
+    let got = inference(&onnx.params, |tr, pids| {
+        let xid = tr.input(x.clone());
+        onnx.forward(tr, pids, &[xid]).unwrap()[0]
+    });
+    assert_eq!(got.shape, vec![8, 3]);
+    assert_eq!(bits(&got), bits(&expected));
+}
+
+#[test]
+fn export_lists_unsupported_ops(){
+    let mut model = Sequential::new().layer(Linear::new(3, 2));
+    let params = model.init_tree();
+    let err = export_onnx(&params, &[input(&[2, 3])], |tr, pids, xs| {
+        model.bind_params(pids);
+        let y = model.forward(tr, xs[0]);
+        let y = functions::apply(tr, y, |v| v.sin(), |v| v.cos());
+        let loss = ops::sum_all(tr, y);
+        let reg = l2_reg(tr, 0.1, pids);
+        ops::add(tr, loss, reg)
+    }).unwrap_err();
+    assert!(err.contains("Map") && err.contains("L2Reg"), "{err}");
+    // les ops exportables ne sont pas listées
+    assert!(!err.contains("MatMul") && !err.contains("SumAll"), "{err}");
+}
+
+#[test]
+fn reductions_round_trip(){
+    let params = vec![Tensor::from_owned((0..24).map(|i| ((i * 5 % 7) as f32 - 3.0) * 0.5).collect(), &[2, 3, 4]).unwrap()];
+    let build = |tr: &mut Trace, pids: &[NodeId], xs: &[NodeId]| {
+        let y = ops::hadamard_mul(tr, xs[0], pids[0]);
+        let s = ops::sum(tr, y, &[1], true);                // [2, 1, 4]
+        let m = ops::mean(tr, y, &[0, 2], false);           // [3]
+        let mx = ops::max(tr, s, &[2], false);              // [2, 1]
+        let mx = ops::reshape(tr, mx, &[2]);
+        let a = ops::unsqueeze(tr, m, 0);                   // [1, 3]
+        let b = ops::unsqueeze(tr, mx, 1);                  // [2, 1]
+        ops::add(tr, a, b)
+    };
+    let x = input(&[2, 3, 4]);
+    let expected = inference(&params, |tr, pids| {
+        let xid = tr.input(x.clone());
+        build(tr, pids, &[xid])
+    });
+
+    let tree = ParamTree::from_named(vec![("w".to_string(), params[0].clone())]).unwrap();
+    let bytes = export_onnx(&tree, std::slice::from_ref(&x), build).unwrap();
+    let onnx = import_onnx(&bytes).unwrap();
+    let got = inference(&onnx.params, |tr, pids| {
+        let xid = tr.input(x.clone());
+        onnx.forward(tr, pids, &[xid]).unwrap()[0]
+    });
+    assert_eq!(got.shape, vec![2, 3]);
+    assert_eq!(bits(&got), bits(&expected));
+}
+
+#[test]
+fn unused_input_and_param_are_kept(){
+    // seul p0 et input0 servent à la sortie
+    let params = ParamTree::from_named(vec![("p0".to_string(), input(&[3])), ("p1".to_string(), input(&[2, 2]))]).unwrap();
+    let xs = [input(&[3]), input(&[5])];
+    let bytes = export_onnx(&params, &xs, |tr, pids, xs| ops::hadamard_mul(tr, pids[0], xs[0])).unwrap();
+    let onnx = import_onnx(&bytes).unwrap();
+    assert_eq!(onnx.inputs, vec!["input0", "input1"]);
+    let mut names = onnx.params.paths();
+    names.sort();
+    assert_eq!(names, vec!["p0", "p1"]);
+    assert_eq!(bits(onnx.params.get("p1").unwrap()), bits(&input(&[2, 2])));
+
+    let got = inference(&onnx.params, |tr, pids| {
+        let ids: Vec<NodeId> = xs.iter().map(|x| tr.input(x.clone())).collect();
+        onnx.forward(tr, pids, &ids).unwrap()[0]
+    });
+    let want: Vec<f32> = input(&[3]).data.iter().map(|v| v * v).collect();
+    assert_eq!(*got.contiguous().data, want);
+}
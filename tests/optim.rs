@@ -0,0 +1,122 @@
+// état des optimiseurs: un buffer doit toujours avoir la shape du param qu'il suit
+use lamp::tensor::Tensor;
+use lamp::optim::{Optimizer, OptimState};
+use lamp::optim::adam::{Adam, AdamW};
+use lamp::optim::sgd::SgdMomentum;
+use lamp::optim::rmsprop::RmsProp;
+use lamp::optim::adagrad::Adagrad;
+
+fn half_state(names: &[&str]) -> OptimState{
+    OptimState { step: 3, buffers: names.iter().map(|n| (n.to_string(), vec![Tensor::ones(&[2])])).collect() }
+}
+
+#[test]
+fn load_state_checked_rejects_wrong_shapes(){
+    let params = vec![Tensor::ones(&[4])];
+    let cases: Vec<(Box<dyn Optimizer>, &[&str])> = vec![
+        (Box::new(Adam::new(0.1)), &["m", "v"]),
+        (Box::new(SgdMomentum::new(0.1, 0.9)), &["velocity"]),
+        (Box::new(RmsProp::new(0.1)), &["sq_avg"]),
+        (Box::new(Adagrad::new(0.1)), &["sum_sq"]),
+    ];
+    for (mut opt, names) in cases{
+        let err = opt.load_state_checked(half_state(names), &params).unwrap_err();
+        assert!(err.contains("shape"), "{err}");
+    }
+}
+
+#[test]
+fn load_state_checked_accepts_own_state(){
+    let mut params = vec![Tensor::ones(&[4]), Tensor::ones(&[2, 3])];
+    let grads = vec![Tensor::ones(&[4]), Tensor::ones(&[2, 3])];
+    let mut opt = Adam::new(0.1);
+    opt.update_(&mut params, &grads);
+    let mut resumed = Adam::new(0.1);
+    resumed.load_state_checked(opt.state(), &params).unwrap();
+    // optimiseur jamais mis à jour: buffers vides, rien à vérifier
+    resumed.load_state_checked(Adam::new(0.1).state(), &params).unwrap();
+}
+
+#[test]
+fn adam_moments_of_different_shapes(){
+    let state = OptimState { step: 1, buffers: vec![("m".into(), vec![Tensor::ones(&[4])]), ("v".into(), vec![Tensor::ones(&[2])])] };
+    assert!(Adam::new(0.1).load_state(state).is_err());
+}
+
+#[test]
+#[should_panic(expected = "n'a pas la shape de son param")]
+fn update_after_unchecked_load_panics(){
+    let mut opt = Adam::new(0.1);
+    opt.load_state(half_state(&["m", "v"])).unwrap();
+    opt.update_(&mut [Tensor::ones(&[4])], &[Tensor::ones(&[4])]);
+}
+
+#[test]
+#[should_panic(expected = "shape du gradient")]
+fn grad_shape_mismatch_panics(){
+    // (1) se broadcasterait vers (4): il faut le refuser plutot que l'étaler
+    RmsProp::new(0.1).update_(&mut [Tensor::ones(&[4])], &[Tensor::ones(&[1])]);
+}
+
+// p = 1, grads imposés à chaque pas, renvoie p après chaque pas
+fn run(opt: &mut dyn Optimizer, grads: &[f32]) -> Vec<f32>{
+    let mut params = vec![Tensor::from_vec(&[1.0], &[1]).unwrap()];
+    grads.iter().map(|&g| {
+        opt.update_(&mut params, &[Tensor::from_vec(&[g], &[1]).unwrap()]);
+        params[0].data[0]
+    }).collect()
+}
+
+fn assert_close(got: &[f32], want: &[f32]){
+    assert_eq!(got.len(), want.len());
+    for (g, w) in got.iter().zip(want){
+        assert!((g - w).abs() < 1e-6, "{got:?} != {want:?}");
+    }
+}
+
+// betas (0.5, 0.75), eps 0, lr 0.1, grads 2, -1, 0.5: calculs à la main
+// pas 1: m = 1, v = 1, m_hat = 2, v_hat = 4 -> p -= 0.1 * 2/2 (la correction de biais donne lr*signe(g))
+// pas 2: m = 0 -> p ne bouge pas
+// pas 3: m = 0.25, v = 0.8125, m_hat = 0.25/0.875, v_hat = 0.8125/0.578125
+#[test]
+fn adam_steps_match_hand_computed(){
+    let mut opt = Adam::new(0.1).betas(0.5, 0.75).eps(0.0);
+    assert_close(&run(&mut opt, &[2.0, -1.0, 0.5]), &[0.9, 0.9, 0.875_899_2]);
+}
+
+// L2: g' = g + 0.5 p entre dans m et v
+// pas 1: g' = 2.5 -> encore lr*signe(g') = 0.9
+// pas 2: g' = -1 + 0.45 = -0.55, m = 0.35, v = 1.2475 -> p = 0.9 - 0.1 * (0.35/0.75)/sqrt(1.2475/0.4375)
+#[test]
+fn adam_weight_decay_is_l2(){
+    let mut opt = Adam::new(0.1).betas(0.5, 0.75).eps(0.0).weight_decay(0.5);
+    assert_close(&run(&mut opt, &[2.0, -1.0, 0.5]), &[0.9, 0.872_364, 0.820_360_3]);
+}
+
+// decay découplé: m et v comme sans decay, p -= lr * (m_hat/sqrt(v_hat) + 0.5 p)
+// pas 1: 1 - 0.1 * (1 + 0.5) = 0.85
+// pas 2: m = 0 -> 0.85 - 0.1 * 0.5 * 0.85 = 0.8075
+#[test]
+fn adamw_weight_decay_is_decoupled(){
+    let mut opt = AdamW::new(0.1).betas(0.5, 0.75).eps(0.0).weight_decay(0.5);
+    assert_close(&run(&mut opt, &[2.0, -1.0, 0.5]), &[0.85, 0.8075, 0.743_024_2]);
+}
+
+#[test]
+fn adamw_default_weight_decay(){
+    let mut opt = AdamW::new(0.1);
+    assert_eq!(opt.weight_decay, 1e-2);
+    // premier pas: lr*signe(g) + lr * 1e-2 * p
+    assert_close(&run(&mut opt, &[2.0, -1.0]), &[0.899, 0.871_467_3]);
+}
+
+// lr 0.1, momentum 0.9, grads 1, 1, -2
+// b: 1, 1.9, -0.29
+// classique:  p -= lr * b           -> 0.9, 0.71, 0.739
+// nesterov:   p -= lr * (g + 0.9 b) -> 0.81, 0.539, 0.7651
+#[test]
+fn sgd_momentum_nesterov_steps(){
+    let grads = [1.0, 1.0, -2.0];
+    assert_close(&run(&mut SgdMomentum::new(0.1, 0.9), &grads), &[0.9, 0.71, 0.739]);
+    assert_close(&run(&mut SgdMomentum::new(0.1, 0.9).nesterov(true), &grads), &[0.81, 0.539, 0.7651]);
+}
@@ -0,0 +1,89 @@
+use lamp::tensor::Tensor;
+use lamp::io::safetensors::{serialize, deserialize};
+
+// fichier écrit à la main: u64 taille du header | header | buffers
+fn raw(header: &str, body: &[u8]) -> Vec<u8>{
+    let mut out = (header.len() as u64).to_le_bytes().to_vec();
+    out.extend_from_slice(header.as_bytes());
+    out.extend_from_slice(body);
+    out
+}
+
+#[test]
+fn round_trip(){
+    let a = Tensor::from_owned((0..6).map(|i| i as f32 - 2.5).collect(), &[2, 3]).unwrap();
+    let scalar = Tensor::from_vec(&[f32::INFINITY], &[]).unwrap();
+    let tensors = vec![
+        ("w \"quoted\"\\path".to_string(), a.mat_transpose()), // une vue: écrite dans l'ordre logique
+        ("layer0.b".to_string(), scalar.clone()),
+        ("empty".to_string(), Tensor::zeros(&[0, 4])),
+    ];
+    let meta = vec![("format".to_string(), "pt".to_string()), ("note".to_string(), "é\n\"x\"".to_string())];
+    let bytes = serialize(&tensors, &meta).unwrap();
+    // les buffers commencent sur un multiple de 8
+    assert_eq!(u64::from_le_bytes(bytes[..8].try_into().unwrap()) % 8, 0);
+
+    let (back, meta_back) = deserialize(&bytes).unwrap();
+    assert_eq!(meta_back, meta);
+    assert_eq!(back.len(), 3);
+    for ((n, t), (n2, t2)) in tensors.iter().zip(&back){
+        assert_eq!(n, n2);
+        assert_eq!(t.shape, t2.shape);
+        assert_eq!(t.contiguous().data.to_vec(), t2.data.to_vec());
+    }
+}
+
+#[test]
+fn duplicate_names(){
+    let t = Tensor::ones(&[1]);
+    assert!(serialize(&[("a".into(), t.clone()), ("a".into(), t.clone())], &[]).is_err());
+    assert!(serialize(&[("__metadata__".into(), t)], &[]).is_err());
+}
+
+#[test]
+fn decode_f16_bf16_f64(){
+    let mut body = Vec::new();
+    for h in [0x3c00u16, 0xc000, 0x0001, 0x7c00, 0x8000]{
+        body.extend_from_slice(&h.to_le_bytes());
+    }
+    for h in [0x3f80u16, 0xc040]{
+        body.extend_from_slice(&h.to_le_bytes());
+    }
+    body.extend_from_slice(&0.5f64.to_le_bytes());
+    let header = r#"{"h":{"dtype":"F16","shape":[5],"data_offsets":[0,10]},"bf":{"dtype":"BF16","shape":[2,1],"data_offsets":[10,14]},"d":{"dtype":"F64","shape":[],"data_offsets":[14,22]}}"#;
+    let (t, meta) = deserialize(&raw(header, &body)).unwrap();
+    assert!(meta.is_empty());
+    assert_eq!(t[0].1.data.to_vec(), vec![1.0, -2.0, 2f32.powi(-24), f32::INFINITY, -0.0]);
+    assert_eq!(t[1].1.data.to_vec(), vec![1.0, -3.0]);
+    assert_eq!(t[1].1.shape, vec![2, 1]);
+    assert_eq!(t[2].1.data.to_vec(), vec![0.5]);
+}
+
+fn err(bytes: &[u8]) -> String{
+    deserialize(bytes).unwrap_err()
+}
+
+#[test]
+fn errors(){
+    assert!(err(&[1, 2, 3]).contains("trop court"));
+    // header annoncé plus long que le fichier
+    let mut truncated = raw(r#"{"a":{"dtype":"F32","shape":[1],"data_offsets":[0,4]}}"#, &[0; 4]);
+    truncated.truncate(20);
+    assert!(err(&truncated).contains("header plus long"));
+
+    let out_of_range = raw(r#"{"a":{"dtype":"F32","shape":[2],"data_offsets":[0,8]}}"#, &[0; 4]);
+    assert!(err(&out_of_range).contains("hors du fichier"));
+    let reversed = raw(r#"{"a":{"dtype":"F32","shape":[0],"data_offsets":[4,0]}}"#, &[0; 4]);
+    assert!(err(&reversed).contains("hors du fichier"));
+
+    let mismatch = raw(r#"{"a":{"dtype":"F32","shape":[3],"data_offsets":[0,8]}}"#, &[0; 8]);
+    assert!(err(&mismatch).contains("3 attendues"), "{}", err(&mismatch));
+    let ragged = raw(r#"{"a":{"dtype":"F32","shape":[1],"data_offsets":[0,3]}}"#, &[0; 4]);
+    assert!(err(&ragged).contains("multiple de 4"));
+
+    assert!(err(&raw(r#"{"a":{"dtype":"I8","shape":[1],"data_offsets":[0,1]}}"#, &[0])).contains("non supporté"));
+    assert!(err(&raw(r#"{"a":{"dtype":"F32","shape":[1]}}"#, &[0; 4])).contains("data_offsets"));
+    assert!(err(&raw(r#"{"__metadata__":{"k":1}}"#, &[])).contains("non textuelle"));
+    assert!(err(&raw("[1, 2]", &[])).contains("pas un objet"));
+    assert!(err(&raw("{\"a\":", &[])).contains("json"));
+}
@@ -0,0 +1,76 @@
+// ops élément par élément et réductions au dela de PAR_MIN, découpées en morceaux sur plusieurs threads:
+// les morceaux (PAR_CHUNK) tombent au milieu des lignes, le résultat doit suivre la définition (get_from_lin)
+use std::sync::Arc;
+
+use lamp::tensor::Tensor;
+use lamp::utils::parallel::set_num_threads;
+
+// (97, 3, 461) = 134151 éléments > PAR_MIN, et 461 ne divise pas PAR_CHUNK
+const SHAPE: [usize; 3] = [97, 3, 461];
+
+fn buffer(n: usize) -> Arc<Vec<f32>>{
+    Arc::new((0..n).map(|i| ((i * 7919) % 1013) as f32 * 0.25).collect())
+}
+
+fn values(t: &Tensor) -> Vec<f32>{
+    (0..t.shape.iter().product()).map(|i| t.get_from_lin(i)).collect()
+}
+
+// transposée, sous vue décalée (buffer plus grand que la vue), broadcast
+fn views() -> Vec<Tensor>{
+    let [a, b, c] = SHAPE;
+    vec![
+        Tensor::new(buffer(a*b*c), &[c, b, a], 0).permute(&[2, 1, 0]),
+        Tensor { data: buffer(a*b*(c+5) + 11), shape: SHAPE.to_vec(), strides: vec![b*(c+5), c+5, 1], offset: 11 },
+        Tensor::new(buffer(b*c), &[1, b, c], 0).broadcast_view(&SHAPE).unwrap(),
+        Tensor::new(buffer(a), &[a, 1, 1], 0).broadcast_view(&SHAPE).unwrap(),
+    ]
+}
+
+#[test]
+fn gather_and_map(){
+    // 4 threads, meme sur une machine à 1 coeur
+    set_num_threads(4);
+    for v in views(){
+        let expected = values(&v);
+        assert_eq!(v.contiguous().data.as_slice(), expected.as_slice(), "{:?}", v.strides);
+        let y = v.apply(|x| 2.0*x - 1.0);
+        assert_eq!(values(&y), expected.iter().map(|x| 2.0*x - 1.0).collect::<Vec<_>>(), "{:?}", v.strides);
+    }
+}
+
+#[test]
+fn zip_map_and_in_place(){
+    set_num_threads(4);
+    let vs = views();
+    for a in &vs{
+        for b in &vs{
+            let (va, vb) = (values(a), values(b));
+            let y = a - b;
+            assert_eq!(y.data.as_slice(), va.iter().zip(&vb).map(|(x, y)| x - y).collect::<Vec<_>>().as_slice());
+        }
+        // b plus petit, broadcasté vers a
+        let row = Tensor::new(buffer(SHAPE[2]), &[SHAPE[2]], 0);
+        let mut c = a.contiguous();
+        c.add_(&row);
+        let vr = values(&row);
+        let expected: Vec<f32> = values(a).iter().enumerate().map(|(i, x)| x + vr[i % SHAPE[2]]).collect();
+        assert_eq!(c.data.as_slice(), expected.as_slice());
+    }
+}
+
+#[test]
+fn reductions(){
+    set_num_threads(4);
+    let [a, b, c] = SHAPE;
+    for v in views(){
+        let x = values(&v);
+        let at = |i: usize, j: usize, k: usize| x[(i*b + j)*c + k];
+        let s = v.sum(&[1], false);
+        let expected: Vec<f32> = (0..a).flat_map(|i| (0..c).map(move |k| (i, k))).map(|(i, k)| (0..b).map(|j| at(i, j, k)).sum()).collect();
+        assert_eq!(s.data.as_slice(), expected.as_slice(), "{:?}", v.strides);
+        let m = v.max(&[0, 2], false);
+        let expected: Vec<f32> = (0..b).map(|j| (0..a).flat_map(|i| (0..c).map(move |k| (i, k))).map(|(i, k)| at(i, j, k)).fold(f32::NEG_INFINITY, f32::max)).collect();
+        assert_eq!(m.data.as_slice(), expected.as_slice(), "{:?}", v.strides);
+    }
+}